@@ -0,0 +1,677 @@
+//! Typed Graphviz attributes.
+//!
+//! [`Attr`] covers the commonly used part of the
+//! [attribute catalogue](https://www.graphviz.org/doc/info/attrs.html), with
+//! a value type for each attribute. Values are escaped when rendered, so
+//! they may contain spaces, quotes and other special characters.
+//! Attributes that are not in the catalogue can be given with
+//! [`Attr::Custom`].
+
+use std::borrow::Cow;
+
+use crate::types::is_balanced_html;
+use crate::{Arrow, Color, Id, RankDir, Shape, Styles, Text};
+
+/// Conversion between attribute values and their DOT representation.
+pub(crate) trait AttrValue<'a>: Sized {
+    type Owned;
+
+    /// Renders the value as it appears after `=` in a .dot file.
+    fn to_dot_string(&self) -> String;
+
+    /// Interprets the DOT value `text`, if it is valid for this type.
+    fn from_text(text: &Text<'a>) -> Option<Self>;
+
+    /// Checks that Graphviz accepts the value, returning the problem if not.
+    fn check(&self) -> Result<(), &'static str> {
+        Ok(())
+    }
+
+    fn into_owned(self) -> Self::Owned;
+}
+
+impl<'a> AttrValue<'a> for Text<'a> {
+    type Owned = Text<'static>;
+
+    fn to_dot_string(&self) -> String {
+        self.to_escaped_string()
+    }
+
+    fn from_text(text: &Text<'a>) -> Option<Self> {
+        Some(text.clone())
+    }
+
+    fn check(&self) -> Result<(), &'static str> {
+        match self {
+            Text::Html(s) if !is_balanced_html(s) => Err("has unbalanced angle brackets"),
+            _ => Ok(()),
+        }
+    }
+
+    fn into_owned(self) -> Text<'static> {
+        Text::into_owned(self)
+    }
+}
+
+impl<'a> AttrValue<'a> for Id<'a> {
+    type Owned = Id<'static>;
+
+    fn to_dot_string(&self) -> String {
+        self.to_string()
+    }
+
+    fn from_text(text: &Text<'a>) -> Option<Self> {
+        match text {
+            Text::Html(s) => Id::html(s.clone()).ok(),
+            Text::Label(s) | Text::Esc(s) => Id::quoted(s.clone()).ok(),
+        }
+    }
+
+    fn into_owned(self) -> Id<'static> {
+        Id::into_owned(self)
+    }
+}
+
+/// Implements [`AttrValue`] for types that are parsed from the text of the
+/// value with `$parse`, and rendered unquoted.
+macro_rules! impl_attr_value_parsed {
+    ( $( $ty:ty => $parse:expr ),+ $(,)? ) => {
+        $(
+            impl AttrValue<'_> for $ty {
+                type Owned = $ty;
+
+                fn to_dot_string(&self) -> String {
+                    self.to_string()
+                }
+
+                fn from_text(text: &Text<'_>) -> Option<Self> {
+                    match text {
+                        Text::Label(s) | Text::Esc(s) => $parse(s.trim()),
+                        Text::Html(_) => None,
+                    }
+                }
+
+                fn into_owned(self) -> Self {
+                    self
+                }
+            }
+        )+
+    };
+}
+
+impl_attr_value_parsed! {
+    u32 => |s: &str| s.parse().ok(),
+    bool => |s: &str| match s.to_ascii_lowercase().as_str() {
+        "true" | "yes" => Some(true),
+        "false" | "no" => Some(false),
+        s => s.parse::<i64>().ok().map(|n| n != 0),
+    },
+}
+
+/// Implements [`AttrValue`] for enums with an `as_static_str` method, which
+/// are rendered quoted.
+macro_rules! impl_attr_value_enum {
+    ( $( $ty:ident { $($variant:ident)+ } )+ ) => {
+        $(
+            impl AttrValue<'_> for $ty {
+                type Owned = $ty;
+
+                fn to_dot_string(&self) -> String {
+                    format!("\"{}\"", self.as_static_str())
+                }
+
+                fn from_text(text: &Text<'_>) -> Option<Self> {
+                    let s = match text {
+                        Text::Label(s) | Text::Esc(s) => s.trim(),
+                        Text::Html(_) => return None,
+                    };
+                    [$($ty::$variant),+]
+                        .into_iter()
+                        .find(|v| v.as_static_str().eq_ignore_ascii_case(s))
+                }
+
+                fn into_owned(self) -> Self {
+                    self
+                }
+            }
+        )+
+    };
+}
+
+impl_attr_value_enum! {
+    RankDir { TopBottom LeftRight BottomTop RightLeft }
+    Dir { Forward Back Both None }
+    Rank { Same Min Source Max Sink }
+    Splines { None Line Polyline Curved Ortho Spline }
+}
+
+impl AttrValue<'_> for f64 {
+    type Owned = f64;
+
+    fn to_dot_string(&self) -> String {
+        self.to_string()
+    }
+
+    fn from_text(text: &Text<'_>) -> Option<Self> {
+        match text {
+            Text::Label(s) | Text::Esc(s) => s.trim().parse().ok().filter(|v: &f64| v.is_finite()),
+            Text::Html(_) => None,
+        }
+    }
+
+    fn check(&self) -> Result<(), &'static str> {
+        match self.is_finite() {
+            true => Ok(()),
+            false => Err("is not a finite number"),
+        }
+    }
+
+    fn into_owned(self) -> Self {
+        self
+    }
+}
+
+impl<'a> AttrValue<'a> for Color<'a> {
+    type Owned = Color<'static>;
+
+    fn to_dot_string(&self) -> String {
+        format!("\"{}\"", Text::escape_str(&self.to_string()))
+    }
+
+    fn from_text(text: &Text<'a>) -> Option<Self> {
+        match text {
+            Text::Label(s) | Text::Esc(s) => s.parse().ok(),
+            Text::Html(_) => None,
+        }
+    }
+
+    fn check(&self) -> Result<(), &'static str> {
+        Color::check(self)
+    }
+
+    fn into_owned(self) -> Color<'static> {
+        Color::into_owned(self)
+    }
+}
+
+impl AttrValue<'_> for Styles {
+    type Owned = Styles;
+
+    fn to_dot_string(&self) -> String {
+        format!("\"{self}\"")
+    }
+
+    fn from_text(text: &Text<'_>) -> Option<Self> {
+        match text {
+            Text::Label(s) | Text::Esc(s) => s.parse().ok(),
+            Text::Html(_) => None,
+        }
+    }
+
+    fn check(&self) -> Result<(), &'static str> {
+        match self.line_width() {
+            Some(width) if !width.is_finite() => {
+                Err("has a line width that is not a finite number")
+            }
+            Some(width) if width < 0.0 => Err("has a negative line width"),
+            _ => Ok(()),
+        }
+    }
+
+    fn into_owned(self) -> Self {
+        self
+    }
+}
+
+impl AttrValue<'_> for Shape {
+    type Owned = Shape;
+
+    fn to_dot_string(&self) -> String {
+        format!("\"{}\"", self.name())
+    }
+
+    fn from_text(text: &Text<'_>) -> Option<Self> {
+        match text {
+            Text::Label(s) | Text::Esc(s) => s.trim().parse().ok(),
+            Text::Html(_) => None,
+        }
+    }
+
+    fn check(&self) -> Result<(), &'static str> {
+        match self {
+            Shape::Polygon(polygon) if polygon.sides < 3 => Err("has fewer than three sides"),
+            Shape::Polygon(polygon)
+                if !polygon.skew.is_finite() || !polygon.distortion.is_finite() =>
+            {
+                Err("has a skew or distortion that is not a finite number")
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn into_owned(self) -> Self {
+        self
+    }
+}
+
+impl AttrValue<'_> for Arrow {
+    type Owned = Arrow;
+
+    fn to_dot_string(&self) -> String {
+        format!("\"{}\"", Arrow::to_dot_string(self))
+    }
+
+    fn from_text(text: &Text<'_>) -> Option<Self> {
+        match text {
+            Text::Label(s) | Text::Esc(s) => s.trim().parse().ok(),
+            Text::Html(_) => None,
+        }
+    }
+
+    fn check(&self) -> Result<(), &'static str> {
+        match self.arrows.len() {
+            0..=4 => Ok(()),
+            _ => Err("has more than four arrow shapes"),
+        }
+    }
+
+    fn into_owned(self) -> Self {
+        self
+    }
+}
+
+/// Defines [`Attr`] from a table of variants, value types and attribute
+/// names.
+macro_rules! define_attrs {
+    ( $( $(#[$meta:meta])* $variant:ident($ty:ty) = $name:literal, )+ ) => {
+        /// A Graphviz attribute together with its value.
+        ///
+        /// See <https://www.graphviz.org/doc/info/attrs.html> for which
+        /// attributes apply to graphs, clusters, nodes and edges.
+        #[derive(Clone, Debug, PartialEq)]
+        pub enum Attr<'a> {
+            $( $(#[$meta])* $variant($ty), )+
+            /// Any other attribute, given by name.
+            Custom(Id<'a>, Text<'a>),
+        }
+
+        impl<'a> Attr<'a> {
+            /// The name of the attribute, such as `"penwidth"`.
+            pub fn name(&self) -> &str {
+                match self {
+                    $( Attr::$variant(_) => $name, )+
+                    Attr::Custom(name, _) => name,
+                }
+            }
+
+            /// Renders the value as it appears after `=` in a .dot file,
+            /// including quotes or other delimiters where needed.
+            pub fn value_to_string(&self) -> String {
+                match self {
+                    $( Attr::$variant(value) => AttrValue::to_dot_string(value), )+
+                    Attr::Custom(_, value) => value.to_escaped_string(),
+                }
+            }
+
+            /// Interprets the attribute `name` with the DOT value `value`.
+            ///
+            /// Falls back to [`Attr::Custom`] if the attribute is not in the
+            /// catalogue, or if `value` is not valid for it.
+            pub fn parse(name: &str, value: Text<'a>) -> Attr<'a> {
+                match name {
+                    $(
+                        $name => {
+                            if let Some(value) = AttrValue::from_text(&value) {
+                                return Attr::$variant(value);
+                            }
+                        }
+                    )+
+                    _ => {}
+                }
+                let name = Id::new(Cow::Owned(name.to_owned()))
+                    .or_else(|_| Id::quoted(name.replace('\0', "")))
+                    .or_else(|_| Id::quoted(name.replace(['\0', '\\'], "")))
+                    .unwrap();
+                Attr::Custom(name, value)
+            }
+
+            /// Checks that Graphviz accepts the value of the attribute,
+            /// returning a description of the problem if it does not.
+            pub fn check(&self) -> Result<(), &'static str> {
+                match self {
+                    $( Attr::$variant(value) => AttrValue::check(value)?, )+
+                    Attr::Custom(_, value) => AttrValue::check(value)?,
+                }
+                match self {
+                    Attr::ArrowSize(v)
+                    | Attr::FontSize(v)
+                    | Attr::Height(v)
+                    | Attr::LabelDistance(v)
+                    | Attr::Margin(v)
+                    | Attr::NodeSep(v)
+                    | Attr::PenWidth(v)
+                    | Attr::RankSep(v)
+                    | Attr::Weight(v)
+                    | Attr::Width(v)
+                        if *v < 0.0 =>
+                    {
+                        Err("is negative")
+                    }
+                    Attr::FontColor(Color::List(_)) | Attr::PenColor(Color::List(_)) => {
+                        Err("cannot be a color list")
+                    }
+                    _ => Ok(()),
+                }
+            }
+
+            /// Converts the attribute into one that owns its value.
+            pub fn into_owned(self) -> Attr<'static> {
+                match self {
+                    $( Attr::$variant(value) => Attr::$variant(AttrValue::into_owned(value)), )+
+                    Attr::Custom(name, value) => Attr::Custom(name.into_owned(), value.into_owned()),
+                }
+            }
+        }
+    };
+}
+
+define_attrs! {
+    /// Scaling factor for the size of arrowheads.
+    ArrowSize(f64) = "arrowsize",
+    ArrowHead(Arrow) = "arrowhead",
+    ArrowTail(Arrow) = "arrowtail",
+    /// Canvas background color.
+    BgColor(Color<'a>) = "bgcolor",
+    /// Whether to center the drawing on the page.
+    Center(bool) = "center",
+    /// Basic drawing color for graphics, not text.
+    Color(Color<'a>) = "color",
+    Comment(Text<'a>) = "comment",
+    /// Allow edges between clusters, see `LHead` and `LTail`.
+    Compound(bool) = "compound",
+    /// Merge multiedges into a single edge.
+    Concentrate(bool) = "concentrate",
+    /// Whether the edge is used in ranking the nodes.
+    Constraint(bool) = "constraint",
+    /// Connect the edge label to the edge with a line.
+    Decorate(bool) = "decorate",
+    /// Which ends of the edge have arrowheads.
+    Dir(Dir) = "dir",
+    /// Distortion factor of `polygon` shapes.
+    Distortion(f64) = "distortion",
+    /// Color used to fill nodes and clusters.
+    FillColor(Color<'a>) = "fillcolor",
+    /// Whether `Width` and `Height` are fixed, rather than minimums.
+    FixedSize(bool) = "fixedsize",
+    FontColor(Color<'a>) = "fontcolor",
+    FontName(Text<'a>) = "fontname",
+    /// Font size in points.
+    FontSize(f64) = "fontsize",
+    /// Nodes in the same group are kept in a straight line where possible.
+    Group(Text<'a>) = "group",
+    /// Label near the head of an edge.
+    HeadLabel(Text<'a>) = "headlabel",
+    /// Height of a node in inches.
+    Height(f64) = "height",
+    /// Synonym for `Url`.
+    Href(Text<'a>) = "href",
+    /// Image file to display inside a node.
+    Image(Text<'a>) = "image",
+    Label(Text<'a>) = "label",
+    /// Angle in degrees of head and tail labels from the edge.
+    LabelAngle(f64) = "labelangle",
+    /// Scaling factor for the distance of head and tail labels from the edge.
+    LabelDistance(f64) = "labeldistance",
+    /// Allow edge labels to be less constrained in position.
+    LabelFloat(bool) = "labelfloat",
+    /// Justification of graph and cluster labels: `l`, `r` or centered.
+    LabelJust(Text<'a>) = "labeljust",
+    /// Vertical placement of labels: `t`, `b` or centered.
+    LabelLoc(Text<'a>) = "labelloc",
+    /// Cluster that the head of an edge is clipped to.
+    LHead(Id<'a>) = "lhead",
+    /// Cluster that the tail of an edge is clipped to.
+    LTail(Id<'a>) = "ltail",
+    /// Margin around a node's label, or the drawing, in inches.
+    Margin(f64) = "margin",
+    /// Minimum rank distance between the head and tail of an edge.
+    MinLen(u32) = "minlen",
+    /// Use the newer ranking algorithm, which allows `rank` in clusters.
+    NewRank(bool) = "newrank",
+    /// Minimum space between adjacent nodes in the same rank, in inches.
+    NodeSep(f64) = "nodesep",
+    /// Rotation of node shapes in degrees.
+    Orientation(f64) = "orientation",
+    /// Color used to draw the bounding box of a cluster.
+    PenColor(Color<'a>) = "pencolor",
+    /// Width of the pen in points, used for lines and curves.
+    PenWidth(f64) = "penwidth",
+    /// Number of peripheries of node shapes and cluster boundaries.
+    Peripheries(u32) = "peripheries",
+    /// Rank constraint on the nodes in a subgraph.
+    Rank(Rank) = "rank",
+    RankDir(RankDir) = "rankdir",
+    /// Separation between ranks in inches.
+    RankSep(f64) = "ranksep",
+    /// Force `polygon` shapes to be regular.
+    Regular(bool) = "regular",
+    Shape(Shape) = "shape",
+    /// Number of sides of `polygon` shapes.
+    Sides(u32) = "sides",
+    /// Skew factor of `polygon` shapes.
+    Skew(f64) = "skew",
+    /// How edges are drawn.
+    Splines(Splines) = "splines",
+    Style(Styles) = "style",
+    /// Label near the tail of an edge.
+    TailLabel(Text<'a>) = "taillabel",
+    /// Browser window to open `Url` links in, for map and SVG outputs.
+    Target(Text<'a>) = "target",
+    /// Tooltip for map and SVG outputs.
+    Tooltip(Text<'a>) = "tooltip",
+    /// Hyperlink for map and SVG outputs.
+    Url(Text<'a>) = "URL",
+    /// Weight of an edge; heavier edges are kept shorter and straighter.
+    Weight(f64) = "weight",
+    /// Width of a node in inches.
+    Width(f64) = "width",
+    /// External label of a node or edge, placed outside of it.
+    XLabel(Text<'a>) = "xlabel",
+}
+
+impl<'a> Attr<'a> {
+    /// Creates a [`Attr::Custom`] attribute.
+    pub fn custom(name: Id<'a>, value: Text<'a>) -> Self {
+        Attr::Custom(name, value)
+    }
+}
+
+impl std::fmt::Display for Attr<'_> {
+    /// Writes the attribute as `name=value` in DOT syntax.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Attr::Custom(name, _) => write!(f, "{name}={}", self.value_to_string()),
+            _ => write!(f, "{}={}", self.name(), self.value_to_string()),
+        }
+    }
+}
+
+/// Which ends of an edge have arrowheads.
+/// See <https://graphviz.org/docs/attr-types/dirType/>.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Dir {
+    Forward,
+    Back,
+    Both,
+    None,
+}
+
+impl Dir {
+    pub const fn as_static_str(self) -> &'static str {
+        match self {
+            Dir::Forward => "forward",
+            Dir::Back => "back",
+            Dir::Both => "both",
+            Dir::None => "none",
+        }
+    }
+}
+
+/// Rank constraints on the nodes in a subgraph.
+/// See <https://graphviz.org/docs/attr-types/rankType/>.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Rank {
+    Same,
+    Min,
+    Source,
+    Max,
+    Sink,
+}
+
+impl Rank {
+    pub const fn as_static_str(self) -> &'static str {
+        match self {
+            Rank::Same => "same",
+            Rank::Min => "min",
+            Rank::Source => "source",
+            Rank::Max => "max",
+            Rank::Sink => "sink",
+        }
+    }
+}
+
+/// How edges are drawn.
+/// See <https://graphviz.org/docs/attrs/splines/>.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Splines {
+    None,
+    Line,
+    Polyline,
+    Curved,
+    Ortho,
+    Spline,
+}
+
+impl Splines {
+    pub const fn as_static_str(self) -> &'static str {
+        match self {
+            Splines::None => "none",
+            Splines::Line => "line",
+            Splines::Polyline => "polyline",
+            Splines::Curved => "curved",
+            Splines::Ortho => "ortho",
+            Splines::Spline => "spline",
+        }
+    }
+}
+
+/// An ordered collection of attributes, with at most one per name.
+///
+/// Attributes are kept in the order they were first set, which is the
+/// order they are rendered in. Setting an attribute that is already present
+/// replaces its value in place.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Attrs<'a>(Vec<Attr<'a>>);
+
+impl<'a> Attrs<'a> {
+    pub fn new() -> Self {
+        Attrs(Vec::new())
+    }
+
+    /// Sets `attr`, replacing any attribute with the same name.
+    pub fn with(mut self, attr: Attr<'a>) -> Self {
+        self.set(attr);
+        self
+    }
+
+    /// Sets `attr`, returning the attribute with the same name that it
+    /// replaced, if any.
+    pub fn set(&mut self, attr: Attr<'a>) -> Option<Attr<'a>> {
+        match self.0.iter_mut().find(|a| a.name() == attr.name()) {
+            Some(existing) => Some(std::mem::replace(existing, attr)),
+            None => {
+                self.0.push(attr);
+                None
+            }
+        }
+    }
+
+    /// The attribute named `name`, if it is set.
+    pub fn get(&self, name: &str) -> Option<&Attr<'a>> {
+        self.0.iter().find(|a| a.name() == name)
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.get(name).is_some()
+    }
+
+    /// Removes the attribute named `name`, keeping the order of the rest.
+    pub fn remove(&mut self, name: &str) -> Option<Attr<'a>> {
+        let i = self.0.iter().position(|a| a.name() == name)?;
+        Some(self.0.remove(i))
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Attr<'a>> {
+        self.0.iter()
+    }
+
+    /// Converts the attributes into ones that own their values.
+    pub fn into_owned(self) -> Attrs<'static> {
+        Attrs(self.0.into_iter().map(Attr::into_owned).collect())
+    }
+}
+
+impl<'a> Extend<Attr<'a>> for Attrs<'a> {
+    /// Sets each attribute in turn, so later ones replace earlier ones.
+    fn extend<T: IntoIterator<Item = Attr<'a>>>(&mut self, iter: T) {
+        for attr in iter {
+            self.set(attr);
+        }
+    }
+}
+
+impl<'a> FromIterator<Attr<'a>> for Attrs<'a> {
+    fn from_iter<T: IntoIterator<Item = Attr<'a>>>(iter: T) -> Self {
+        let mut attrs = Attrs::new();
+        attrs.extend(iter);
+        attrs
+    }
+}
+
+impl<'a, const N: usize> From<[Attr<'a>; N]> for Attrs<'a> {
+    fn from(attrs: [Attr<'a>; N]) -> Self {
+        attrs.into_iter().collect()
+    }
+}
+
+impl<'a> From<Vec<Attr<'a>>> for Attrs<'a> {
+    fn from(attrs: Vec<Attr<'a>>) -> Self {
+        attrs.into_iter().collect()
+    }
+}
+
+impl<'a> IntoIterator for Attrs<'a> {
+    type Item = Attr<'a>;
+    type IntoIter = std::vec::IntoIter<Attr<'a>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'a, 'b> IntoIterator for &'b Attrs<'a> {
+    type Item = &'b Attr<'a>;
+    type IntoIter = std::slice::Iter<'b, Attr<'a>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
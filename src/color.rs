@@ -0,0 +1,308 @@
+//! Typed Graphviz colors.
+//!
+//! [`Color`] covers the [color forms](https://graphviz.org/docs/attr-types/color/)
+//! that Graphviz accepts: names from the X11, SVG and Brewer color schemes,
+//! `#rrggbb[aa]` values and HSV triples, as well as the weighted
+//! [color lists](https://graphviz.org/docs/attr-types/colorList/) used for
+//! gradients and for [`Style::Striped`](crate::Style::Striped) and
+//! [`Style::Wedged`](crate::Style::Wedged) fills.
+//!
+//! ```rust
+//! use dotwalk::Color;
+//!
+//! let stripes: Color<'_> = "red:green;0.3:blue".parse().unwrap();
+//! assert_eq!(
+//!     stripes,
+//!     Color::list([
+//!         (Color::named("red"), None),
+//!         (Color::named("green"), Some(0.3)),
+//!         (Color::named("blue"), None),
+//!     ])
+//! );
+//! assert_eq!(Color::rgba(255, 0, 0, 128).to_string(), "#ff000080");
+//! assert_eq!(Color::brewer("blues9", 3).to_string(), "/blues9/3");
+//! ```
+
+use std::borrow::Cow;
+
+/// A Graphviz color, or a list of weighted colors.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Color<'a> {
+    /// A color name, such as `red` or `lightblue`, looked up in the
+    /// `colorscheme` of the element, which is X11 by default.
+    Named(Cow<'a, str>),
+    /// A color `name` in the color scheme `scheme`, such as `/blues9/3` for
+    /// the third color of the nine-class Brewer `blues` scheme, or
+    /// `/svg/coral`.
+    Scheme {
+        scheme: Cow<'a, str>,
+        name: Cow<'a, str>,
+    },
+    /// `#rrggbb`
+    Rgb(u8, u8, u8),
+    /// `#rrggbbaa`
+    Rgba(u8, u8, u8, u8),
+    /// Hue, saturation and value, each from 0 to 1.
+    Hsv(f64, f64, f64),
+    /// Colors with optional weights, which give the fraction of the
+    /// gradient or fill that each color takes up. The remaining fraction is
+    /// shared evenly by the colors without a weight.
+    List(Vec<(Color<'a>, Option<f64>)>),
+}
+
+impl<'a> Color<'a> {
+    /// A color name in the default color scheme.
+    pub fn named(name: impl Into<Cow<'a, str>>) -> Self {
+        Color::Named(name.into())
+    }
+
+    /// The color `name` in the color scheme `scheme`.
+    pub fn scheme(scheme: impl Into<Cow<'a, str>>, name: impl Into<Cow<'a, str>>) -> Self {
+        Color::Scheme {
+            scheme: scheme.into(),
+            name: name.into(),
+        }
+    }
+
+    /// The color at the 1-based `index` of a [Brewer](https://colorbrewer2.org/)
+    /// color scheme, such as `blues9` or `set312`.
+    pub fn brewer(scheme: impl Into<Cow<'a, str>>, index: u32) -> Self {
+        Color::scheme(scheme, index.to_string())
+    }
+
+    pub fn rgb(r: u8, g: u8, b: u8) -> Self {
+        Color::Rgb(r, g, b)
+    }
+
+    pub fn rgba(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Color::Rgba(r, g, b, a)
+    }
+
+    pub fn hsv(h: f64, s: f64, v: f64) -> Self {
+        Color::Hsv(h, s, v)
+    }
+
+    /// A list of colors with optional weights.
+    pub fn list(colors: impl IntoIterator<Item = (Color<'a>, Option<f64>)>) -> Self {
+        Color::List(colors.into_iter().collect())
+    }
+
+    /// Checks that Graphviz accepts the color, returning the problem if not.
+    pub fn check(&self) -> Result<(), &'static str> {
+        match self {
+            Color::Named(name) if !is_color_name(name, false) => Err("is not a valid color name"),
+            Color::Scheme { scheme, name }
+                if !is_color_name(scheme, false) || !is_color_name(name, true) =>
+            {
+                Err("is not a valid color name")
+            }
+            Color::Hsv(h, s, v) if [h, s, v].iter().any(|c| !(0.0..=1.0).contains(*c)) => {
+                Err("has an HSV component outside of 0 to 1")
+            }
+            Color::List(colors) => {
+                if colors.is_empty() {
+                    return Err("is an empty color list");
+                }
+                let mut total = 0.0;
+                for (color, weight) in colors {
+                    if let Color::List(_) = color {
+                        return Err("nests a color list");
+                    }
+                    color.check()?;
+                    if let Some(weight) = weight {
+                        if !(0.0..=1.0).contains(weight) {
+                            return Err("has a color weight outside of 0 to 1");
+                        }
+                        total += weight;
+                    }
+                }
+                // Allow for rounding in weights such as thirds.
+                match total <= 1.0 + 1e-9 {
+                    true => Ok(()),
+                    false => Err("has color weights that add up to more than 1"),
+                }
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// The color as a CSS color, for output formats other than DOT: names
+    /// as they are, HSV as RGB, and the first color of a list. Returns
+    /// `None` for a color in a scheme other than X11, which only Graphviz
+    /// can look up.
+    pub fn to_css(&self) -> Option<String> {
+        match self {
+            Color::Named(name) => Some(name.to_string()),
+            Color::Scheme { scheme, name } if scheme.eq_ignore_ascii_case("x11") => {
+                Some(name.to_string())
+            }
+            Color::Scheme { .. } => None,
+            Color::Rgb(..) | Color::Rgba(..) => Some(self.to_string()),
+            Color::Hsv(h, s, v) => {
+                let (h, s, v) = (
+                    h.clamp(0.0, 1.0) * 6.0,
+                    s.clamp(0.0, 1.0),
+                    v.clamp(0.0, 1.0),
+                );
+                let f = |n: f64| {
+                    let k = (n + h) % 6.0;
+                    let c = v - v * s * k.min(4.0 - k).clamp(0.0, 1.0);
+                    (c * 255.0).round() as u8
+                };
+                Some(Color::Rgb(f(5.0), f(3.0), f(1.0)).to_string())
+            }
+            Color::List(colors) => colors.first().and_then(|(color, _)| color.to_css()),
+        }
+    }
+
+    /// Converts the color into one that owns its names.
+    pub fn into_owned(self) -> Color<'static> {
+        match self {
+            Color::Named(name) => Color::Named(Cow::Owned(name.into_owned())),
+            Color::Scheme { scheme, name } => Color::Scheme {
+                scheme: Cow::Owned(scheme.into_owned()),
+                name: Cow::Owned(name.into_owned()),
+            },
+            Color::Rgb(r, g, b) => Color::Rgb(r, g, b),
+            Color::Rgba(r, g, b, a) => Color::Rgba(r, g, b, a),
+            Color::Hsv(h, s, v) => Color::Hsv(h, s, v),
+            Color::List(colors) => Color::List(
+                colors
+                    .into_iter()
+                    .map(|(color, weight)| (color.into_owned(), weight))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// Returns `true` if `name` is made of ASCII letters and digits, starting
+/// with a letter unless `digit_first` is set.
+fn is_color_name(name: &str, digit_first: bool) -> bool {
+    name.chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || (digit_first && c.is_ascii_digit()))
+        && name.chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+impl std::fmt::Display for Color<'_> {
+    /// Writes the color as it appears in a .dot file, without quotes.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Color::Named(name) => f.write_str(name),
+            Color::Scheme { scheme, name } => write!(f, "/{scheme}/{name}"),
+            Color::Rgb(r, g, b) => write!(f, "#{r:02x}{g:02x}{b:02x}"),
+            Color::Rgba(r, g, b, a) => write!(f, "#{r:02x}{g:02x}{b:02x}{a:02x}"),
+            Color::Hsv(h, s, v) => write!(f, "{h},{s},{v}"),
+            Color::List(colors) => {
+                for (i, (color, weight)) in colors.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(":")?;
+                    }
+                    write!(f, "{color}")?;
+                    if let Some(weight) = weight {
+                        write!(f, ";{weight}")?;
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Error returned when parsing a [`Color`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ColorError {
+    Empty,
+    /// A `#` value that is not six or eight hexadecimal digits.
+    InvalidHex(String),
+    /// A value starting with a digit that is not three numbers.
+    InvalidHsv(String),
+    InvalidName(String),
+    InvalidWeight(String),
+}
+
+impl std::error::Error for ColorError {}
+
+impl std::fmt::Display for ColorError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ColorError::Empty => write!(f, "Color cannot be empty"),
+            ColorError::InvalidHex(s) => write!(f, "Invalid RGB color '{s}'"),
+            ColorError::InvalidHsv(s) => write!(f, "Invalid HSV color '{s}'"),
+            ColorError::InvalidName(s) => write!(f, "Invalid color name '{s}'"),
+            ColorError::InvalidWeight(s) => write!(f, "Invalid color weight '{s}'"),
+        }
+    }
+}
+
+impl std::str::FromStr for Color<'static> {
+    type Err = ColorError;
+
+    /// Parses a [`color`](https://graphviz.org/docs/attr-types/color/) or
+    /// [`colorList`](https://graphviz.org/docs/attr-types/colorList/), such
+    /// as `"red"`, `"#ff000080"`, `"0.5 1 1"` or `"red:green;0.3:blue"`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.contains([':', ';']) {
+            let colors = s
+                .split(':')
+                .map(|item| {
+                    let (color, weight) = match item.split_once(';') {
+                        Some((color, weight)) => {
+                            let parsed = weight.trim().parse().ok().filter(|w: &f64| w.is_finite());
+                            let weight = parsed
+                                .ok_or_else(|| ColorError::InvalidWeight(weight.to_owned()))?;
+                            (color, Some(weight))
+                        }
+                        None => (item, None),
+                    };
+                    Ok((parse_single(color.trim())?, weight))
+                })
+                .collect::<Result<_, _>>()?;
+            return Ok(Color::List(colors));
+        }
+        parse_single(s)
+    }
+}
+
+/// Parses a color that is not a list.
+fn parse_single(s: &str) -> Result<Color<'static>, ColorError> {
+    let Some(first) = s.chars().next() else {
+        return Err(ColorError::Empty);
+    };
+    if let Some(hex) = s.strip_prefix('#') {
+        let bytes = (hex.len() % 2 == 0 && hex.is_ascii())
+            .then(|| {
+                (0..hex.len())
+                    .step_by(2)
+                    .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+                    .collect::<Option<Vec<_>>>()
+            })
+            .flatten();
+        return match bytes.as_deref() {
+            Some(&[r, g, b]) => Ok(Color::Rgb(r, g, b)),
+            Some(&[r, g, b, a]) => Ok(Color::Rgba(r, g, b, a)),
+            _ => Err(ColorError::InvalidHex(s.to_owned())),
+        };
+    }
+    if first.is_ascii_digit() || first == '.' {
+        let components: Option<Vec<f64>> = s
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|c| !c.is_empty())
+            .map(|c| c.parse().ok())
+            .collect();
+        return match components.as_deref() {
+            Some(&[h, s, v]) => Ok(Color::Hsv(h, s, v)),
+            _ => Err(ColorError::InvalidHsv(s.to_owned())),
+        };
+    }
+    let color = match s.strip_prefix('/').and_then(|rest| rest.split_once('/')) {
+        Some((scheme, name)) => Color::scheme(scheme.to_owned(), name.to_owned()),
+        None => Color::named(s.to_owned()),
+    };
+    match color.check() {
+        Ok(()) => Ok(color),
+        Err(_) => Err(ColorError::InvalidName(s.to_owned())),
+    }
+}
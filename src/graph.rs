@@ -0,0 +1,767 @@
+//! An owned graph model that can be rendered directly.
+//!
+//! [`DotGraph`] stores nodes, edges and subgraphs along with their
+//! attributes, and implements both [`Labeller`] and [`GraphWalk`], so it can
+//! be handed straight to [`render`](crate::render()). It is also the target
+//! for conversions from other representations, such as the syntax tree
+//! produced by [`parse`](crate::parse()).
+//!
+//! ```rust
+//! use dotwalk as dot;
+//! use dot::graph::{DotGraph, Edge, Node, Subgraph};
+//! use dot::{Attr, GraphKind, Id, Shape, Text};
+//!
+//! let mut graph = DotGraph::new(Id::new("g").ok(), GraphKind::Directed);
+//! let a = graph.add_node(Node::new(Id::new("a").unwrap()).with_label(Text::label("A")));
+//! let b = graph.add_node(Node::new(Id::new("b").unwrap()).with_attr(Attr::Shape(Shape::Box)));
+//! graph.add_edge(Edge::new(a, b).with_label(Text::label("a to b")));
+//! graph.add_subgraph(Subgraph::new(Id::new("cluster_0").ok()).with_nodes([a, b]));
+//!
+//! let mut output = Vec::new();
+//! dot::render(&graph, &mut output).unwrap();
+//! ```
+
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+
+use crate::parse::{self, AttrTarget, ParseError, Stmt, Vertex};
+use crate::{
+    Attr, Attrs, Classes, CompassPoint, GraphKind, GraphWalk, Id, Labeller, Record, Shape,
+    Stylesheet, Text,
+};
+
+/// Handle to a node of a [`DotGraph`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NodeIndex(pub usize);
+
+/// Handle to an edge of a [`DotGraph`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct EdgeIndex(pub usize);
+
+/// Handle to a subgraph of a [`DotGraph`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SubgraphIndex(pub usize);
+
+/// A node of a [`DotGraph`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Node {
+    pub id: Id<'static>,
+    /// The label, which defaults to the `id` if `None`.
+    pub label: Option<Text<'static>>,
+    pub attrs: Attrs<'static>,
+    /// The classes that the graph's stylesheet selects the node by.
+    pub classes: Classes<'static>,
+}
+
+impl Node {
+    pub fn new(id: Id<'static>) -> Self {
+        Node {
+            id,
+            label: None,
+            attrs: Attrs::new(),
+            classes: Vec::new(),
+        }
+    }
+
+    pub fn with_label(self, label: Text<'static>) -> Self {
+        Self {
+            label: Some(label),
+            ..self
+        }
+    }
+
+    /// Sets `attr`, replacing any attribute with the same name.
+    pub fn with_attr(mut self, attr: Attr<'_>) -> Self {
+        self.attrs.set(attr.into_owned());
+        self
+    }
+
+    /// Labels the node with `record`, and gives it the `record` shape
+    /// unless it already has a record shape.
+    pub fn with_record(mut self, record: Record<'_>) -> Self {
+        if !matches!(self.attrs.get("shape"), Some(Attr::Shape(shape)) if shape.is_record()) {
+            self.attrs.set(Attr::Shape(Shape::Record));
+        }
+        self.with_label(record.into_owned().into())
+    }
+
+    /// Adds the class `class`.
+    pub fn with_class(mut self, class: impl Into<Cow<'static, str>>) -> Self {
+        self.classes.push(class.into());
+        self
+    }
+}
+
+/// An edge of a [`DotGraph`], connecting two of its nodes.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Edge {
+    pub source: NodeIndex,
+    pub target: NodeIndex,
+    pub source_port: Option<Id<'static>>,
+    pub source_compass: Option<CompassPoint>,
+    pub target_port: Option<Id<'static>>,
+    pub target_compass: Option<CompassPoint>,
+    pub label: Option<Text<'static>>,
+    pub attrs: Attrs<'static>,
+    /// The classes that the graph's stylesheet selects the edge by.
+    pub classes: Classes<'static>,
+}
+
+impl Edge {
+    pub fn new(source: NodeIndex, target: NodeIndex) -> Self {
+        Edge {
+            source,
+            target,
+            source_port: None,
+            source_compass: None,
+            target_port: None,
+            target_compass: None,
+            label: None,
+            attrs: Attrs::new(),
+            classes: Vec::new(),
+        }
+    }
+
+    pub fn with_source_port(
+        self,
+        port: Option<Id<'static>>,
+        compass: Option<CompassPoint>,
+    ) -> Self {
+        Self {
+            source_port: port,
+            source_compass: compass,
+            ..self
+        }
+    }
+
+    pub fn with_target_port(
+        self,
+        port: Option<Id<'static>>,
+        compass: Option<CompassPoint>,
+    ) -> Self {
+        Self {
+            target_port: port,
+            target_compass: compass,
+            ..self
+        }
+    }
+
+    pub fn with_label(self, label: Text<'static>) -> Self {
+        Self {
+            label: Some(label),
+            ..self
+        }
+    }
+
+    /// Sets `attr`, replacing any attribute with the same name.
+    pub fn with_attr(mut self, attr: Attr<'_>) -> Self {
+        self.attrs.set(attr.into_owned());
+        self
+    }
+
+    /// Adds the class `class`.
+    pub fn with_class(mut self, class: impl Into<Cow<'static, str>>) -> Self {
+        self.classes.push(class.into());
+        self
+    }
+}
+
+/// A subgraph of a [`DotGraph`], grouping some of its nodes.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Subgraph {
+    pub id: Option<Id<'static>>,
+    pub label: Option<Text<'static>>,
+    pub attrs: Attrs<'static>,
+    /// The classes that the graph's stylesheet selects the subgraph by.
+    pub classes: Classes<'static>,
+    /// Defaults for the nodes first mentioned within the subgraph.
+    pub node_defaults: Attrs<'static>,
+    /// Defaults for the edges within the subgraph.
+    pub edge_defaults: Attrs<'static>,
+    pub nodes: Vec<NodeIndex>,
+    /// The edges declared within the subgraph.
+    pub edges: Vec<EdgeIndex>,
+    /// The subgraphs nested directly within this one.
+    pub subgraphs: Vec<SubgraphIndex>,
+}
+
+impl Subgraph {
+    pub fn new(id: Option<Id<'static>>) -> Self {
+        Subgraph {
+            id,
+            label: None,
+            attrs: Attrs::new(),
+            classes: Vec::new(),
+            node_defaults: Attrs::new(),
+            edge_defaults: Attrs::new(),
+            nodes: Vec::new(),
+            edges: Vec::new(),
+            subgraphs: Vec::new(),
+        }
+    }
+
+    pub fn with_label(self, label: Text<'static>) -> Self {
+        Self {
+            label: Some(label),
+            ..self
+        }
+    }
+
+    /// Sets `attr`, replacing any attribute with the same name.
+    pub fn with_attr(mut self, attr: Attr<'_>) -> Self {
+        self.attrs.set(attr.into_owned());
+        self
+    }
+
+    /// Adds the class `class`.
+    pub fn with_class(mut self, class: impl Into<Cow<'static, str>>) -> Self {
+        self.classes.push(class.into());
+        self
+    }
+
+    pub fn with_node_defaults(self, node_defaults: Attrs<'_>) -> Self {
+        Self {
+            node_defaults: node_defaults.into_owned(),
+            ..self
+        }
+    }
+
+    pub fn with_edge_defaults(self, edge_defaults: Attrs<'_>) -> Self {
+        Self {
+            edge_defaults: edge_defaults.into_owned(),
+            ..self
+        }
+    }
+
+    pub fn with_nodes(mut self, nodes: impl IntoIterator<Item = NodeIndex>) -> Self {
+        self.nodes.extend(nodes);
+        self
+    }
+
+    pub fn with_edges(mut self, edges: impl IntoIterator<Item = EdgeIndex>) -> Self {
+        self.edges.extend(edges);
+        self
+    }
+
+    pub fn with_subgraphs(mut self, subgraphs: impl IntoIterator<Item = SubgraphIndex>) -> Self {
+        self.subgraphs.extend(subgraphs);
+        self
+    }
+}
+
+/// An owned, mutable graph.
+///
+/// Node ids are unique: adding a node whose id is already present merges
+/// the new label and attributes into the existing node instead.
+///
+/// `node_defaults` and `edge_defaults` are rendered as `node[...]` and
+/// `edge[...]` statements. Graphs converted from a syntax tree have their
+/// `node` and `edge` statements applied to each element instead, since
+/// those only affect the elements that follow them.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DotGraph {
+    pub id: Option<Id<'static>>,
+    pub kind: GraphKind,
+    pub attrs: Attrs<'static>,
+    pub node_defaults: Attrs<'static>,
+    pub edge_defaults: Attrs<'static>,
+    /// Styles the nodes, edges and subgraphs by their classes.
+    pub stylesheet: Stylesheet<'static>,
+    nodes: Vec<Node>,
+    edges: Vec<Edge>,
+    subgraphs: Vec<Subgraph>,
+    node_ids: HashMap<Cow<'static, str>, NodeIndex>,
+}
+
+impl DotGraph {
+    pub fn new(id: Option<Id<'static>>, kind: GraphKind) -> Self {
+        DotGraph {
+            id,
+            kind,
+            attrs: Attrs::new(),
+            node_defaults: Attrs::new(),
+            edge_defaults: Attrs::new(),
+            stylesheet: Stylesheet::new(),
+            nodes: Vec::new(),
+            edges: Vec::new(),
+            subgraphs: Vec::new(),
+            node_ids: HashMap::new(),
+        }
+    }
+
+    /// Sets `attr`, replacing any attribute with the same name.
+    pub fn with_attr(mut self, attr: Attr<'_>) -> Self {
+        self.attrs.set(attr.into_owned());
+        self
+    }
+
+    pub fn with_node_defaults(self, node_defaults: Attrs<'_>) -> Self {
+        Self {
+            node_defaults: node_defaults.into_owned(),
+            ..self
+        }
+    }
+
+    pub fn with_edge_defaults(self, edge_defaults: Attrs<'_>) -> Self {
+        Self {
+            edge_defaults: edge_defaults.into_owned(),
+            ..self
+        }
+    }
+
+    pub fn with_stylesheet(self, stylesheet: Stylesheet<'_>) -> Self {
+        Self {
+            stylesheet: stylesheet.into_owned(),
+            ..self
+        }
+    }
+
+    /// Adds `node`, or merges it into the node with the same id, adding
+    /// any classes that the existing node does not have.
+    pub fn add_node(&mut self, node: Node) -> NodeIndex {
+        if let Some(&index) = self.node_ids.get(&node.id.name) {
+            let existing = &mut self.nodes[index.0];
+            if node.label.is_some() {
+                existing.label = node.label;
+            }
+            existing.attrs.extend(node.attrs);
+            for class in node.classes {
+                if !existing.classes.contains(&class) {
+                    existing.classes.push(class);
+                }
+            }
+            return index;
+        }
+        let index = NodeIndex(self.nodes.len());
+        self.node_ids.insert(node.id.name.clone(), index);
+        self.nodes.push(node);
+        index
+    }
+
+    /// Adds `edge`. Both of its endpoints must already be in the graph.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the source or target of `edge` is not a node of the graph.
+    pub fn add_edge(&mut self, edge: Edge) -> EdgeIndex {
+        assert!(
+            edge.source.0 < self.nodes.len() && edge.target.0 < self.nodes.len(),
+            "edge {} -> {} refers to a node that is not in a graph of {} nodes",
+            edge.source.0,
+            edge.target.0,
+            self.nodes.len()
+        );
+        self.edges.push(edge);
+        EdgeIndex(self.edges.len() - 1)
+    }
+
+    /// Adds `subgraph`. It is at the top level unless it is later listed
+    /// in the `subgraphs` of another subgraph.
+    pub fn add_subgraph(&mut self, subgraph: Subgraph) -> SubgraphIndex {
+        self.subgraphs.push(subgraph);
+        SubgraphIndex(self.subgraphs.len() - 1)
+    }
+
+    /// Finds the node with the id `name`.
+    pub fn find_node(&self, name: &str) -> Option<NodeIndex> {
+        self.node_ids.get(name).copied()
+    }
+
+    /// Finds the first subgraph with the id `name`.
+    pub fn find_subgraph(&self, name: &str) -> Option<SubgraphIndex> {
+        self.subgraphs
+            .iter()
+            .position(|s| s.id.as_ref().is_some_and(|id| id.name == name))
+            .map(SubgraphIndex)
+    }
+
+    pub fn node(&self, index: NodeIndex) -> &Node {
+        &self.nodes[index.0]
+    }
+
+    /// The node at `index`. Its `id` must not be changed.
+    pub fn node_mut(&mut self, index: NodeIndex) -> &mut Node {
+        &mut self.nodes[index.0]
+    }
+
+    pub fn edge(&self, index: EdgeIndex) -> &Edge {
+        &self.edges[index.0]
+    }
+
+    pub fn edge_mut(&mut self, index: EdgeIndex) -> &mut Edge {
+        &mut self.edges[index.0]
+    }
+
+    pub fn subgraph(&self, index: SubgraphIndex) -> &Subgraph {
+        &self.subgraphs[index.0]
+    }
+
+    pub fn subgraph_mut(&mut self, index: SubgraphIndex) -> &mut Subgraph {
+        &mut self.subgraphs[index.0]
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn edge_count(&self) -> usize {
+        self.edges.len()
+    }
+
+    pub fn subgraph_count(&self) -> usize {
+        self.subgraphs.len()
+    }
+}
+
+impl FromStr for DotGraph {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse::parse(s).map(DotGraph::from)
+    }
+}
+
+impl From<parse::Graph<'_>> for DotGraph {
+    /// Converts a syntax tree to a graph, resolving `node` and `edge`
+    /// defaults and expanding edge chains and subgraph operands into
+    /// individual edges.
+    ///
+    /// The nodes and edges of a nested subgraph also belong to the enclosing
+    /// subgraphs. In a `strict` graph, repeated edges are merged into the
+    /// first one.
+    fn from(ast: parse::Graph<'_>) -> Self {
+        let mut graph = DotGraph::new(ast.id.map(Id::into_owned), ast.kind);
+        let mut builder = Builder {
+            graph: &mut graph,
+            strict: ast.strict,
+            stack: Vec::new(),
+        };
+        builder.stmts(ast.stmts, &mut Scope::default());
+        graph
+    }
+}
+
+/// The `node` and `edge` defaults in effect at a point in the syntax tree.
+#[derive(Clone, Default)]
+struct Scope {
+    node: Vec<(String, Text<'static>)>,
+    edge: Vec<(String, Text<'static>)>,
+}
+
+struct Builder<'g> {
+    graph: &'g mut DotGraph,
+    strict: bool,
+    /// The subgraphs enclosing the current statement, innermost last.
+    stack: Vec<SubgraphIndex>,
+}
+
+/// Sets the parsed attribute `name`, storing a `label` separately.
+pub(crate) fn set_parsed(
+    label: &mut Option<Text<'static>>,
+    attrs: &mut Attrs<'static>,
+    name: &str,
+    value: Text<'_>,
+) {
+    if name == "label" {
+        *label = Some(value.into_owned());
+    } else {
+        attrs.set(Attr::parse(name, value).into_owned());
+    }
+}
+
+impl Builder<'_> {
+    /// Converts `stmts`, returning every node that they mention.
+    fn stmts(&mut self, stmts: Vec<Stmt<'_>>, scope: &mut Scope) -> Vec<NodeIndex> {
+        let mut mentioned = Vec::new();
+        for stmt in stmts {
+            match stmt {
+                Stmt::Node(stmt) => {
+                    let index = self.node(stmt.node.id, scope);
+                    let node = self.graph.node_mut(index);
+                    for attr in stmt.attrs {
+                        set_parsed(&mut node.label, &mut node.attrs, &attr.name, attr.value);
+                    }
+                    mentioned.push(index);
+                }
+                Stmt::Edge(stmt) => {
+                    let mut operands = Vec::new();
+                    for vertex in stmt.chain {
+                        let endpoints = match vertex {
+                            Vertex::Node(n) => {
+                                let index = self.node(n.id, scope);
+                                vec![(index, n.port.map(Id::into_owned), n.compass)]
+                            }
+                            Vertex::Subgraph(s) => self
+                                .subgraph(s, scope)
+                                .into_iter()
+                                .map(|index| (index, None, None))
+                                .collect(),
+                        };
+                        mentioned.extend(endpoints.iter().map(|(index, _, _)| *index));
+                        operands.push(endpoints);
+                    }
+                    for pair in operands.windows(2) {
+                        for (source, source_port, source_compass) in &pair[0] {
+                            for (target, target_port, target_compass) in &pair[1] {
+                                let edge = Edge::new(*source, *target)
+                                    .with_source_port(source_port.clone(), *source_compass)
+                                    .with_target_port(target_port.clone(), *target_compass);
+                                self.edge(edge, scope, &stmt.attrs);
+                            }
+                        }
+                    }
+                }
+                Stmt::Attr(stmt) => match stmt.target {
+                    AttrTarget::Graph => {
+                        for attr in stmt.attrs {
+                            self.graph_attr(&attr.name, attr.value);
+                        }
+                    }
+                    AttrTarget::Node => scope.node.extend(
+                        stmt.attrs
+                            .into_iter()
+                            .map(|attr| (attr.name.name.into_owned(), attr.value.into_owned())),
+                    ),
+                    AttrTarget::Edge => scope.edge.extend(
+                        stmt.attrs
+                            .into_iter()
+                            .map(|attr| (attr.name.name.into_owned(), attr.value.into_owned())),
+                    ),
+                },
+                Stmt::Assign(attr) => self.graph_attr(&attr.name, attr.value),
+                Stmt::Subgraph(subgraph) => mentioned.extend(self.subgraph(subgraph, scope)),
+            }
+        }
+        mentioned
+    }
+
+    /// Sets an attribute of the innermost subgraph, or of the graph itself.
+    fn graph_attr(&mut self, name: &str, value: Text<'_>) {
+        match self.stack.last() {
+            Some(&index) => {
+                let subgraph = self.graph.subgraph_mut(index);
+                set_parsed(&mut subgraph.label, &mut subgraph.attrs, name, value);
+            }
+            None => {
+                self.graph.attrs.set(Attr::parse(name, value).into_owned());
+            }
+        }
+    }
+
+    /// Finds or creates the node `id`, and adds it to the enclosing subgraphs.
+    fn node(&mut self, id: Id<'_>, scope: &Scope) -> NodeIndex {
+        let index = match self.graph.find_node(&id) {
+            Some(index) => index,
+            None => {
+                let mut node = Node::new(id.into_owned());
+                for (name, value) in &scope.node {
+                    set_parsed(&mut node.label, &mut node.attrs, name, value.clone());
+                }
+                self.graph.add_node(node)
+            }
+        };
+        for &s in &self.stack {
+            let nodes = &mut self.graph.subgraph_mut(s).nodes;
+            if !nodes.contains(&index) {
+                nodes.push(index);
+            }
+        }
+        index
+    }
+
+    fn edge(&mut self, mut edge: Edge, scope: &Scope, attrs: &[parse::Attribute<'_>]) {
+        let defaults = scope.edge.iter().map(|(name, value)| (&name[..], value));
+        let attrs = attrs.iter().map(|attr| (&attr.name[..], &attr.value));
+        let existing = self.strict.then(|| {
+            self.graph.edges.iter().position(|e| {
+                (e.source, e.target) == (edge.source, edge.target)
+                    || (self.graph.kind == GraphKind::Undirected
+                        && (e.source, e.target) == (edge.target, edge.source))
+            })
+        });
+        match existing.flatten() {
+            Some(i) => {
+                let edge = &mut self.graph.edges[i];
+                for (name, value) in attrs {
+                    set_parsed(&mut edge.label, &mut edge.attrs, name, value.clone());
+                }
+            }
+            None => {
+                for (name, value) in defaults.chain(attrs) {
+                    set_parsed(&mut edge.label, &mut edge.attrs, name, value.clone());
+                }
+                let index = self.graph.add_edge(edge);
+                for &s in &self.stack {
+                    self.graph.subgraph_mut(s).edges.push(index);
+                }
+            }
+        }
+    }
+
+    /// Converts `ast` into a new subgraph, or into the existing subgraph
+    /// with the same id, returning every node that it mentions.
+    fn subgraph(&mut self, ast: parse::Subgraph<'_>, scope: &Scope) -> Vec<NodeIndex> {
+        let existing = ast.id.as_ref().and_then(|id| self.graph.find_subgraph(id));
+        let index = existing.unwrap_or_else(|| {
+            let index = self
+                .graph
+                .add_subgraph(Subgraph::new(ast.id.map(Id::into_owned)));
+            if let Some(&parent) = self.stack.last() {
+                self.graph.subgraph_mut(parent).subgraphs.push(index);
+            }
+            index
+        });
+        self.stack.push(index);
+        let mentioned = self.stmts(ast.stmts, &mut scope.clone());
+        self.stack.pop();
+        mentioned
+    }
+}
+
+impl<'a> Labeller<'a> for DotGraph {
+    type Node = NodeIndex;
+    type Edge = EdgeIndex;
+    type Subgraph = SubgraphIndex;
+
+    fn graph_id(&'a self) -> Id<'a> {
+        match &self.id {
+            Some(id) => id.clone(),
+            None => Id::quoted("").unwrap(),
+        }
+    }
+
+    fn graph_attrs(&'a self) -> Attrs<'a> {
+        self.attrs.clone()
+    }
+
+    fn node_defaults(&'a self) -> Attrs<'a> {
+        self.node_defaults.clone()
+    }
+
+    fn edge_defaults(&'a self) -> Attrs<'a> {
+        self.edge_defaults.clone()
+    }
+
+    fn stylesheet(&'a self) -> Stylesheet<'a> {
+        self.stylesheet.clone()
+    }
+
+    fn node_id(&'a self, n: &NodeIndex) -> Id<'a> {
+        self.node(*n).id.clone()
+    }
+
+    fn node_label(&'a self, n: &NodeIndex) -> Text<'a> {
+        let node = self.node(*n);
+        match &node.label {
+            Some(label) => label.clone(),
+            None => Text::Label(node.id.name.clone()),
+        }
+    }
+
+    fn node_attrs(&'a self, n: &NodeIndex) -> Attrs<'a> {
+        self.node(*n).attrs.clone()
+    }
+
+    fn node_classes(&'a self, n: &NodeIndex) -> Classes<'a> {
+        self.node(*n).classes.clone()
+    }
+
+    fn edge_label(&'a self, e: &EdgeIndex) -> Text<'a> {
+        self.edge(*e).label.clone().unwrap_or(Text::label(""))
+    }
+
+    fn edge_attrs(&'a self, e: &EdgeIndex) -> Attrs<'a> {
+        self.edge(*e).attrs.clone()
+    }
+
+    fn edge_classes(&'a self, e: &EdgeIndex) -> Classes<'a> {
+        self.edge(*e).classes.clone()
+    }
+
+    fn edge_start_point(&'a self, e: &EdgeIndex) -> Option<CompassPoint> {
+        self.edge(*e).source_compass
+    }
+
+    fn edge_end_point(&'a self, e: &EdgeIndex) -> Option<CompassPoint> {
+        self.edge(*e).target_compass
+    }
+
+    fn edge_start_port(&'a self, e: &EdgeIndex) -> Option<Id<'a>> {
+        self.edge(*e).source_port.clone()
+    }
+
+    fn edge_end_port(&'a self, e: &EdgeIndex) -> Option<Id<'a>> {
+        self.edge(*e).target_port.clone()
+    }
+
+    fn kind(&self) -> GraphKind {
+        self.kind
+    }
+
+    fn subgraph_id(&'a self, s: &SubgraphIndex) -> Option<Id<'a>> {
+        self.subgraph(*s).id.clone()
+    }
+
+    fn subgraph_label(&'a self, s: &SubgraphIndex) -> Text<'a> {
+        self.subgraph(*s).label.clone().unwrap_or(Text::label(""))
+    }
+
+    fn subgraph_attrs(&'a self, s: &SubgraphIndex) -> Attrs<'a> {
+        self.subgraph(*s).attrs.clone()
+    }
+
+    fn subgraph_classes(&'a self, s: &SubgraphIndex) -> Classes<'a> {
+        self.subgraph(*s).classes.clone()
+    }
+
+    fn subgraph_node_defaults(&'a self, s: &SubgraphIndex) -> Attrs<'a> {
+        self.subgraph(*s).node_defaults.clone()
+    }
+
+    fn subgraph_edge_defaults(&'a self, s: &SubgraphIndex) -> Attrs<'a> {
+        self.subgraph(*s).edge_defaults.clone()
+    }
+}
+
+impl<'a> GraphWalk<'a> for DotGraph {
+    type Node = NodeIndex;
+    type Edge = EdgeIndex;
+    type Subgraph = SubgraphIndex;
+
+    fn nodes(&'a self) -> crate::Nodes<'a, NodeIndex> {
+        (0..self.nodes.len()).map(NodeIndex).collect()
+    }
+
+    fn edges(&'a self) -> crate::Edges<'a, EdgeIndex> {
+        (0..self.edges.len()).map(EdgeIndex).collect()
+    }
+
+    fn source(&'a self, edge: &EdgeIndex) -> NodeIndex {
+        self.edge(*edge).source
+    }
+
+    fn target(&'a self, edge: &EdgeIndex) -> NodeIndex {
+        self.edge(*edge).target
+    }
+
+    fn subgraphs(&'a self) -> crate::Subgraphs<'a, SubgraphIndex> {
+        let nested: HashSet<_> = self.subgraphs.iter().flat_map(|s| &s.subgraphs).collect();
+        (0..self.subgraphs.len())
+            .map(SubgraphIndex)
+            .filter(|s| !nested.contains(s))
+            .collect()
+    }
+
+    fn subgraph_children(&'a self, s: &SubgraphIndex) -> crate::Subgraphs<'a, SubgraphIndex> {
+        Cow::Borrowed(&self.subgraph(*s).subgraphs)
+    }
+
+    fn subgraph_nodes(&'a self, s: &SubgraphIndex) -> crate::Nodes<'a, NodeIndex> {
+        Cow::Borrowed(&self.subgraph(*s).nodes)
+    }
+
+    fn subgraph_edges(&'a self, s: &SubgraphIndex) -> crate::Edges<'a, usize> {
+        self.subgraph(*s).edges.iter().map(|e| e.0).collect()
+    }
+}
@@ -0,0 +1,1072 @@
+//! Conversion to and from [GraphML](http://graphml.graphdrawing.org/).
+//!
+//! [`render`] writes a graph as GraphML, for tools such as yEd and Gephi.
+//! Each attribute of the graph, a node or an edge is written as a `<data>`
+//! element, for a `<key>` declared with the name of the attribute, so labels
+//! are found under the key `label`. Subgraphs become nodes that hold a
+//! nested `<graph>`, with the attributes of the subgraph on that graph.
+//!
+//! [`parse`] reads GraphML back into a [`DotGraph`], which can be handed to
+//! [`crate::render()`] or to the other backends. Keys are read as attributes
+//! with the `attr.name` of the key, and nested graphs as subgraphs with the
+//! id of the node that holds them.
+//!
+//! ```rust
+//! use dotwalk::graph::{DotGraph, Edge, Node};
+//! use dotwalk::{graphml, Attr, GraphKind, Id, Shape, Text};
+//!
+//! let mut graph = DotGraph::new(Id::new("g").ok(), GraphKind::Directed);
+//! let a = graph.add_node(Node::new(Id::new("a").unwrap()).with_label(Text::label("Start")));
+//! let b = graph.add_node(Node::new(Id::new("b").unwrap()).with_attr(Attr::Shape(Shape::Box)));
+//! graph.add_edge(Edge::new(a, b).with_label(Text::label("go")));
+//!
+//! let mut output = Vec::new();
+//! graphml::render(&graph, &mut output).unwrap();
+//! let xml = String::from_utf8(output).unwrap();
+//! assert_eq!(
+//!     xml,
+//!     r#"<?xml version="1.0" encoding="UTF-8"?>
+//! <graphml xmlns="http://graphml.graphdrawing.org/xmlns">
+//!     <key id="d0" for="node" attr.name="label" attr.type="string"/>
+//!     <key id="d1" for="node" attr.name="shape" attr.type="string"/>
+//!     <key id="d2" for="edge" attr.name="label" attr.type="string"/>
+//!     <graph id="g" edgedefault="directed">
+//!         <node id="a"><data key="d0">Start</data></node>
+//!         <node id="b"><data key="d0">b</data><data key="d1">box</data></node>
+//!         <edge source="a" target="b"><data key="d2">go</data></edge>
+//!     </graph>
+//! </graphml>
+//! "#
+//! );
+//!
+//! let parsed = graphml::parse(&xml).unwrap();
+//! let a = parsed.find_node("a").unwrap();
+//! assert_eq!(parsed.node(a).label, Some(Text::esc("Start")));
+//! ```
+//!
+//! # Values
+//!
+//! Values are written as they would be in DOT, without the quotes: a line
+//! break in a label is written as `\n`, and an HTML-like label between `<`
+//! and `>`. Other values that start with `<` or `"`, such as the record label
+//! `<in>|<out>`, keep their DOT quotes. [`parse`] reads them back the same
+//! way, as [`Text::Esc`] or [`Text::Html`], so a graph keeps its labels
+//! through GraphML. All keys have the type `string`.
+//!
+//! # Differences from the DOT output
+//!
+//! * The `node` and `edge` defaults of the graph and its subgraphs, and the
+//!   attributes that the stylesheet gives, are written on each node and
+//!   edge, since GraphML has no defaults that only apply to part of a
+//!   graph. The classes themselves are dropped.
+//! * A node is written in the first subgraph that lists it, as GraphML
+//!   graphs nest as a tree. Subgraphs without an id are given one.
+//! * The font, the theme and the legend of the [`RenderOptions`] are
+//!   ignored.
+//!
+//! When reading, the first `<graph>` of the file is read, and the type of
+//! each `<data>` is ignored. Edges whose `directed` differs from the graph
+//! get a `dir` attribute. Key defaults are applied to each node, edge or
+//! graph without a value for the key. yEd keeps its labels in `<y:NodeLabel>`
+//! and `<y:EdgeLabel>` elements of its graphics data, which are read as
+//! labels unless there is a `label` key. Hyperedges and edges to nested
+//! graphs cannot be read, as DOT has nothing like them.
+
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+
+use crate::attrs::Dir;
+use crate::graph::{set_parsed, DotGraph, Edge, Node, NodeIndex, Subgraph, SubgraphIndex};
+use crate::parse::compass_point;
+use crate::render::{
+    check_ids, ordered, resolve_edge_attrs, resolve_node_attrs, resolve_subgraph_attrs,
+    walk_subgraph, Emitted, Printer, SubgraphVisitor,
+};
+use crate::types::decode_entities;
+use crate::{
+    Attr, Attrs, CompassPoint, GraphKind, GraphWalk, Id, Labeller, RenderError, RenderOptions,
+    Stylesheet, Text,
+};
+
+/// Renders graph `g` into the writer `w` as GraphML.
+pub fn render<'a, N, E, S, G, W>(g: &'a G, w: &mut W) -> Result<(), RenderError>
+where
+    N: Clone + 'a,
+    E: Clone + 'a,
+    S: Clone + 'a,
+    G: Labeller<'a, Node = N, Edge = E, Subgraph = S>
+        + GraphWalk<'a, Node = N, Edge = E, Subgraph = S>,
+    W: Write,
+{
+    render_opts(g, w, RenderOptions::default())
+}
+
+/// Renders graph `g` into the writer `w` as GraphML, leaving out the labels,
+/// styles, colors or arrows as `options` says, and ordering the data,
+/// indenting and ending lines as it says.
+pub fn render_opts<'a, N, E, S, G, W>(
+    g: &'a G,
+    w: &mut W,
+    options: impl Into<RenderOptions>,
+) -> Result<(), RenderError>
+where
+    N: Clone + 'a,
+    E: Clone + 'a,
+    S: Clone + 'a,
+    G: Labeller<'a, Node = N, Edge = E, Subgraph = S>
+        + GraphWalk<'a, Node = N, Edge = E, Subgraph = S>,
+    W: Write,
+{
+    let options = options.into();
+    check_ids(g)?;
+
+    let mut writer = Writer {
+        graph: g,
+        stylesheet: g.stylesheet(),
+        options: &options,
+        keys: Vec::new(),
+        ports: HashMap::new(),
+        used: HashSet::new(),
+        written: HashSet::new(),
+        lines: Vec::new(),
+    };
+    for n in g.nodes().iter() {
+        writer.used.insert(g.node_id(n)[..].to_owned());
+    }
+    // GraphML declares the ports of a node within it.
+    for e in g.edges().iter() {
+        let ends = [
+            (g.source(e), g.edge_start_port(e), g.edge_start_point(e)),
+            (g.target(e), g.edge_end_port(e), g.edge_end_point(e)),
+        ];
+        for (n, port, compass) in ends {
+            if let Some(port) = port_name(port.as_ref(), compass) {
+                let ports = writer
+                    .ports
+                    .entry(g.node_id(&n)[..].to_owned())
+                    .or_default();
+                if !ports.contains(&port) {
+                    ports.push(port);
+                }
+            }
+        }
+    }
+
+    let mut attrs = g.graph_attrs();
+    if let (GraphKind::Directed, Some(rank_dir)) = (g.kind(), g.rank_dir()) {
+        if !attrs.contains("rankdir") {
+            attrs.set(Attr::RankDir(rank_dir));
+        }
+    }
+    let id = g.graph_id();
+    let id = match id.is_empty() {
+        true => String::new(),
+        false => format!(" id=\"{}\"", escape(&id)),
+    };
+    writer.lines.push((
+        1,
+        format!("<graph{id} edgedefault=\"{}\">", edge_default(g.kind())),
+    ));
+    writer.data(2, "graph", &attrs);
+
+    let node_defaults = g.node_defaults();
+    let edge_defaults = g.edge_defaults();
+    let edges = g.edges();
+    let mut emitted = Emitted::default();
+    for s in g.subgraphs().iter() {
+        emitted.extend(walk_subgraph(
+            g,
+            &mut writer,
+            s,
+            2,
+            &edges,
+            &node_defaults,
+            &edge_defaults,
+        )?);
+    }
+    for n in g.nodes().iter() {
+        if !emitted.nodes.contains(&g.node_id(n)) {
+            writer.visit_node(n, 2, &node_defaults)?;
+        }
+    }
+    for (i, e) in edges.iter().enumerate() {
+        if !emitted.edges.contains(&i) {
+            writer.visit_edge(e, 2, &edge_defaults)?;
+        }
+    }
+    writer.lines.push((1, "</graph>".to_owned()));
+
+    let mut p = Printer::new(Vec::new(), &options);
+    p.line(0, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    p.line(
+        0,
+        r#"<graphml xmlns="http://graphml.graphdrawing.org/xmlns">"#,
+    )?;
+    for (i, (domain, name)) in writer.keys.iter().enumerate() {
+        p.line(
+            1,
+            format_args!(
+                "<key id=\"d{i}\" for=\"{domain}\" attr.name=\"{}\" attr.type=\"string\"/>",
+                escape(name)
+            ),
+        )?;
+    }
+    for (depth, line) in &writer.lines {
+        p.line(*depth, line)?;
+    }
+    p.line(0, "</graphml>")?;
+
+    w.write_all(&p.finish()?)?;
+    Ok(())
+}
+
+/// The state of a GraphML document being written.
+struct Writer<'a, 'o, G> {
+    graph: &'a G,
+    stylesheet: Stylesheet<'a>,
+    options: &'o RenderOptions,
+    /// The domain and attribute name of each key, whose id is `d` and its
+    /// index.
+    keys: Vec<(&'static str, String)>,
+    /// The ports that the edges use on each node.
+    ports: HashMap<String, Vec<String>>,
+    /// The ids of the nodes, and of the nodes that hold subgraphs.
+    used: HashSet<String>,
+    /// The nodes written so far. Unlike DOT, GraphML takes each node only
+    /// once, so a node in several subgraphs is written in the first one.
+    written: HashSet<Id<'a>>,
+    /// The lines of the `<graph>`, with their depth, written after the keys.
+    lines: Vec<(usize, String)>,
+}
+
+impl<'a, N, E, S, G> Writer<'a, '_, G>
+where
+    N: Clone + 'a,
+    E: Clone + 'a,
+    S: Clone + 'a,
+    G: Labeller<'a, Node = N, Edge = E, Subgraph = S>
+        + GraphWalk<'a, Node = N, Edge = E, Subgraph = S>,
+{
+    /// The id of the key for the attribute `name` in `domain`, declaring the
+    /// key if it is new.
+    fn key(&mut self, domain: &'static str, name: &str) -> String {
+        let index = match self
+            .keys
+            .iter()
+            .position(|(d, n)| *d == domain && n == name)
+        {
+            Some(index) => index,
+            None => {
+                self.keys.push((domain, name.to_owned()));
+                self.keys.len() - 1
+            }
+        };
+        format!("d{index}")
+    }
+
+    /// The `<data>` element for `attr`, which is in `domain`.
+    fn data_element(&mut self, domain: &'static str, attr: &Attr<'_>) -> String {
+        let key = self.key(domain, attr.name());
+        format!("<data key=\"{key}\">{}</data>", escape(&dot_value(attr)))
+    }
+
+    /// Writes a line for each attribute among `attrs`, which are in
+    /// `domain`.
+    fn data(&mut self, depth: usize, domain: &'static str, attrs: &Attrs<'_>) {
+        for attr in ordered(attrs, self.options) {
+            let data = self.data_element(domain, attr);
+            self.lines.push((depth, data));
+        }
+    }
+
+    /// The `<data>` elements for `attrs`, which are in `domain`, on one line.
+    fn data_elements(&mut self, domain: &'static str, attrs: &Attrs<'_>) -> String {
+        ordered(attrs, self.options)
+            .into_iter()
+            .map(|attr| self.data_element(domain, attr))
+            .collect()
+    }
+}
+
+impl<'a, N, E, S, G> SubgraphVisitor<'a, N, E, S> for Writer<'a, '_, G>
+where
+    N: Clone + 'a,
+    E: Clone + 'a,
+    S: Clone + 'a,
+    G: Labeller<'a, Node = N, Edge = E, Subgraph = S>
+        + GraphWalk<'a, Node = N, Edge = E, Subgraph = S>,
+{
+    type Error = RenderError;
+
+    /// Writes the subgraph `s` as a node that holds a nested graph.
+    fn enter(
+        &mut self,
+        s: &S,
+        depth: usize,
+        _: &Attrs<'a>,
+        _: &Attrs<'a>,
+    ) -> Result<usize, RenderError> {
+        let g = self.graph;
+        let id = g.subgraph_id(s);
+        let base = id.as_ref().map_or("subgraph", |id| &id[..]);
+        let mut name = base.to_owned();
+        let mut i = 1;
+        while self.used.contains(&name) {
+            name = format!("{base}_{i}");
+            i += 1;
+        }
+        self.used.insert(name.clone());
+
+        let name = escape(&name);
+        self.lines.push((depth, format!("<node id=\"{name}\">")));
+        self.lines.push((
+            depth + 1,
+            format!(
+                "<graph id=\"{name}:\" edgedefault=\"{}\">",
+                edge_default(g.kind())
+            ),
+        ));
+        let mut attrs = resolve_subgraph_attrs(g, &self.stylesheet, s, self.options);
+        if let Some(Attr::Label(label)) = attrs.get("label") {
+            if label.is_empty() {
+                attrs.remove("label");
+            }
+        }
+        self.data(depth + 2, "graph", &attrs);
+        Ok(depth + 2)
+    }
+
+    fn visit_node(&mut self, n: &N, depth: usize, defaults: &Attrs<'a>) -> Result<(), RenderError> {
+        let g = self.graph;
+        let id = g.node_id(n);
+        if !self.written.insert(id.clone()) {
+            return Ok(());
+        }
+        let mut attrs = defaults.clone();
+        attrs.extend(resolve_node_attrs(g, &self.stylesheet, n, self.options));
+
+        let mut content = String::new();
+        for port in self.ports.get(&id[..]).into_iter().flatten() {
+            content.push_str(&format!("<port name=\"{}\"/>", escape(port)));
+        }
+        content.push_str(&self.data_elements("node", &attrs));
+        let line = match content.is_empty() {
+            true => format!("<node id=\"{}\"/>", escape(&id)),
+            false => format!("<node id=\"{}\">{content}</node>", escape(&id)),
+        };
+        self.lines.push((depth, line));
+        Ok(())
+    }
+
+    fn visit_edge(&mut self, e: &E, depth: usize, defaults: &Attrs<'a>) -> Result<(), RenderError> {
+        let g = self.graph;
+        let mut attrs = defaults.clone();
+        attrs.extend(resolve_edge_attrs(g, &self.stylesheet, e, self.options));
+
+        let mut edge = format!(
+            "<edge source=\"{}\" target=\"{}\"",
+            escape(&g.node_id(&g.source(e))),
+            escape(&g.node_id(&g.target(e)))
+        );
+        let ports = [
+            ("sourceport", g.edge_start_port(e), g.edge_start_point(e)),
+            ("targetport", g.edge_end_port(e), g.edge_end_point(e)),
+        ];
+        for (name, port, compass) in ports {
+            if let Some(port) = port_name(port.as_ref(), compass) {
+                edge.push_str(&format!(" {name}=\"{}\"", escape(&port)));
+            }
+        }
+        let content = self.data_elements("edge", &attrs);
+        match content.is_empty() {
+            true => edge.push_str("/>"),
+            false => edge.push_str(&format!(">{content}</edge>")),
+        }
+        self.lines.push((depth, edge));
+        Ok(())
+    }
+
+    fn exit(&mut self, depth: usize, _: usize) -> Result<(), RenderError> {
+        self.lines.push((depth + 1, "</graph>".to_owned()));
+        self.lines.push((depth, "</node>".to_owned()));
+        Ok(())
+    }
+}
+
+/// The `edgedefault` of a graph of the kind `kind`.
+fn edge_default(kind: GraphKind) -> &'static str {
+    match kind {
+        GraphKind::Directed => "directed",
+        GraphKind::Undirected => "undirected",
+    }
+}
+
+/// The name of a GraphML port for the DOT port `port` and `compass`, such
+/// as `in:n`.
+fn port_name(port: Option<&Id<'_>>, compass: Option<CompassPoint>) -> Option<String> {
+    let compass = compass.map_or("", |compass| compass.as_static_str());
+    match port {
+        Some(port) => Some(format!("{}{compass}", &port[..])),
+        None => compass.strip_prefix(':').map(str::to_owned),
+    }
+}
+
+/// The value of `attr` as it appears in DOT, without quotes unless the
+/// value would then start like an HTML-like label or a quoted one.
+fn dot_value(attr: &Attr<'_>) -> String {
+    let value = attr.value_to_string();
+    let Some(quoted) = value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) else {
+        return value;
+    };
+    let out = unquote(quoted);
+    match out.starts_with(['<', '"']) {
+        true => value,
+        false => out,
+    }
+}
+
+/// Undoes the quoting of the DOT string `quoted`, given without its quotes,
+/// as the DOT parser does: `\"` becomes `"` and other escapes are kept.
+fn unquote(quoted: &str) -> String {
+    let mut out = String::with_capacity(quoted.len());
+    let mut chars = quoted.chars();
+    while let Some(c) = chars.next() {
+        match (c, chars.clone().next()) {
+            ('\\', Some('"')) => {}
+            ('\\', Some(next)) => {
+                out.push(c);
+                out.push(next);
+                chars.next();
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Escapes `s` for XML text or a quoted attribute value, keeping line
+/// breaks in character references so that each element stays on its line.
+fn escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\n' => out.push_str("&#10;"),
+            '\r' => out.push_str("&#13;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Parses the GraphML document `input` into a graph.
+///
+/// ```rust
+/// use dotwalk::graphml::{self, GraphmlErrorKind};
+/// use dotwalk::GraphKind;
+///
+/// let graph = graphml::parse(
+///     r#"<graphml>
+///         <key id="w" for="edge" attr.name="weight"/>
+///         <graph edgedefault="undirected">
+///             <node id="a"/>
+///             <node id="b"/>
+///             <edge source="a" target="b"><data key="w">2</data></edge>
+///         </graph>
+///     </graphml>"#,
+/// )
+/// .unwrap();
+/// assert_eq!(graph.kind, GraphKind::Undirected);
+/// assert_eq!(graph.edge_count(), 1);
+///
+/// let err = graphml::parse("<graphml>\n<graph><edge source=\"a\" target=\"b\"/></graph>\n</graphml>")
+///     .unwrap_err();
+/// assert_eq!(err.kind, GraphmlErrorKind::UnknownNode("a".to_owned()));
+/// assert_eq!(err.to_string(), "2:8: edge to unknown node a");
+/// ```
+pub fn parse(input: &str) -> Result<DotGraph, GraphmlError> {
+    let mut xml = Xml { input, pos: 0 };
+    let root = xml.document()?;
+    let error = |offset, kind| xml.error(offset, kind);
+    if root.name != "graphml" {
+        return Err(error(root.offset, GraphmlErrorKind::Missing("graphml")));
+    }
+
+    let mut keys = Vec::new();
+    for key in root.elements().filter(|el| el.name == "key") {
+        let id = key.required("id").map_err(|kind| error(key.offset, kind))?;
+        let domain = key.attr("for").unwrap_or("all");
+        let name = key.attr("attr.name").unwrap_or(id);
+        let default = key
+            .elements()
+            .find(|el| el.name == "default")
+            .map(Element::text);
+        keys.push((id, domain, name, default));
+    }
+    let Some(graph) = root.elements().find(|el| el.name == "graph") else {
+        return Err(error(root.offset, GraphmlErrorKind::Missing("graph")));
+    };
+
+    let kind = match graph.attr("edgedefault") {
+        None | Some("directed") => GraphKind::Directed,
+        Some("undirected") => GraphKind::Undirected,
+        Some(value) => {
+            let kind = GraphmlErrorKind::InvalidValue {
+                attr: "edgedefault",
+                value: value.to_owned(),
+            };
+            return Err(error(graph.offset, kind));
+        }
+    };
+    let id = graph.attr("id").and_then(|id| to_id(id).ok());
+    let mut reader = Reader {
+        xml: &xml,
+        keys,
+        graph: DotGraph::new(id, kind),
+        subgraphs: HashSet::new(),
+        edges: Vec::new(),
+    };
+    for (name, value) in reader.data(graph, "graph")? {
+        reader
+            .graph
+            .attrs
+            .set(Attr::parse(&name, value).into_owned());
+    }
+    reader.graph(graph, &mut Vec::new())?;
+    for (edge, stack) in std::mem::take(&mut reader.edges) {
+        reader.edge(edge, &stack)?;
+    }
+    Ok(reader.graph)
+}
+
+/// Error returned by [`parse`], located in the parsed document.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GraphmlError {
+    pub kind: GraphmlErrorKind,
+    /// The line of the error, starting from 1.
+    pub line: usize,
+    /// The column of the error in characters, starting from 1.
+    pub column: usize,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum GraphmlErrorKind {
+    /// XML that is not well-formed, such as an unquoted attribute value.
+    Syntax(&'static str),
+    /// A closing tag that does not match the element it closes.
+    MismatchedTag { open: String, close: String },
+    /// A document without this element, such as a `<graphml>` without a
+    /// `<graph>`.
+    Missing(&'static str),
+    /// An `element` without the attribute `attr` that it needs.
+    MissingAttribute { element: String, attr: &'static str },
+    /// An attribute with a value that GraphML does not allow.
+    InvalidValue { attr: &'static str, value: String },
+    /// A node id that cannot be a DOT id, as it holds a NUL character.
+    InvalidId(String),
+    /// A node id that is given to more than one node.
+    DuplicateNode(String),
+    /// A `<data>` element for a key that is not declared.
+    UnknownKey(String),
+    /// An edge to a node that is not in the document.
+    UnknownNode(String),
+    /// An edge to a node that holds a nested graph.
+    SubgraphEdge(String),
+    /// A `<hyperedge>`.
+    Hyperedge,
+}
+
+impl std::error::Error for GraphmlError {}
+
+impl std::fmt::Display for GraphmlError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}: ", self.line, self.column)?;
+        match &self.kind {
+            GraphmlErrorKind::Syntax(s) => f.write_str(s),
+            GraphmlErrorKind::MismatchedTag { open, close } => {
+                write!(f, "expected </{open}>, found </{close}>")
+            }
+            GraphmlErrorKind::Missing(element) => write!(f, "no <{element}> element"),
+            GraphmlErrorKind::MissingAttribute { element, attr } => {
+                write!(f, "<{element}> has no {attr} attribute")
+            }
+            GraphmlErrorKind::InvalidValue { attr, value } => {
+                write!(f, "invalid value '{value}' for {attr}")
+            }
+            GraphmlErrorKind::InvalidId(id) => write!(f, "invalid node id '{id}'"),
+            GraphmlErrorKind::DuplicateNode(id) => write!(f, "node {id} is given more than once"),
+            GraphmlErrorKind::UnknownKey(key) => write!(f, "undeclared key {key}"),
+            GraphmlErrorKind::UnknownNode(id) => write!(f, "edge to unknown node {id}"),
+            GraphmlErrorKind::SubgraphEdge(id) => {
+                write!(f, "edge to node {id}, which holds a nested graph")
+            }
+            GraphmlErrorKind::Hyperedge => f.write_str("hyperedges are not supported"),
+        }
+    }
+}
+
+/// Builds a [`DotGraph`] from the elements of a GraphML document.
+struct Reader<'x, 's> {
+    xml: &'x Xml<'s>,
+    /// The id, domain, attribute name and default of each key.
+    keys: Vec<(&'x str, &'x str, &'x str, Option<String>)>,
+    graph: DotGraph,
+    /// The ids of the nodes that hold nested graphs.
+    subgraphs: HashSet<&'x str>,
+    /// The edges, with the subgraphs that enclose them, which are added
+    /// once all the nodes are known.
+    edges: Vec<(&'x Element, Vec<SubgraphIndex>)>,
+}
+
+impl<'x> Reader<'x, '_> {
+    fn error(&self, el: &Element, kind: GraphmlErrorKind) -> GraphmlError {
+        self.xml.error(el.offset, kind)
+    }
+
+    /// The attributes that the `<data>` of `el` and the defaults of the keys
+    /// give, for `el` in `domain`, with yEd's label if there is no other.
+    fn data(
+        &self,
+        el: &Element,
+        domain: &str,
+    ) -> Result<Vec<(String, Text<'static>)>, GraphmlError> {
+        let mut values: Vec<(String, Text<'static>)> = Vec::new();
+        let mut set = |name: &str, value: &str| {
+            let html = value.strip_prefix('<').and_then(|v| v.strip_suffix('>'));
+            let quoted = value.strip_prefix('"').and_then(|v| v.strip_suffix('"'));
+            let text = match (html, quoted) {
+                (Some(html), _) => Text::html(html.to_owned()),
+                (None, Some(quoted)) => Text::esc(unquote(quoted)),
+                (None, None) => Text::esc(value.to_owned()),
+            };
+            values.retain(|(n, _)| n != name);
+            values.push((name.to_owned(), text));
+        };
+
+        for (_, for_, name, default) in &self.keys {
+            if let Some(default) = default
+                .as_deref()
+                .filter(|_| [domain, "all"].contains(for_))
+            {
+                set(name, default);
+            }
+        }
+        let mut yed_label = None;
+        for data in el.elements().filter(|el| el.name == "data") {
+            let key = data
+                .required("key")
+                .map_err(|kind| self.error(data, kind))?;
+            let Some((_, _, name, _)) = self.keys.iter().find(|(id, ..)| *id == key) else {
+                return Err(self.error(data, GraphmlErrorKind::UnknownKey(key.to_owned())));
+            };
+            match data.elements().next() {
+                None => set(name, &data.text()),
+                Some(_) => {
+                    yed_label = yed_label.or_else(|| {
+                        data.descendants()
+                            .find(|el| el.name == "NodeLabel" || el.name == "EdgeLabel")
+                            .map(|el| el.text().trim().to_owned())
+                    });
+                }
+            }
+        }
+        if let Some(label) = yed_label.filter(|label| !label.is_empty()) {
+            // yEd labels are plain text, not DOT values.
+            if values.iter().all(|(name, _)| name != "label") {
+                values.push(("label".to_owned(), Text::label(label)));
+            }
+        }
+        Ok(values)
+    }
+
+    /// Reads the nodes of the graph `el`, and saves its edges for later,
+    /// within the subgraphs `stack`.
+    fn graph(
+        &mut self,
+        el: &'x Element,
+        stack: &mut Vec<SubgraphIndex>,
+    ) -> Result<(), GraphmlError> {
+        for child in el.elements() {
+            match &child.name[..] {
+                "node" => self.node(child, stack)?,
+                "edge" => self.edges.push((child, stack.clone())),
+                "hyperedge" => return Err(self.error(child, GraphmlErrorKind::Hyperedge)),
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads the node `el`, as a subgraph if it holds a nested graph.
+    fn node(
+        &mut self,
+        el: &'x Element,
+        stack: &mut Vec<SubgraphIndex>,
+    ) -> Result<(), GraphmlError> {
+        let name = el.required("id").map_err(|kind| self.error(el, kind))?;
+        let id = to_id(name)
+            .map_err(|_| self.error(el, GraphmlErrorKind::InvalidId(name.to_owned())))?;
+        if self.graph.find_node(name).is_some() || self.subgraphs.contains(name) {
+            return Err(self.error(el, GraphmlErrorKind::DuplicateNode(name.to_owned())));
+        }
+
+        if let Some(nested) = el.elements().find(|el| el.name == "graph") {
+            self.subgraphs.insert(name);
+            let mut subgraph = Subgraph::new(Some(id));
+            for (name, value) in self.data(el, "node")? {
+                if name == "label" {
+                    subgraph.label = Some(value);
+                }
+            }
+            for (name, value) in self.data(nested, "graph")? {
+                set_parsed(&mut subgraph.label, &mut subgraph.attrs, &name, value);
+            }
+            let index = self.graph.add_subgraph(subgraph);
+            if let Some(&parent) = stack.last() {
+                self.graph.subgraph_mut(parent).subgraphs.push(index);
+            }
+            stack.push(index);
+            self.graph(nested, stack)?;
+            stack.pop();
+            return Ok(());
+        }
+
+        let mut node = Node::new(id);
+        for (name, value) in self.data(el, "node")? {
+            set_parsed(&mut node.label, &mut node.attrs, &name, value);
+        }
+        let index = self.graph.add_node(node);
+        for &s in stack.iter() {
+            self.graph.subgraph_mut(s).nodes.push(index);
+        }
+        Ok(())
+    }
+
+    /// Adds the edge `el`, within the subgraphs `stack`.
+    fn edge(&mut self, el: &Element, stack: &[SubgraphIndex]) -> Result<(), GraphmlError> {
+        let endpoint = |attr| -> Result<NodeIndex, GraphmlError> {
+            let id = el.required(attr).map_err(|kind| self.error(el, kind))?;
+            self.graph.find_node(id).ok_or_else(|| {
+                let kind = match self.subgraphs.contains(id) {
+                    true => GraphmlErrorKind::SubgraphEdge(id.to_owned()),
+                    false => GraphmlErrorKind::UnknownNode(id.to_owned()),
+                };
+                self.error(el, kind)
+            })
+        };
+        let (source, target) = (endpoint("source")?, endpoint("target")?);
+        let (source_port, source_compass) = port(el.attr("sourceport"));
+        let (target_port, target_compass) = port(el.attr("targetport"));
+        let mut edge = Edge::new(source, target)
+            .with_source_port(source_port, source_compass)
+            .with_target_port(target_port, target_compass);
+
+        let directed = match el.attr("directed") {
+            None => self.graph.kind == GraphKind::Directed,
+            Some("true") => true,
+            Some("false") => false,
+            Some(value) => {
+                let kind = GraphmlErrorKind::InvalidValue {
+                    attr: "directed",
+                    value: value.to_owned(),
+                };
+                return Err(self.error(el, kind));
+            }
+        };
+        match (self.graph.kind, directed) {
+            (GraphKind::Directed, false) => edge.attrs.set(Attr::Dir(Dir::None)),
+            (GraphKind::Undirected, true) => edge.attrs.set(Attr::Dir(Dir::Forward)),
+            _ => None,
+        };
+        for (name, value) in self.data(el, "edge")? {
+            set_parsed(&mut edge.label, &mut edge.attrs, &name, value);
+        }
+
+        let index = self.graph.add_edge(edge);
+        for &s in stack {
+            self.graph.subgraph_mut(s).edges.push(index);
+        }
+        Ok(())
+    }
+}
+
+/// The DOT id for the GraphML id `name`.
+fn to_id(name: &str) -> Result<Id<'static>, crate::IdError> {
+    Id::new(name.to_owned()).or_else(|_| Id::quoted(name.to_owned()))
+}
+
+/// The DOT port and compass point for the GraphML port `name`, which may
+/// end in a compass point, such as `in:n`.
+fn port(name: Option<&str>) -> (Option<Id<'static>>, Option<CompassPoint>) {
+    let Some(name) = name else {
+        return (None, None);
+    };
+    if let Some(compass) = compass_point(name) {
+        return (None, Some(compass));
+    }
+    match name.rsplit_once(':') {
+        Some((port, compass)) if compass_point(compass).is_some() => {
+            (to_id(port).ok(), compass_point(compass))
+        }
+        _ => (to_id(name).ok(), None),
+    }
+}
+
+/// An XML element, named without its namespace prefix.
+struct Element {
+    name: String,
+    attrs: Vec<(String, String)>,
+    children: Vec<Content>,
+    /// The offset of the start tag in the document.
+    offset: usize,
+}
+
+enum Content {
+    Element(Element),
+    Text(String),
+}
+
+impl Element {
+    fn attr(&self, name: &str) -> Option<&str> {
+        self.attrs
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, value)| &value[..])
+    }
+
+    /// The attribute `attr`, which the element must have.
+    fn required(&self, attr: &'static str) -> Result<&str, GraphmlErrorKind> {
+        self.attr(attr)
+            .ok_or_else(|| GraphmlErrorKind::MissingAttribute {
+                element: self.name.clone(),
+                attr,
+            })
+    }
+
+    fn elements(&self) -> impl Iterator<Item = &Element> {
+        self.children.iter().filter_map(|child| match child {
+            Content::Element(el) => Some(el),
+            Content::Text(_) => None,
+        })
+    }
+
+    /// The elements within this one, at any depth.
+    fn descendants(&self) -> Box<dyn Iterator<Item = &Element> + '_> {
+        Box::new(
+            self.elements()
+                .flat_map(|el| std::iter::once(el).chain(el.descendants())),
+        )
+    }
+
+    /// The text within the element, at any depth.
+    fn text(&self) -> String {
+        let mut out = String::new();
+        for child in &self.children {
+            match child {
+                Content::Element(el) => out.push_str(&el.text()),
+                Content::Text(text) => out.push_str(text),
+            }
+        }
+        out
+    }
+}
+
+/// A parser for the elements, attributes and text of an XML document,
+/// which skips the prolog, comments and processing instructions.
+struct Xml<'s> {
+    input: &'s str,
+    pos: usize,
+}
+
+impl<'s> Xml<'s> {
+    fn error(&self, offset: usize, kind: GraphmlErrorKind) -> GraphmlError {
+        let before = &self.input[..offset];
+        let line = before.matches('\n').count() + 1;
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        let column = before[line_start..].chars().count() + 1;
+        GraphmlError { kind, line, column }
+    }
+
+    fn rest(&self) -> &'s str {
+        &self.input[self.pos..]
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    /// Skips past `end`, failing with `message` if it is missing.
+    fn skip_past(&mut self, end: &str, message: &'static str) -> Result<(), GraphmlError> {
+        match self.rest().find(end) {
+            Some(i) => {
+                self.pos += i + end.len();
+                Ok(())
+            }
+            None => Err(self.error(self.pos, GraphmlErrorKind::Syntax(message))),
+        }
+    }
+
+    /// Skips a comment, processing instruction or declaration, returning
+    /// `false` if there is none.
+    fn skip_misc(&mut self) -> Result<bool, GraphmlError> {
+        let rest = self.rest();
+        if rest.starts_with("<!--") {
+            self.skip_past("-->", "unterminated comment")?;
+        } else if rest.starts_with("<?") {
+            self.skip_past("?>", "unterminated processing instruction")?;
+        } else if rest.starts_with("<!DOCTYPE") {
+            let end = rest.find('>').unwrap_or(rest.len());
+            match rest[..end].contains('[') {
+                true => self.skip_past("]>", "unterminated DOCTYPE")?,
+                false => self.skip_past(">", "unterminated DOCTYPE")?,
+            }
+        } else {
+            return Ok(false);
+        }
+        Ok(true)
+    }
+
+    /// Parses the root element of the document.
+    fn document(&mut self) -> Result<Element, GraphmlError> {
+        self.input = self.input.strip_prefix('\u{feff}').unwrap_or(self.input);
+        loop {
+            self.skip_whitespace();
+            if !self.skip_misc()? {
+                break;
+            }
+        }
+        if !self.rest().starts_with('<') {
+            return Err(self.error(self.pos, GraphmlErrorKind::Syntax("expected an element")));
+        }
+        let root = self.element()?;
+        loop {
+            self.skip_whitespace();
+            if !self.skip_misc()? {
+                break;
+            }
+        }
+        match self.rest().is_empty() {
+            true => Ok(root),
+            false => Err(self.error(
+                self.pos,
+                GraphmlErrorKind::Syntax("expected the end of the document"),
+            )),
+        }
+    }
+
+    /// Consumes a tag or attribute name.
+    fn name(&mut self) -> Result<&'s str, GraphmlError> {
+        let rest = self.rest();
+        let len = rest
+            .find(|c: char| c.is_whitespace() || "=/>\"'<".contains(c))
+            .unwrap_or(rest.len());
+        if len == 0 {
+            return Err(self.error(self.pos, GraphmlErrorKind::Syntax("expected a name")));
+        }
+        self.pos += len;
+        Ok(&rest[..len])
+    }
+
+    /// Consumes `c`, or fails with `message`.
+    fn expect(&mut self, c: char, message: &'static str) -> Result<(), GraphmlError> {
+        self.skip_whitespace();
+        match self.rest().starts_with(c) {
+            true => {
+                self.pos += c.len_utf8();
+                Ok(())
+            }
+            false => Err(self.error(self.pos, GraphmlErrorKind::Syntax(message))),
+        }
+    }
+
+    /// Parses an element, starting at its `<`.
+    fn element(&mut self) -> Result<Element, GraphmlError> {
+        let offset = self.pos;
+        self.pos += 1;
+        let tag = self.name()?;
+        let mut el = Element {
+            name: local_name(tag).to_owned(),
+            attrs: Vec::new(),
+            children: Vec::new(),
+            offset,
+        };
+        loop {
+            self.skip_whitespace();
+            let rest = self.rest();
+            if rest.starts_with("/>") {
+                self.pos += 2;
+                return Ok(el);
+            }
+            if rest.starts_with('>') {
+                self.pos += 1;
+                break;
+            }
+            let name = self.name()?;
+            self.expect('=', "expected '=' after the attribute name")?;
+            self.skip_whitespace();
+            let rest = self.rest();
+            let Some(quote) = rest.chars().next().filter(|c| *c == '"' || *c == '\'') else {
+                return Err(self.error(
+                    self.pos,
+                    GraphmlErrorKind::Syntax("attribute values must be quoted"),
+                ));
+            };
+            let Some(len) = rest[1..].find(quote) else {
+                return Err(self.error(
+                    self.pos,
+                    GraphmlErrorKind::Syntax("unterminated attribute value"),
+                ));
+            };
+            el.attrs
+                .push((name.to_owned(), decode_entities(&rest[1..1 + len])));
+            self.pos += len + 2;
+        }
+
+        loop {
+            let rest = self.rest();
+            if rest.is_empty() {
+                return Err(self.error(offset, GraphmlErrorKind::Syntax("unclosed element")));
+            }
+            if let Some(cdata) = rest.strip_prefix("<![CDATA[") {
+                let Some(end) = cdata.find("]]>") else {
+                    return Err(
+                        self.error(self.pos, GraphmlErrorKind::Syntax("unterminated CDATA"))
+                    );
+                };
+                el.children.push(Content::Text(cdata[..end].to_owned()));
+                self.pos += 9 + end + 3;
+            } else if rest.starts_with("</") {
+                let close_offset = self.pos;
+                self.pos += 2;
+                let close = self.name()?;
+                self.expect('>', "expected '>'")?;
+                if close != tag {
+                    let kind = GraphmlErrorKind::MismatchedTag {
+                        open: tag.to_owned(),
+                        close: close.to_owned(),
+                    };
+                    return Err(self.error(close_offset, kind));
+                }
+                return Ok(el);
+            } else if self.skip_misc()? {
+                continue;
+            } else if rest.starts_with('<') {
+                el.children.push(Content::Element(self.element()?));
+            } else {
+                let len = rest.find('<').unwrap_or(rest.len());
+                el.children
+                    .push(Content::Text(decode_entities(&rest[..len])));
+                self.pos += len;
+            }
+        }
+    }
+}
+
+/// The name `name` without its namespace prefix.
+fn local_name(name: &str) -> &str {
+    name.rsplit_once(':').map_or(name, |(_, local)| local)
+}
@@ -0,0 +1,1300 @@
+//! Typed builders for HTML-like labels.
+//!
+//! An [`HtmlLabel`] is either formatted text, built with [`HtmlText`], or a
+//! [`Table`] of [`Row`]s and [`Cell`]s, following the
+//! [grammar](https://www.graphviz.org/doc/info/shapes.html#html) of Graphviz
+//! HTML-like labels. Text and attribute values are escaped, and only
+//! well-formed labels can be built: a table with no rows, or a row with no
+//! cells, is given an empty cell, and rules between rows or cells are only
+//! written where the grammar allows them.
+//!
+//! A label converts into a [`Text::Html`]. Labels written by hand can be
+//! checked against the same grammar with [`check`], which
+//! [`validate`](crate::validate()) does for every HTML-like label of a graph.
+//!
+//! ```rust
+//! use dotwalk::html::{Cell, HtmlLabel, HtmlText, Row, Table};
+//! use dotwalk::{Color, Id, Text};
+//!
+//! let table = Table::new()
+//!     .with_border(0)
+//!     .with_cell_border(1)
+//!     .with_row(Row::new().with_cell(
+//!         Cell::new(HtmlText::new().with_bold("bb0")).with_colspan(2),
+//!     ))
+//!     .with_row(
+//!         Row::new()
+//!             .with_cell(Cell::new("_1 = &_2").with_port(Id::new("s0").unwrap()))
+//!             .with_cell(Cell::new("a < b").with_bgcolor(Color::named("yellow"))),
+//!     );
+//! assert_eq!(
+//!     Text::from(HtmlLabel::from(table)).to_escaped_string(),
+//!     concat!(
+//!         r#"<<TABLE BORDER="0" CELLBORDER="1">"#,
+//!         r#"<TR><TD COLSPAN="2"><B>bb0</B></TD></TR>"#,
+//!         r#"<TR><TD PORT="s0">_1 = &amp;_2</TD><TD BGCOLOR="yellow">a &lt; b</TD></TR>"#,
+//!         r#"</TABLE>>"#,
+//!     )
+//! );
+//! ```
+
+use std::borrow::Cow;
+use std::collections::VecDeque;
+use std::fmt::{self, Write as _};
+
+use crate::{Color, Id, Text};
+
+/// Escapes `s` for use as text or as an attribute value in an HTML-like
+/// label.
+fn escape(s: &str, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    for c in s.chars() {
+        match c {
+            '&' => f.write_str("&amp;")?,
+            '<' => f.write_str("&lt;")?,
+            '>' => f.write_str("&gt;")?,
+            '"' => f.write_str("&quot;")?,
+            c => f.write_char(c)?,
+        }
+    }
+    Ok(())
+}
+
+/// Horizontal alignment.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Align {
+    Left,
+    Center,
+    Right,
+}
+
+impl Align {
+    pub const fn as_static_str(self) -> &'static str {
+        match self {
+            Align::Left => "LEFT",
+            Align::Center => "CENTER",
+            Align::Right => "RIGHT",
+        }
+    }
+}
+
+/// Vertical alignment.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum VAlign {
+    Top,
+    Middle,
+    Bottom,
+}
+
+impl VAlign {
+    pub const fn as_static_str(self) -> &'static str {
+        match self {
+            VAlign::Top => "TOP",
+            VAlign::Middle => "MIDDLE",
+            VAlign::Bottom => "BOTTOM",
+        }
+    }
+}
+
+/// The attributes of an element, in the order they were first set.
+#[derive(Clone, Debug, Default, PartialEq)]
+struct ElementAttrs<'a>(Vec<(&'static str, Cow<'a, str>)>);
+
+impl<'a> ElementAttrs<'a> {
+    fn set(&mut self, name: &'static str, value: impl Into<Cow<'a, str>>) {
+        let value = value.into();
+        match self.0.iter_mut().find(|(n, _)| *n == name) {
+            Some((_, v)) => *v = value,
+            None => self.0.push((name, value)),
+        }
+    }
+
+    fn into_owned(self) -> ElementAttrs<'static> {
+        ElementAttrs(
+            self.0
+                .into_iter()
+                .map(|(name, value)| (name, Cow::Owned(value.into_owned())))
+                .collect(),
+        )
+    }
+}
+
+impl fmt::Display for ElementAttrs<'_> {
+    /// Writes the attributes with a leading space each.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (name, value) in &self.0 {
+            write!(f, " {name}=\"")?;
+            escape(value, f)?;
+            f.write_str("\"")?;
+        }
+        Ok(())
+    }
+}
+
+/// The font of some text or of a table, written as a `<FONT>` element.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Font<'a> {
+    attrs: ElementAttrs<'a>,
+}
+
+impl<'a> Font<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_color(mut self, color: Color<'a>) -> Self {
+        self.attrs.set("COLOR", color.to_string());
+        self
+    }
+
+    /// The name of the font, such as `"Helvetica"`.
+    pub fn with_face(mut self, face: impl Into<Cow<'a, str>>) -> Self {
+        self.attrs.set("FACE", face);
+        self
+    }
+
+    /// The size of the font in points.
+    pub fn with_point_size(mut self, size: f64) -> Self {
+        self.attrs.set("POINT-SIZE", size.to_string());
+        self
+    }
+
+    pub fn into_owned(self) -> Font<'static> {
+        Font {
+            attrs: self.attrs.into_owned(),
+        }
+    }
+}
+
+/// The formatting elements that can enclose text.
+#[derive(Clone, Debug, PartialEq)]
+enum TextStyle<'a> {
+    Font(Font<'a>),
+    Bold,
+    Italic,
+    Underline,
+    Overline,
+    Subscript,
+    Superscript,
+    Strikethrough,
+}
+
+impl TextStyle<'_> {
+    fn tag(&self) -> &'static str {
+        match self {
+            TextStyle::Font(_) => "FONT",
+            TextStyle::Bold => "B",
+            TextStyle::Italic => "I",
+            TextStyle::Underline => "U",
+            TextStyle::Overline => "O",
+            TextStyle::Subscript => "SUB",
+            TextStyle::Superscript => "SUP",
+            TextStyle::Strikethrough => "S",
+        }
+    }
+
+    fn into_owned(self) -> TextStyle<'static> {
+        match self {
+            TextStyle::Font(font) => TextStyle::Font(font.into_owned()),
+            TextStyle::Bold => TextStyle::Bold,
+            TextStyle::Italic => TextStyle::Italic,
+            TextStyle::Underline => TextStyle::Underline,
+            TextStyle::Overline => TextStyle::Overline,
+            TextStyle::Subscript => TextStyle::Subscript,
+            TextStyle::Superscript => TextStyle::Superscript,
+            TextStyle::Strikethrough => TextStyle::Strikethrough,
+        }
+    }
+
+    fn open(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TextStyle::Font(font) => write!(f, "<FONT{}>", font.attrs),
+            style => write!(f, "<{}>", style.tag()),
+        }
+    }
+
+    fn close(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "</{}>", self.tag())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum TextItem<'a> {
+    Str(Cow<'a, str>),
+    Break(Option<Align>),
+    Styled(TextStyle<'a>, HtmlText<'a>),
+}
+
+/// Formatted text: strings, line breaks and text in a font or style.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct HtmlText<'a> {
+    items: Vec<TextItem<'a>>,
+}
+
+impl<'a> HtmlText<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the string `s`, which is escaped.
+    pub fn with_str(mut self, s: impl Into<Cow<'a, str>>) -> Self {
+        self.items.push(TextItem::Str(s.into()));
+        self
+    }
+
+    /// Adds a line break, which aligns the line before it by `align`, or by
+    /// the `BALIGN` of the cell if `None`.
+    pub fn with_break(mut self, align: Option<Align>) -> Self {
+        self.items.push(TextItem::Break(align));
+        self
+    }
+
+    fn with_style(mut self, style: TextStyle<'a>, text: impl Into<HtmlText<'a>>) -> Self {
+        self.items.push(TextItem::Styled(style, text.into()));
+        self
+    }
+
+    /// Adds `text` in the font `font`.
+    pub fn with_font(self, font: Font<'a>, text: impl Into<HtmlText<'a>>) -> Self {
+        self.with_style(TextStyle::Font(font), text)
+    }
+
+    pub fn with_bold(self, text: impl Into<HtmlText<'a>>) -> Self {
+        self.with_style(TextStyle::Bold, text)
+    }
+
+    pub fn with_italic(self, text: impl Into<HtmlText<'a>>) -> Self {
+        self.with_style(TextStyle::Italic, text)
+    }
+
+    pub fn with_underline(self, text: impl Into<HtmlText<'a>>) -> Self {
+        self.with_style(TextStyle::Underline, text)
+    }
+
+    pub fn with_overline(self, text: impl Into<HtmlText<'a>>) -> Self {
+        self.with_style(TextStyle::Overline, text)
+    }
+
+    pub fn with_subscript(self, text: impl Into<HtmlText<'a>>) -> Self {
+        self.with_style(TextStyle::Subscript, text)
+    }
+
+    pub fn with_superscript(self, text: impl Into<HtmlText<'a>>) -> Self {
+        self.with_style(TextStyle::Superscript, text)
+    }
+
+    pub fn with_strikethrough(self, text: impl Into<HtmlText<'a>>) -> Self {
+        self.with_style(TextStyle::Strikethrough, text)
+    }
+
+    /// Adds the lines of `s`, each ended by a line break aligned by
+    /// `align`, as [`escape_html`](crate::escape_html) does with left
+    /// alignment.
+    pub fn with_lines(mut self, s: &str, align: Option<Align>) -> Self {
+        for line in s.lines() {
+            self = self.with_str(Cow::Owned(line.to_owned())).with_break(align);
+        }
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn into_owned(self) -> HtmlText<'static> {
+        HtmlText {
+            items: self
+                .items
+                .into_iter()
+                .map(|item| match item {
+                    TextItem::Str(s) => TextItem::Str(Cow::Owned(s.into_owned())),
+                    TextItem::Break(align) => TextItem::Break(align),
+                    TextItem::Styled(style, text) => {
+                        TextItem::Styled(style.into_owned(), text.into_owned())
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+impl<'a> From<&'a str> for HtmlText<'a> {
+    fn from(s: &'a str) -> Self {
+        HtmlText::new().with_str(s)
+    }
+}
+
+impl From<String> for HtmlText<'_> {
+    fn from(s: String) -> Self {
+        HtmlText::new().with_str(s)
+    }
+}
+
+impl fmt::Display for HtmlText<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for item in &self.items {
+            match item {
+                TextItem::Str(s) => escape(s, f)?,
+                TextItem::Break(None) => f.write_str("<BR/>")?,
+                TextItem::Break(Some(align)) => {
+                    write!(f, "<BR ALIGN=\"{}\"/>", align.as_static_str())?
+                }
+                TextItem::Styled(style, text) => {
+                    style.open(f)?;
+                    write!(f, "{text}")?;
+                    style.close(f)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Implements the `with_*` methods for the attributes that tables and
+/// cells share.
+macro_rules! impl_shared_attrs {
+    ( $($ty:ident)+ ) => {
+        $(
+            impl<'a> $ty<'a> {
+                pub fn with_align(mut self, align: Align) -> Self {
+                    self.attrs.set("ALIGN", align.as_static_str());
+                    self
+                }
+
+                pub fn with_valign(mut self, valign: VAlign) -> Self {
+                    self.attrs.set("VALIGN", valign.as_static_str());
+                    self
+                }
+
+                /// The background color, or a gradient for a color list.
+                pub fn with_bgcolor(mut self, color: Color<'a>) -> Self {
+                    self.attrs.set("BGCOLOR", color.to_string());
+                    self
+                }
+
+                /// The color of the border.
+                pub fn with_color(mut self, color: Color<'a>) -> Self {
+                    self.attrs.set("COLOR", color.to_string());
+                    self
+                }
+
+                /// The width of the border in points.
+                pub fn with_border(mut self, border: u8) -> Self {
+                    self.attrs.set("BORDER", border.to_string());
+                    self
+                }
+
+                /// The space between the border and the content, in points.
+                pub fn with_cell_padding(mut self, padding: u8) -> Self {
+                    self.attrs.set("CELLPADDING", padding.to_string());
+                    self
+                }
+
+                /// The space between cells, in points.
+                pub fn with_cell_spacing(mut self, spacing: u8) -> Self {
+                    self.attrs.set("CELLSPACING", spacing.to_string());
+                    self
+                }
+
+                /// The minimum width in points.
+                pub fn with_width(mut self, width: u16) -> Self {
+                    self.attrs.set("WIDTH", width.to_string());
+                    self
+                }
+
+                /// The minimum height in points.
+                pub fn with_height(mut self, height: u16) -> Self {
+                    self.attrs.set("HEIGHT", height.to_string());
+                    self
+                }
+
+                /// Names the element as a port that edges can connect to.
+                pub fn with_port(mut self, port: Id<'a>) -> Self {
+                    self.attrs.set("PORT", port.name);
+                    self
+                }
+
+                pub fn with_tooltip(mut self, tooltip: impl Into<Cow<'a, str>>) -> Self {
+                    self.attrs.set("TOOLTIP", tooltip);
+                    self
+                }
+
+                pub fn with_href(mut self, href: impl Into<Cow<'a, str>>) -> Self {
+                    self.attrs.set("HREF", href);
+                    self
+                }
+            }
+        )+
+    };
+}
+
+impl_shared_attrs!(Table Cell);
+
+/// A `<TABLE>`, made up of rows, optionally in a font or style.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Table<'a> {
+    attrs: ElementAttrs<'a>,
+    styles: Vec<TextStyle<'a>>,
+    /// The rows, each with whether a rule is drawn above it.
+    rows: Vec<(bool, Row<'a>)>,
+    rule: bool,
+}
+
+impl<'a> Table<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The width of the border of each cell, unless the cell sets its own.
+    pub fn with_cell_border(mut self, border: u8) -> Self {
+        self.attrs.set("CELLBORDER", border.to_string());
+        self
+    }
+
+    /// Adds the row `row`.
+    pub fn with_row(mut self, row: Row<'a>) -> Self {
+        let rule = std::mem::take(&mut self.rule) && !self.rows.is_empty();
+        self.rows.push((rule, row));
+        self
+    }
+
+    /// Draws a horizontal rule, `<HR/>`, between the last row and the next
+    /// one.
+    pub fn with_rule(mut self) -> Self {
+        self.rule = true;
+        self
+    }
+
+    /// Writes the table in the font `font`.
+    pub fn with_font(mut self, font: Font<'a>) -> Self {
+        self.styles.push(TextStyle::Font(font));
+        self
+    }
+
+    pub fn with_bold(mut self) -> Self {
+        self.styles.push(TextStyle::Bold);
+        self
+    }
+
+    pub fn with_italic(mut self) -> Self {
+        self.styles.push(TextStyle::Italic);
+        self
+    }
+
+    pub fn with_underline(mut self) -> Self {
+        self.styles.push(TextStyle::Underline);
+        self
+    }
+
+    pub fn with_overline(mut self) -> Self {
+        self.styles.push(TextStyle::Overline);
+        self
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &Row<'a>> {
+        self.rows.iter().map(|(_, row)| row)
+    }
+
+    pub fn into_owned(self) -> Table<'static> {
+        Table {
+            attrs: self.attrs.into_owned(),
+            styles: self.styles.into_iter().map(TextStyle::into_owned).collect(),
+            rows: self
+                .rows
+                .into_iter()
+                .map(|(rule, row)| (rule, row.into_owned()))
+                .collect(),
+            rule: self.rule,
+        }
+    }
+}
+
+impl fmt::Display for Table<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for style in &self.styles {
+            style.open(f)?;
+        }
+        write!(f, "<TABLE{}>", self.attrs)?;
+        if self.rows.is_empty() {
+            write!(f, "{}", Row::new())?;
+        }
+        for (rule, row) in &self.rows {
+            if *rule {
+                f.write_str("<HR/>")?;
+            }
+            write!(f, "{row}")?;
+        }
+        f.write_str("</TABLE>")?;
+        for style in self.styles.iter().rev() {
+            style.close(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// A `<TR>` of cells.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Row<'a> {
+    /// The cells, each with whether a rule is drawn before it.
+    cells: Vec<(bool, Cell<'a>)>,
+    rule: bool,
+}
+
+impl<'a> Row<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_cell(mut self, cell: Cell<'a>) -> Self {
+        let rule = std::mem::take(&mut self.rule) && !self.cells.is_empty();
+        self.cells.push((rule, cell));
+        self
+    }
+
+    /// Draws a vertical rule, `<VR/>`, between the last cell and the next
+    /// one.
+    pub fn with_rule(mut self) -> Self {
+        self.rule = true;
+        self
+    }
+
+    pub fn cells(&self) -> impl Iterator<Item = &Cell<'a>> {
+        self.cells.iter().map(|(_, cell)| cell)
+    }
+
+    pub fn into_owned(self) -> Row<'static> {
+        Row {
+            cells: self
+                .cells
+                .into_iter()
+                .map(|(rule, cell)| (rule, cell.into_owned()))
+                .collect(),
+            rule: self.rule,
+        }
+    }
+}
+
+impl fmt::Display for Row<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("<TR>")?;
+        if self.cells.is_empty() {
+            write!(f, "{}", Cell::new(HtmlText::new()))?;
+        }
+        for (rule, cell) in &self.cells {
+            if *rule {
+                f.write_str("<VR/>")?;
+            }
+            write!(f, "{cell}")?;
+        }
+        f.write_str("</TR>")
+    }
+}
+
+/// The content of a [`Cell`].
+#[derive(Clone, Debug, PartialEq)]
+enum CellContent<'a> {
+    Label(HtmlLabel<'a>),
+    Image(Image<'a>),
+}
+
+/// A `<TD>`, holding a label, which may be a nested table, or an image.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Cell<'a> {
+    attrs: ElementAttrs<'a>,
+    content: CellContent<'a>,
+}
+
+impl<'a> Cell<'a> {
+    pub fn new(label: impl Into<HtmlLabel<'a>>) -> Self {
+        Cell {
+            attrs: ElementAttrs::default(),
+            content: CellContent::Label(label.into()),
+        }
+    }
+
+    /// A cell holding the image `image`.
+    pub fn image(image: Image<'a>) -> Self {
+        Cell {
+            attrs: ElementAttrs::default(),
+            content: CellContent::Image(image),
+        }
+    }
+
+    /// The number of columns that the cell spans.
+    pub fn with_colspan(mut self, colspan: u16) -> Self {
+        self.attrs.set("COLSPAN", colspan.max(1).to_string());
+        self
+    }
+
+    /// The number of rows that the cell spans.
+    pub fn with_rowspan(mut self, rowspan: u16) -> Self {
+        self.attrs.set("ROWSPAN", rowspan.max(1).to_string());
+        self
+    }
+
+    /// Aligns each line of text by the `ALIGN` of its line break, rather
+    /// than centering the text as a whole.
+    pub fn with_align_text(mut self) -> Self {
+        self.attrs.set("ALIGN", "TEXT");
+        self
+    }
+
+    /// The alignment of the lines whose line breaks do not set one.
+    pub fn with_balign(mut self, align: Align) -> Self {
+        self.attrs.set("BALIGN", align.as_static_str());
+        self
+    }
+
+    pub fn into_owned(self) -> Cell<'static> {
+        Cell {
+            attrs: self.attrs.into_owned(),
+            content: match self.content {
+                CellContent::Label(label) => CellContent::Label(label.into_owned()),
+                CellContent::Image(image) => CellContent::Image(image.into_owned()),
+            },
+        }
+    }
+}
+
+impl fmt::Display for Cell<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<TD{}>", self.attrs)?;
+        match &self.content {
+            CellContent::Label(label) => write!(f, "{label}")?,
+            CellContent::Image(image) => write!(f, "{image}")?,
+        }
+        f.write_str("</TD>")
+    }
+}
+
+/// An `<IMG>` in a cell.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Image<'a> {
+    attrs: ElementAttrs<'a>,
+}
+
+impl<'a> Image<'a> {
+    /// The image in the file `src`.
+    pub fn new(src: impl Into<Cow<'a, str>>) -> Self {
+        let mut attrs = ElementAttrs::default();
+        attrs.set("SRC", src);
+        Image { attrs }
+    }
+
+    /// Scales the image to fit the cell: `"FALSE"`, `"TRUE"`, `"WIDTH"`,
+    /// `"HEIGHT"` or `"BOTH"`.
+    pub fn with_scale(mut self, scale: &'static str) -> Self {
+        self.attrs.set("SCALE", scale);
+        self
+    }
+
+    pub fn into_owned(self) -> Image<'static> {
+        Image {
+            attrs: self.attrs.into_owned(),
+        }
+    }
+}
+
+impl fmt::Display for Image<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "<IMG{}/>", self.attrs)
+    }
+}
+
+/// An HTML-like label: formatted text or a table.
+#[derive(Clone, Debug, PartialEq)]
+pub enum HtmlLabel<'a> {
+    Text(HtmlText<'a>),
+    Table(Table<'a>),
+}
+
+impl HtmlLabel<'_> {
+    pub fn into_owned(self) -> HtmlLabel<'static> {
+        match self {
+            HtmlLabel::Text(text) => HtmlLabel::Text(text.into_owned()),
+            HtmlLabel::Table(table) => HtmlLabel::Table(table.into_owned()),
+        }
+    }
+}
+
+impl<'a> From<HtmlText<'a>> for HtmlLabel<'a> {
+    fn from(text: HtmlText<'a>) -> Self {
+        HtmlLabel::Text(text)
+    }
+}
+
+impl<'a> From<Table<'a>> for HtmlLabel<'a> {
+    fn from(table: Table<'a>) -> Self {
+        HtmlLabel::Table(table)
+    }
+}
+
+impl<'a> From<&'a str> for HtmlLabel<'a> {
+    fn from(s: &'a str) -> Self {
+        HtmlLabel::Text(s.into())
+    }
+}
+
+impl From<String> for HtmlLabel<'_> {
+    fn from(s: String) -> Self {
+        HtmlLabel::Text(s.into())
+    }
+}
+
+impl fmt::Display for HtmlLabel<'_> {
+    /// Writes the content of the label, without the enclosing `<` and `>`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HtmlLabel::Text(text) => write!(f, "{text}"),
+            HtmlLabel::Table(table) => write!(f, "{table}"),
+        }
+    }
+}
+
+impl<'a> From<HtmlLabel<'a>> for Text<'a> {
+    fn from(label: HtmlLabel<'a>) -> Self {
+        Text::Html(label.to_string().into())
+    }
+}
+
+/// The attributes of `<TABLE>`.
+const TABLE_ATTRS: &[&str] = &[
+    "ALIGN",
+    "BGCOLOR",
+    "BORDER",
+    "CELLBORDER",
+    "CELLPADDING",
+    "CELLSPACING",
+    "COLOR",
+    "COLUMNS",
+    "FIXEDSIZE",
+    "GRADIENTANGLE",
+    "HEIGHT",
+    "HREF",
+    "ID",
+    "PORT",
+    "ROWS",
+    "SIDES",
+    "STYLE",
+    "TARGET",
+    "TITLE",
+    "TOOLTIP",
+    "VALIGN",
+    "WIDTH",
+];
+
+/// The attributes of `<TD>`.
+const CELL_ATTRS: &[&str] = &[
+    "ALIGN",
+    "BALIGN",
+    "BGCOLOR",
+    "BORDER",
+    "CELLPADDING",
+    "CELLSPACING",
+    "COLOR",
+    "COLSPAN",
+    "FIXEDSIZE",
+    "GRADIENTANGLE",
+    "HEIGHT",
+    "HREF",
+    "ID",
+    "PORT",
+    "ROWSPAN",
+    "SIDES",
+    "STYLE",
+    "TARGET",
+    "TITLE",
+    "TOOLTIP",
+    "VALIGN",
+    "WIDTH",
+];
+
+/// The tags of HTML-like labels, each with the attributes it accepts.
+const TAGS: [(&str, &[&str]); 15] = [
+    ("TABLE", TABLE_ATTRS),
+    ("TR", &[]),
+    ("TD", CELL_ATTRS),
+    ("FONT", &["COLOR", "FACE", "POINT-SIZE"]),
+    ("B", &[]),
+    ("I", &[]),
+    ("U", &[]),
+    ("O", &[]),
+    ("SUB", &[]),
+    ("SUP", &[]),
+    ("S", &[]),
+    ("BR", &["ALIGN"]),
+    ("HR", &[]),
+    ("VR", &[]),
+    ("IMG", &["SCALE", "SRC"]),
+];
+
+/// Checks that `html`, the content of an HTML-like label without the
+/// enclosing `<` and `>`, follows the
+/// [grammar](https://www.graphviz.org/doc/info/shapes.html#html) of Graphviz:
+/// that it only uses the tags and attributes that Graphviz knows, and only
+/// nests elements where the grammar allows it, such as `<TD>` in `<TR>`.
+///
+/// Returns the values of the `PORT` attributes, in order, or the first
+/// problem found.
+///
+/// ```rust
+/// use dotwalk::html::{check, HtmlErrorKind};
+///
+/// let ports = check(r#"<TABLE><TR><TD PORT="in">a</TD><TD PORT="out">b</TD></TR></TABLE>"#);
+/// assert_eq!(ports, Ok(vec!["in".to_owned(), "out".to_owned()]));
+///
+/// let err = check("<TABLE>\n<TD>a</TD>\n</TABLE>").unwrap_err();
+/// assert_eq!((err.line, err.column), (2, 1));
+/// assert_eq!(
+///     err.kind,
+///     HtmlErrorKind::Misplaced { tag: "TD", parent: Some("TABLE") }
+/// );
+/// assert_eq!(err.to_string(), "2:1: <TD> is not allowed in <TABLE>");
+/// ```
+pub fn check(html: &str) -> Result<Vec<String>, HtmlError> {
+    let mut checker = Checker {
+        input: html,
+        pos: 0,
+        tokens: VecDeque::new(),
+        ports: Vec::new(),
+    };
+    checker.label(None, true)?;
+    Ok(checker.ports)
+}
+
+/// Error returned by [`check`], located in the checked string.
+#[derive(Clone, Debug, PartialEq)]
+pub struct HtmlError {
+    pub kind: HtmlErrorKind,
+    /// The line of the error, starting from 1.
+    pub line: usize,
+    /// The column of the error in characters, starting from 1.
+    pub column: usize,
+}
+
+/// The kind of an [`HtmlError`]. Tags are named in upper case, whatever
+/// their case in the checked string.
+#[derive(Clone, Debug, PartialEq)]
+pub enum HtmlErrorKind {
+    /// Markup that is not well-formed, such as an unquoted attribute value.
+    Syntax(&'static str),
+    /// A tag that is not part of the grammar, such as `<DIV>`.
+    UnknownTag(String),
+    /// An attribute that `tag` does not accept.
+    UnknownAttribute { tag: &'static str, attr: String },
+    /// An attribute that is given more than once.
+    DuplicateAttribute { tag: &'static str, attr: String },
+    /// `tag` inside `parent`, or at the top level if `None`, where the
+    /// grammar does not allow it.
+    Misplaced {
+        tag: &'static str,
+        parent: Option<&'static str>,
+    },
+    /// Text inside `parent`, which can only hold elements.
+    MisplacedText { parent: &'static str },
+    /// A table or image that is not the only content of its label or cell.
+    MixedContent,
+    /// A closing tag that does not match the element it closes, which is
+    /// `expected`, or `None` at the top level.
+    UnexpectedClose {
+        found: &'static str,
+        expected: Option<&'static str>,
+    },
+    /// An element that is not closed by the end of the string.
+    Unclosed(&'static str),
+    /// A `<TABLE>` with no rows, or a `<TR>` with no cells.
+    Empty(&'static str),
+    /// Content in an element that must be empty, such as `<BR>`.
+    NotEmpty(&'static str),
+}
+
+impl std::error::Error for HtmlError {}
+
+impl fmt::Display for HtmlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: ", self.line, self.column)?;
+        match &self.kind {
+            HtmlErrorKind::Syntax(s) => f.write_str(s),
+            HtmlErrorKind::UnknownTag(tag) => write!(f, "unknown tag <{tag}>"),
+            HtmlErrorKind::UnknownAttribute { tag, attr } => {
+                write!(f, "<{tag}> has no attribute {attr}")
+            }
+            HtmlErrorKind::DuplicateAttribute { tag, attr } => {
+                write!(f, "attribute {attr} of <{tag}> is given more than once")
+            }
+            HtmlErrorKind::Misplaced {
+                tag,
+                parent: Some(parent),
+            } => write!(f, "<{tag}> is not allowed in <{parent}>"),
+            HtmlErrorKind::Misplaced { tag, parent: None } => {
+                write!(f, "<{tag}> is not allowed outside of a table")
+            }
+            HtmlErrorKind::MisplacedText { parent } => {
+                write!(f, "text is not allowed in <{parent}>")
+            }
+            HtmlErrorKind::MixedContent => {
+                f.write_str("a table or image must be the only content of its label or cell")
+            }
+            HtmlErrorKind::UnexpectedClose {
+                found,
+                expected: Some(expected),
+            } => write!(f, "expected </{expected}>, found </{found}>"),
+            HtmlErrorKind::UnexpectedClose {
+                found,
+                expected: None,
+            } => write!(f, "unexpected </{found}>"),
+            HtmlErrorKind::Unclosed(tag) => write!(f, "<{tag}> is not closed"),
+            HtmlErrorKind::Empty(tag) => write!(f, "<{tag}> must not be empty"),
+            HtmlErrorKind::NotEmpty(tag) => write!(f, "<{tag}> must be empty"),
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Token<'s> {
+    Open(&'static str),
+    Close(&'static str),
+    Text(&'s str),
+}
+
+/// What a label or cell holds so far.
+#[derive(Copy, Clone, PartialEq)]
+enum Content {
+    Empty,
+    Text,
+    /// A table or image.
+    Block,
+}
+
+/// A recursive descent parser over the tags and text of an HTML-like
+/// label, which keeps nothing but the ports.
+struct Checker<'s> {
+    input: &'s str,
+    pos: usize,
+    /// Tokens that were pushed back, or the closing tags of self-closing
+    /// elements, each with its offset.
+    tokens: VecDeque<(Token<'s>, usize)>,
+    ports: Vec<String>,
+}
+
+impl<'s> Checker<'s> {
+    fn error(&self, offset: usize, kind: HtmlErrorKind) -> HtmlError {
+        let before = &self.input[..offset];
+        let line = before.matches('\n').count() + 1;
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        let column = before[line_start..].chars().count() + 1;
+        HtmlError { kind, line, column }
+    }
+
+    fn rest(&self) -> &'s str {
+        &self.input[self.pos..]
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    /// Consumes `c`, or fails with `expected`.
+    fn expect(&mut self, c: char, expected: &'static str) -> Result<(), HtmlError> {
+        self.skip_whitespace();
+        match self.rest().strip_prefix(c) {
+            Some(_) => {
+                self.pos += c.len_utf8();
+                Ok(())
+            }
+            None => Err(self.error(self.pos, HtmlErrorKind::Syntax(expected))),
+        }
+    }
+
+    /// Consumes a tag or attribute name.
+    fn name(&mut self) -> &'s str {
+        let rest = self.rest();
+        let len = rest
+            .find(|c: char| !c.is_ascii_alphanumeric() && c != '-' && c != '_')
+            .unwrap_or(rest.len());
+        self.pos += len;
+        &rest[..len]
+    }
+
+    /// Consumes a tag name, returning the name as spelled in [`TAGS`]
+    /// and the attributes of the tag.
+    fn tag(&mut self, offset: usize) -> Result<(&'static str, &'static [&'static str]), HtmlError> {
+        let name = self.name();
+        if name.is_empty() {
+            return Err(self.error(
+                offset,
+                HtmlErrorKind::Syntax("expected a tag name after '<'"),
+            ));
+        }
+        TAGS.iter()
+            .find(|(tag, _)| tag.eq_ignore_ascii_case(name))
+            .copied()
+            .ok_or_else(|| self.error(offset, HtmlErrorKind::UnknownTag(name.to_owned())))
+    }
+
+    fn next(&mut self) -> Result<Option<(Token<'s>, usize)>, HtmlError> {
+        if let Some(token) = self.tokens.pop_front() {
+            return Ok(Some(token));
+        }
+        loop {
+            let offset = self.pos;
+            let rest = self.rest();
+            if rest.is_empty() {
+                return Ok(None);
+            }
+            if let Some(comment) = rest.strip_prefix("<!--") {
+                let Some(end) = comment.find("-->") else {
+                    return Err(self.error(offset, HtmlErrorKind::Syntax("unterminated comment")));
+                };
+                self.pos += 4 + end + 3;
+                continue;
+            }
+            if rest.starts_with("</") {
+                self.pos += 2;
+                let (tag, _) = self.tag(offset)?;
+                self.expect('>', "expected '>'")?;
+                return Ok(Some((Token::Close(tag), offset)));
+            }
+            if rest.starts_with('<') {
+                self.pos += 1;
+                let (tag, attrs) = self.tag(offset)?;
+                self.attrs(tag, attrs)?;
+                if self.rest().starts_with('/') {
+                    self.pos += 1;
+                    self.expect('>', "expected '>' after '/'")?;
+                    self.tokens.push_back((Token::Close(tag), offset));
+                } else {
+                    self.expect('>', "expected '>'")?;
+                }
+                return Ok(Some((Token::Open(tag), offset)));
+            }
+            let len = rest.find('<').unwrap_or(rest.len());
+            self.pos += len;
+            return Ok(Some((Token::Text(&rest[..len]), offset)));
+        }
+    }
+
+    /// Consumes the attributes of `tag`, which accepts `allowed`, up to the
+    /// end of the tag.
+    fn attrs(&mut self, tag: &'static str, allowed: &[&str]) -> Result<(), HtmlError> {
+        let mut seen = Vec::new();
+        loop {
+            self.skip_whitespace();
+            let offset = self.pos;
+            let name = self.name();
+            if name.is_empty() {
+                return Ok(());
+            }
+            let Some(attr) = allowed.iter().find(|a| a.eq_ignore_ascii_case(name)) else {
+                let kind = HtmlErrorKind::UnknownAttribute {
+                    tag,
+                    attr: name.to_owned(),
+                };
+                return Err(self.error(offset, kind));
+            };
+            if seen.contains(attr) {
+                let kind = HtmlErrorKind::DuplicateAttribute {
+                    tag,
+                    attr: attr.to_string(),
+                };
+                return Err(self.error(offset, kind));
+            }
+            seen.push(attr);
+
+            self.expect('=', "expected '=' after the attribute name")?;
+            self.skip_whitespace();
+            let rest = self.rest();
+            let Some(quote) = rest.chars().next().filter(|c| *c == '"' || *c == '\'') else {
+                return Err(self.error(
+                    self.pos,
+                    HtmlErrorKind::Syntax("attribute values must be quoted"),
+                ));
+            };
+            let Some(len) = rest[1..].find(quote) else {
+                return Err(self.error(
+                    self.pos,
+                    HtmlErrorKind::Syntax("unterminated attribute value"),
+                ));
+            };
+            if *attr == "PORT" {
+                self.ports.push(rest[1..1 + len].to_owned());
+            }
+            self.pos += len + 2;
+        }
+    }
+
+    /// Consumes the end of the empty element `tag`.
+    fn empty(&mut self, tag: &'static str) -> Result<(), HtmlError> {
+        match self.next()? {
+            Some((Token::Close(close), _)) if close == tag => Ok(()),
+            Some((_, offset)) => Err(self.error(offset, HtmlErrorKind::NotEmpty(tag))),
+            None => Err(self.error(self.pos, HtmlErrorKind::Unclosed(tag))),
+        }
+    }
+
+    /// Consumes a label up to the end of `parent`, the element it is in
+    /// with its offset, or up to the end of the string if `None`.
+    fn label(
+        &mut self,
+        parent: Option<(&'static str, usize)>,
+        tables: bool,
+    ) -> Result<Content, HtmlError> {
+        let parent_tag = parent.map(|(tag, _)| tag);
+        let mut content = Content::Empty;
+        let mut add = |checker: &Self, item: Content, offset: usize| match (content, item) {
+            (Content::Empty, item) => {
+                content = item;
+                Ok(())
+            }
+            (Content::Text, Content::Text) => Ok(()),
+            _ => Err(checker.error(offset, HtmlErrorKind::MixedContent)),
+        };
+        loop {
+            let Some((token, offset)) = self.next()? else {
+                return match parent {
+                    Some((tag, offset)) => Err(self.error(offset, HtmlErrorKind::Unclosed(tag))),
+                    None => Ok(content),
+                };
+            };
+            match token {
+                Token::Text(text) if text.trim().is_empty() => {}
+                Token::Text(_) => add(self, Content::Text, offset)?,
+                Token::Open("BR") => {
+                    self.empty("BR")?;
+                    add(self, Content::Text, offset)?;
+                }
+                Token::Open(tag @ ("FONT" | "B" | "I" | "U" | "O" | "SUB" | "SUP" | "S")) => {
+                    let tables = tables && matches!(tag, "FONT" | "B" | "I" | "U" | "O");
+                    match self.label(Some((tag, offset)), tables)? {
+                        Content::Empty => {}
+                        item => add(self, item, offset)?,
+                    }
+                }
+                Token::Open("TABLE") if tables => {
+                    self.table(offset)?;
+                    add(self, Content::Block, offset)?;
+                }
+                Token::Open(tag) => {
+                    let kind = HtmlErrorKind::Misplaced {
+                        tag,
+                        parent: parent_tag,
+                    };
+                    return Err(self.error(offset, kind));
+                }
+                Token::Close(tag) if Some(tag) == parent_tag => return Ok(content),
+                Token::Close(found) => {
+                    let kind = HtmlErrorKind::UnexpectedClose {
+                        found,
+                        expected: parent_tag,
+                    };
+                    return Err(self.error(offset, kind));
+                }
+            }
+        }
+    }
+
+    /// Consumes the rows of a table, which starts at `start`, or the cells
+    /// of a row: the elements `item` separated by the rules `rule`, which
+    /// are all that `parent` can hold.
+    fn items(
+        &mut self,
+        parent: &'static str,
+        start: usize,
+        item: &'static str,
+        rule: &'static str,
+    ) -> Result<(), HtmlError> {
+        let mut items = 0;
+        let mut after_rule = None;
+        loop {
+            let Some((token, offset)) = self.next()? else {
+                return Err(self.error(start, HtmlErrorKind::Unclosed(parent)));
+            };
+            let misplaced = |tag| HtmlErrorKind::Misplaced {
+                tag,
+                parent: Some(parent),
+            };
+            match token {
+                Token::Text(text) if text.trim().is_empty() => {}
+                Token::Text(_) => {
+                    return Err(self.error(offset, HtmlErrorKind::MisplacedText { parent }));
+                }
+                Token::Open(tag) if tag == item => {
+                    if item == "TR" {
+                        self.items("TR", offset, "TD", "VR")?;
+                    } else {
+                        self.cell(offset)?;
+                    }
+                    items += 1;
+                    after_rule = None;
+                }
+                // Rules can only be between two rows or two cells.
+                Token::Open(tag) if tag == rule => {
+                    self.empty(rule)?;
+                    if items == 0 || after_rule.is_some() {
+                        return Err(self.error(offset, misplaced(rule)));
+                    }
+                    after_rule = Some(offset);
+                }
+                Token::Open(tag) => return Err(self.error(offset, misplaced(tag))),
+                Token::Close(tag) if tag == parent => {
+                    if let Some(offset) = after_rule {
+                        return Err(self.error(offset, misplaced(rule)));
+                    }
+                    if items == 0 {
+                        return Err(self.error(start, HtmlErrorKind::Empty(parent)));
+                    }
+                    return Ok(());
+                }
+                Token::Close(found) => {
+                    let kind = HtmlErrorKind::UnexpectedClose {
+                        found,
+                        expected: Some(parent),
+                    };
+                    return Err(self.error(offset, kind));
+                }
+            }
+        }
+    }
+
+    fn table(&mut self, start: usize) -> Result<(), HtmlError> {
+        self.items("TABLE", start, "TR", "HR")
+    }
+
+    /// Consumes the content of a cell, which starts at `start`: an image,
+    /// or a label.
+    fn cell(&mut self, start: usize) -> Result<(), HtmlError> {
+        let first = loop {
+            match self.next()? {
+                Some((Token::Text(text), _)) if text.trim().is_empty() => {}
+                token => break token,
+            }
+        };
+        match first {
+            Some((Token::Open("IMG"), _)) => {
+                self.empty("IMG")?;
+                loop {
+                    match self.next()? {
+                        Some((Token::Text(text), _)) if text.trim().is_empty() => {}
+                        Some((Token::Close("TD"), _)) => return Ok(()),
+                        Some((Token::Close(found), offset)) => {
+                            let kind = HtmlErrorKind::UnexpectedClose {
+                                found,
+                                expected: Some("TD"),
+                            };
+                            return Err(self.error(offset, kind));
+                        }
+                        Some((_, offset)) => {
+                            return Err(self.error(offset, HtmlErrorKind::MixedContent));
+                        }
+                        None => return Err(self.error(start, HtmlErrorKind::Unclosed("TD"))),
+                    }
+                }
+            }
+            Some(token) => {
+                self.tokens.push_front(token);
+                self.label(Some(("TD", start)), true).map(drop)
+            }
+            None => Err(self.error(start, HtmlErrorKind::Unclosed("TD"))),
+        }
+    }
+}
@@ -0,0 +1,349 @@
+//! Legends that explain the styles used in a graph.
+//!
+//! With [`RenderOptions::with_legend`](crate::RenderOptions::with_legend),
+//! [`render_opts`](crate::render_opts()) collects the distinct combinations
+//! of classes, style, color and arrows that the nodes and edges of the graph
+//! are drawn with, and adds a `cluster_legend` subgraph with a sample node or
+//! edge for each of them. Each sample is labelled with the description given
+//! by [`Labeller::legend_label`].
+//!
+//! The legend is itself a small graph, with nodes named `legend_0`,
+//! `legend_1_tail` and so on, that is rendered with the same code as
+//! [`render_subgraphs`](crate::render::render_subgraphs). Ids that the graph
+//! already uses are avoided by adding a number, as in `legend_0_1`.
+
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+
+use crate::render::unique_id;
+use crate::stylesheet::ElementKind;
+use crate::{
+    Arrow, Classes, Color, Edges, GraphKind, GraphWalk, Id, Labeller, Nodes, RenderOptions, Shape,
+    Styles, Stylesheet, Subgraphs, Text,
+};
+
+/// A combination of classes, style, color and arrows that some of the nodes
+/// or edges of a graph are drawn with.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LegendEntry<'a> {
+    /// Either [`ElementKind::Node`] or [`ElementKind::Edge`].
+    pub kind: ElementKind,
+    pub classes: Classes<'a>,
+    pub style: Styles,
+    pub color: Option<Color<'a>>,
+    /// The arrow at the start of an edge, which is the default for nodes.
+    pub start_arrow: Arrow,
+    /// The arrow at the end of an edge, which is the default for nodes.
+    pub end_arrow: Arrow,
+}
+
+impl LegendEntry<'_> {
+    /// Returns `true` if nothing distinguishes the elements from unstyled
+    /// ones, so that they need no explanation.
+    pub fn is_plain(&self) -> bool {
+        self.classes.is_empty()
+            && self.style.is_empty()
+            && self.color.is_none()
+            && self.start_arrow.is_default()
+            && self.end_arrow.is_default()
+    }
+}
+
+impl std::fmt::Display for LegendEntry<'_> {
+    /// Lists the classes, style, color and arrows, such as
+    /// `error, dashed, red` or `tail tee, inv`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut parts: Vec<Cow<'_, str>> = Vec::new();
+        if !self.classes.is_empty() {
+            parts.push(self.classes.join(" ").into());
+        }
+        if !self.style.is_empty() {
+            parts.push(self.style.to_string().into());
+        }
+        if let Some(color) = &self.color {
+            parts.push(color.to_string().into());
+        }
+        if !self.start_arrow.is_default() {
+            parts.push(format!("tail {}", self.start_arrow.to_dot_string()).into());
+        }
+        if !self.end_arrow.is_default() {
+            parts.push(self.end_arrow.to_dot_string().into());
+        }
+        f.write_str(&parts.join(", "))
+    }
+}
+
+/// Collects the legend entries of `g`, in the order they are first used,
+/// leaving out plain ones. Styles, colors and arrows that `options` leaves
+/// out of the output are ignored.
+pub fn legend_entries<'a, N, E, S, G>(g: &'a G, options: &RenderOptions) -> Vec<LegendEntry<'a>>
+where
+    N: Clone + 'a,
+    E: Clone + 'a,
+    S: Clone + 'a,
+    G: Labeller<'a, Node = N, Edge = E, Subgraph = S>
+        + GraphWalk<'a, Node = N, Edge = E, Subgraph = S>,
+{
+    let mut entries = Vec::new();
+    let mut add = |entry: LegendEntry<'a>| {
+        if !entry.is_plain() && !entries.contains(&entry) {
+            entries.push(entry);
+        }
+    };
+    for n in g.nodes().iter() {
+        add(LegendEntry {
+            kind: ElementKind::Node,
+            classes: g.node_classes(n),
+            style: match options.node_styles {
+                true => g.node_style(n),
+                false => Styles::new(),
+            },
+            color: options.node_colors.then(|| g.node_color(n)).flatten(),
+            start_arrow: Arrow::default(),
+            end_arrow: Arrow::default(),
+        });
+    }
+    for e in g.edges().iter() {
+        add(LegendEntry {
+            kind: ElementKind::Edge,
+            classes: g.edge_classes(e),
+            style: match options.edge_styles {
+                true => g.edge_style(e),
+                false => Styles::new(),
+            },
+            color: options.edge_colors.then(|| g.edge_color(e)).flatten(),
+            start_arrow: match options.arrows {
+                true => g.edge_start_arrow(e),
+                false => Arrow::default(),
+            },
+            end_arrow: match options.arrows {
+                true => g.edge_end_arrow(e),
+                false => Arrow::default(),
+            },
+        });
+    }
+    entries
+}
+
+/// The part of a legend sample that a legend node is.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub(crate) enum LegendNode {
+    /// The sample node of a node entry.
+    Sample(usize),
+    /// The start of the sample edge of an edge entry.
+    Tail(usize),
+    /// The end of the sample edge of an edge entry.
+    Head(usize),
+}
+
+/// The graph of samples that makes up a legend, with a single subgraph
+/// holding all of them.
+pub(crate) struct Legend<'a> {
+    kind: GraphKind,
+    title: Text<'a>,
+    stylesheet: Stylesheet<'a>,
+    entries: Vec<(LegendEntry<'a>, Text<'a>)>,
+    nodes: Vec<LegendNode>,
+    edges: Vec<usize>,
+    /// The ids of the nodes and of the subgraph, which are not used by the
+    /// graph.
+    node_ids: HashMap<LegendNode, String>,
+    subgraph_id: String,
+}
+
+impl<'a> Legend<'a> {
+    /// Builds the legend of `g`, or returns `None` if it has no entries.
+    pub(crate) fn new<N, E, S, G>(g: &'a G, options: &RenderOptions) -> Option<Self>
+    where
+        N: Clone + 'a,
+        E: Clone + 'a,
+        S: Clone + 'a,
+        G: Labeller<'a, Node = N, Edge = E, Subgraph = S>
+            + GraphWalk<'a, Node = N, Edge = E, Subgraph = S>,
+    {
+        let entries: Vec<_> = legend_entries(g, options)
+            .into_iter()
+            .filter_map(|entry| {
+                let label = g.legend_label(&entry)?;
+                Some((entry, label))
+            })
+            .collect();
+        if entries.is_empty() {
+            return None;
+        }
+
+        let mut nodes = Vec::new();
+        let mut edges = Vec::new();
+        for (i, (entry, _)) in entries.iter().enumerate() {
+            match entry.kind {
+                ElementKind::Edge => {
+                    nodes.extend([LegendNode::Tail(i), LegendNode::Head(i)]);
+                    edges.push(i);
+                }
+                _ => nodes.push(LegendNode::Sample(i)),
+            }
+        }
+
+        // The ids of the legend must not clash with those of the graph.
+        let mut used: HashSet<String> = g
+            .nodes()
+            .iter()
+            .map(|n| g.node_id(n)[..].to_owned())
+            .collect();
+        let mut subgraphs = g.subgraphs().into_owned();
+        while let Some(s) = subgraphs.pop() {
+            used.extend(g.subgraph_id(&s).map(|id| id[..].to_owned()));
+            subgraphs.extend(g.subgraph_children(&s).iter().cloned());
+        }
+        let subgraph_id = unique_id("cluster_legend", &mut used, &[]);
+        let node_ids = nodes
+            .iter()
+            .map(|&n| {
+                let name = match n {
+                    LegendNode::Sample(i) => format!("legend_{i}"),
+                    LegendNode::Tail(i) => format!("legend_{i}_tail"),
+                    LegendNode::Head(i) => format!("legend_{i}_head"),
+                };
+                (n, unique_id(&name, &mut used, &[]))
+            })
+            .collect();
+
+        Some(Legend {
+            kind: g.kind(),
+            title: g.legend_title(),
+            stylesheet: g.stylesheet(),
+            entries,
+            nodes,
+            edges,
+            node_ids,
+            subgraph_id,
+        })
+    }
+
+    fn entry(&self, i: usize) -> &LegendEntry<'a> {
+        &self.entries[i].0
+    }
+}
+
+impl<'l, 'a: 'l> Labeller<'l> for Legend<'a> {
+    type Node = LegendNode;
+    type Edge = usize;
+    type Subgraph = ();
+
+    fn graph_id(&'l self) -> Id<'l> {
+        Id::new("legend").unwrap()
+    }
+
+    fn stylesheet(&'l self) -> Stylesheet<'l> {
+        self.stylesheet.clone()
+    }
+
+    fn node_id(&'l self, n: &LegendNode) -> Id<'l> {
+        Id::new(&self.node_ids[n][..]).unwrap()
+    }
+
+    fn node_shape(&'l self, n: &LegendNode) -> Option<Shape> {
+        match n {
+            LegendNode::Sample(_) => None,
+            LegendNode::Tail(_) | LegendNode::Head(_) => Some(Shape::Point),
+        }
+    }
+
+    fn node_label(&'l self, n: &LegendNode) -> Text<'l> {
+        match n {
+            LegendNode::Sample(i) => self.entries[*i].1.clone(),
+            LegendNode::Tail(_) | LegendNode::Head(_) => Text::label(""),
+        }
+    }
+
+    fn node_style(&'l self, n: &LegendNode) -> Styles {
+        match n {
+            LegendNode::Sample(i) => self.entry(*i).style.clone(),
+            LegendNode::Tail(_) | LegendNode::Head(_) => Styles::new(),
+        }
+    }
+
+    fn node_color(&'l self, n: &LegendNode) -> Option<Color<'l>> {
+        match n {
+            LegendNode::Sample(i) => self.entry(*i).color.clone(),
+            LegendNode::Tail(_) | LegendNode::Head(_) => None,
+        }
+    }
+
+    fn node_classes(&'l self, n: &LegendNode) -> Classes<'l> {
+        match n {
+            LegendNode::Sample(i) => self.entry(*i).classes.clone(),
+            LegendNode::Tail(_) | LegendNode::Head(_) => Vec::new(),
+        }
+    }
+
+    fn edge_label(&'l self, e: &usize) -> Text<'l> {
+        self.entries[*e].1.clone()
+    }
+
+    fn edge_style(&'l self, e: &usize) -> Styles {
+        self.entry(*e).style.clone()
+    }
+
+    fn edge_color(&'l self, e: &usize) -> Option<Color<'l>> {
+        self.entry(*e).color.clone()
+    }
+
+    fn edge_start_arrow(&'l self, e: &usize) -> Arrow {
+        self.entry(*e).start_arrow.clone()
+    }
+
+    fn edge_end_arrow(&'l self, e: &usize) -> Arrow {
+        self.entry(*e).end_arrow.clone()
+    }
+
+    fn edge_classes(&'l self, e: &usize) -> Classes<'l> {
+        self.entry(*e).classes.clone()
+    }
+
+    fn kind(&self) -> GraphKind {
+        self.kind
+    }
+
+    fn subgraph_id(&'l self, _s: &()) -> Option<Id<'l>> {
+        Id::new(&self.subgraph_id[..]).ok()
+    }
+
+    fn subgraph_label(&'l self, _s: &()) -> Text<'l> {
+        self.title.clone()
+    }
+}
+
+impl<'l, 'a: 'l> GraphWalk<'l> for Legend<'a> {
+    type Node = LegendNode;
+    type Edge = usize;
+    type Subgraph = ();
+
+    fn nodes(&'l self) -> Nodes<'l, LegendNode> {
+        Cow::Borrowed(&self.nodes)
+    }
+
+    fn edges(&'l self) -> Edges<'l, usize> {
+        Cow::Borrowed(&self.edges)
+    }
+
+    fn source(&'l self, e: &usize) -> LegendNode {
+        LegendNode::Tail(*e)
+    }
+
+    fn target(&'l self, e: &usize) -> LegendNode {
+        LegendNode::Head(*e)
+    }
+
+    fn subgraphs(&'l self) -> Subgraphs<'l, ()> {
+        Cow::Borrowed(&[()])
+    }
+
+    fn subgraph_nodes(&'l self, _s: &()) -> Nodes<'l, LegendNode> {
+        Cow::Borrowed(&self.nodes)
+    }
+
+    fn subgraph_edges(&'l self, _s: &()) -> Edges<'l, usize> {
+        (0..self.edges.len()).collect()
+    }
+}
@@ -10,13 +10,29 @@
 //! this library exposes two traits that clients can implement on their
 //! own structs before handing them over to the rendering function.
 //!
+//! Going the other way, the [`parse`](mod@parse) module reads DOT text
+//! back into a typed syntax tree. For graphs that are built up directly
+//! rather than adapted from another structure, [`DotGraph`] is an owned
+//! graph that implements both traits.
+//!
+//! Attributes beyond the labels, styles and colors that have their own
+//! `Labeller` methods are given as typed [`Attr`] values, which cover the
+//! common part of the [attribute catalogue](https://www.graphviz.org/doc/info/attrs.html)
+//! and are escaped when rendered. [`validate`](validate()) reports the
+//! problems in a graph, such as edges to unknown nodes, before it is
+//! rendered. The colors and font of the output can be set with a
+//! [`Theme`], and attributes shared by many elements can be given once in a
+//! [`Stylesheet`] that maps class names to attributes. A [`Palette`] or
+//! [`Scale`] picks node and edge colors by key or by value. Record and
+//! HTML-like labels can be built with [`Record`] and [`HtmlLabel`], which
+//! escape their text. Besides DOT, graphs can be rendered as
+//! [Mermaid](mermaid) flowcharts and [PlantUML](plantuml) diagrams, and
+//! exchanged with other tools as [GraphML](graphml).
+//!
 //! Note: This library does not yet provide access to the full
 //! expressiveness of the [DOT language](https://www.graphviz.org/doc/info/lang.html).
-//! For example, there are many [attributes](https://www.graphviz.org/doc/info/attrs.html)
-//! related to providing layout hints (e.g., left-to-right versus top-down, which
-//! algorithm to use, etc). The current intention of this library is to
-//! emit a human-readable .dot file with very regular structure suitable
-//! for easy post-processing.
+//! The current intention of this library is to emit a human-readable .dot
+//! file with very regular structure suitable for easy post-processing.
 //!
 //! # Examples
 //!
@@ -115,12 +131,12 @@
 //!     N2[label="N2"];
 //!     N3[label="N3"];
 //!     N4[label="N4"];
-//!     N0 -> N1[label=""];
-//!     N0 -> N2[label=""];
-//!     N1 -> N3[label=""];
-//!     N2 -> N3[label=""];
-//!     N3 -> N4[label=""];
-//!     N4 -> N4[label=""];
+//!     N0 -> N1;
+//!     N0 -> N2;
+//!     N1 -> N3;
+//!     N2 -> N3;
+//!     N3 -> N4;
+//!     N4 -> N4;
 //! }
 //! ```
 //!
@@ -401,7 +417,6 @@
 //!         N1;
 //!         N2;
 //!     }
-//!
 //!     subgraph cluster_1 {
 //!         label="";
 //!         N3;
@@ -430,21 +445,47 @@
 #![warn(unreachable_pub)]
 // tidy-alphabetical-end
 
+pub mod attrs;
+pub mod color;
+pub mod graph;
+pub mod graphml;
+pub mod html;
+pub mod legend;
+pub mod mermaid;
+pub mod palette;
+pub mod parse;
+pub mod plantuml;
+pub mod record;
 pub mod render;
+pub mod shape;
+pub mod stylesheet;
+pub mod theme;
 pub mod types;
+pub mod validate;
 
 use std::borrow::Cow;
-use std::collections::HashMap;
 
-pub use render::{render, render_opts};
+pub use attrs::{Attr, Attrs};
+pub use color::Color;
+pub use graph::DotGraph;
+pub use html::HtmlLabel;
+pub use legend::LegendEntry;
+pub use palette::{Palette, Scale};
+pub use parse::parse;
+pub use record::Record;
+pub use render::{render, render_opts, RenderError, RenderOptions};
+pub use shape::Shape;
+pub use stylesheet::{Classes, Selector, Stylesheet};
+pub use theme::Theme;
 pub use types::*;
+pub use validate::{validate, Diagnostic};
 
 /// Each instance of a type that implements `Label<C>` maps to a
 /// unique identifier with respect to `C`, which is used to identify
 /// it in the generated .dot file. They can also provide more
 /// elaborate (and non-unique) label text that is used in the graphviz
 /// rendered output.
-
+///
 /// The graph instance is responsible for providing the DOT compatible
 /// identifiers for the nodes and (optionally) rendered labels for the nodes and
 /// edges, as well as an identifier for the graph itself.
@@ -454,11 +495,38 @@ pub trait Labeller<'a> {
     type Subgraph;
 
     /// Must return a DOT compatible identifier naming the graph.
+    ///
+    /// An empty quoted id, `Id::quoted("")`, leaves the graph anonymous: it
+    /// is written as `digraph {` rather than with a name.
     fn graph_id(&'a self) -> Id<'a>;
 
-    /// A list of attributes to apply to the graph
-    fn graph_attrs(&'a self) -> HashMap<&str, &str> {
-        HashMap::default()
+    /// A list of attributes to apply to the graph, rendered in order.
+    ///
+    /// These also act as defaults for the graph attributes of subgraphs.
+    fn graph_attrs(&'a self) -> Attrs<'a> {
+        Attrs::new()
+    }
+
+    /// Default attributes for every node, emitted once as a `node[...]`
+    /// statement instead of on each node.
+    fn node_defaults(&'a self) -> Attrs<'a> {
+        Attrs::new()
+    }
+
+    /// Default attributes for every edge, emitted once as an `edge[...]`
+    /// statement instead of on each edge.
+    fn edge_defaults(&'a self) -> Attrs<'a> {
+        Attrs::new()
+    }
+
+    /// The rules that style elements by their classes, see
+    /// [`node_classes`](Self::node_classes) and the like.
+    ///
+    /// The attributes from the stylesheet are overridden by those given by
+    /// the other methods for an element, such as `node_style` and
+    /// `node_attrs`.
+    fn stylesheet(&'a self) -> Stylesheet<'a> {
+        Stylesheet::new()
     }
 
     /// Maps `n` to a unique identifier with respect to `self`. The
@@ -466,11 +534,11 @@ pub trait Labeller<'a> {
     /// is a valid DOT identifier.
     fn node_id(&'a self, n: &Self::Node) -> Id<'a>;
 
-    /// Maps `n` to one of the [graphviz `shape` names][1]. If `None`
-    /// is returned, no `shape` attribute is specified.
+    /// Maps `n` to one of the [graphviz shapes][1]. If `None` is returned,
+    /// no `shape` attribute is specified.
     ///
     /// [1]: https://www.graphviz.org/doc/info/shapes.html
-    fn node_shape(&'a self, _node: &Self::Node) -> Option<Text<'a>> {
+    fn node_shape(&'a self, _node: &Self::Node) -> Option<Shape> {
         None
     }
 
@@ -488,9 +556,10 @@ pub trait Labeller<'a> {
         Text::Label("".into())
     }
 
-    /// Maps `n` to a style that will be used in the rendered output.
-    fn node_style(&'a self, _n: &Self::Node) -> Style {
-        Style::None
+    /// Maps `n` to the styles that will be used in the rendered output.
+    /// A single [`Style`] converts into [`Styles`] with `into()`.
+    fn node_style(&'a self, _n: &Self::Node) -> Styles {
+        Styles::new()
     }
 
     /// Return an explicit rank dir to use for directed graphs.
@@ -500,17 +569,21 @@ pub trait Labeller<'a> {
         None
     }
 
-    /// Maps `n` to one of the [graphviz `color` names][1]. If `None`
-    /// is returned, no `color` attribute is specified.
-    ///
-    /// [1]: https://graphviz.gitlab.io/_pages/doc/info/colors.html
-    fn node_color(&'a self, _node: &Self::Node) -> Option<Text<'a>> {
+    /// Maps `n` to a [`Color`]. If `None` is returned, no `color`
+    /// attribute is specified.
+    fn node_color(&'a self, _node: &Self::Node) -> Option<Color<'a>> {
         None
     }
 
-    /// Maps `n` to a set of arbritrary node attributes.
-    fn node_attrs(&'a self, _n: &Self::Node) -> HashMap<&str, &str> {
-        HashMap::default()
+    /// Maps `n` to a set of arbritrary node attributes, rendered in order.
+    fn node_attrs(&'a self, _n: &Self::Node) -> Attrs<'a> {
+        Attrs::new()
+    }
+
+    /// Maps `n` to the classes that the [`stylesheet`](Self::stylesheet)
+    /// selects it by.
+    fn node_classes(&'a self, _n: &Self::Node) -> Classes<'a> {
+        Vec::new()
     }
 
     /// Maps `e` to arrow style that will be used on the end of an edge.
@@ -525,22 +598,26 @@ pub trait Labeller<'a> {
         Arrow::default()
     }
 
-    /// Maps `e` to a style that will be used in the rendered output.
-    fn edge_style(&'a self, _e: &Self::Edge) -> Style {
-        Style::None
+    /// Maps `e` to the styles that will be used in the rendered output.
+    fn edge_style(&'a self, _e: &Self::Edge) -> Styles {
+        Styles::new()
     }
 
-    /// Maps `e` to one of the [graphviz `color` names][1]. If `None`
-    /// is returned, no `color` attribute is specified.
-    ///
-    /// [1]: https://graphviz.gitlab.io/_pages/doc/info/colors.html
-    fn edge_color(&'a self, _e: &Self::Edge) -> Option<Text<'a>> {
+    /// Maps `e` to a [`Color`]. If `None` is returned, no `color`
+    /// attribute is specified.
+    fn edge_color(&'a self, _e: &Self::Edge) -> Option<Color<'a>> {
         None
     }
 
-    /// Maps `e` to a set of arbritrary edge attributes.
-    fn edge_attrs(&'a self, _e: &Self::Edge) -> HashMap<&str, &str> {
-        HashMap::default()
+    /// Maps `e` to a set of arbritrary edge attributes, rendered in order.
+    fn edge_attrs(&'a self, _e: &Self::Edge) -> Attrs<'a> {
+        Attrs::new()
+    }
+
+    /// Maps `e` to the classes that the [`stylesheet`](Self::stylesheet)
+    /// selects it by.
+    fn edge_classes(&'a self, _e: &Self::Edge) -> Classes<'a> {
+        Vec::new()
     }
 
     /// Maps `e` to the compass point that the edge will start from.
@@ -581,26 +658,68 @@ pub trait Labeller<'a> {
         Text::Label("".into())
     }
 
-    /// Maps `s` to the corresponding subgraph style (default to `Style::None`).
-    fn subgraph_style(&'a self, _s: &Self::Subgraph) -> Style {
-        Style::None
+    /// Maps `s` to the corresponding subgraph styles (default to none).
+    fn subgraph_style(&'a self, _s: &Self::Subgraph) -> Styles {
+        Styles::new()
     }
 
-    /// Maps `s` to the corresponding subgraph shape.
+    /// Maps `s` to the shape of the nodes within it. Graphviz ignores the
+    /// `shape` of a subgraph itself, so this is emitted in the `node[...]`
+    /// statement of the subgraph, before the
+    /// [`subgraph_node_defaults`](Self::subgraph_node_defaults).
     /// If `None` is returned (default), no `shape` attribute is specified.
-    fn subgraph_shape(&'a self, _s: &Self::Subgraph) -> Option<Text<'a>> {
+    fn subgraph_shape(&'a self, _s: &Self::Subgraph) -> Option<Shape> {
         None
     }
 
-    /// Maps `s` to the corresponding subgraph color (default to `Style::None`).
+    /// Maps `s` to the corresponding subgraph color.
     /// If `None` is returned (default), no `color` attribute is specified.
-    fn subgraph_color(&'a self, _s: &Self::Subgraph) -> Option<Text<'a>> {
+    fn subgraph_color(&'a self, _s: &Self::Subgraph) -> Option<Color<'a>> {
         None
     }
 
-    /// Maps `s` to a set of arbritrary node attributes.
-    fn subgraph_attrs(&'a self, _n: &Self::Subgraph) -> HashMap<&str, &str> {
-        HashMap::default()
+    /// Maps `s` to a set of arbritrary subgraph attributes, rendered in
+    /// order.
+    fn subgraph_attrs(&'a self, _n: &Self::Subgraph) -> Attrs<'a> {
+        Attrs::new()
+    }
+
+    /// Maps `s` to the classes that the [`stylesheet`](Self::stylesheet)
+    /// selects it by.
+    fn subgraph_classes(&'a self, _s: &Self::Subgraph) -> Classes<'a> {
+        Vec::new()
+    }
+
+    /// Default attributes for the nodes within `s`, emitted as a `node[...]`
+    /// statement at the start of the subgraph.
+    ///
+    /// Graphviz only applies these to nodes that are first mentioned within
+    /// the subgraph, so they do not affect nodes that were already emitted
+    /// in an earlier subgraph.
+    fn subgraph_node_defaults(&'a self, _s: &Self::Subgraph) -> Attrs<'a> {
+        Attrs::new()
+    }
+
+    /// Default attributes for the edges within `s`, emitted as an
+    /// `edge[...]` statement at the start of the subgraph. Only the edges
+    /// emitted within the subgraph, see [`GraphWalk::subgraph_edges`], are
+    /// affected.
+    fn subgraph_edge_defaults(&'a self, _s: &Self::Subgraph) -> Attrs<'a> {
+        Attrs::new()
+    }
+
+    /// The label of the legend, see [`RenderOptions::with_legend`].
+    fn legend_title(&'a self) -> Text<'a> {
+        Text::label("Legend")
+    }
+
+    /// Describes the nodes or edges drawn like `entry` in the legend, see
+    /// [`RenderOptions::with_legend`]. If `None` is returned, the entry is
+    /// left out of the legend.
+    ///
+    /// The default lists the classes, style, color and arrows of the entry.
+    fn legend_label(&'a self, entry: &LegendEntry<'a>) -> Option<Text<'a>> {
+        Some(Text::label(entry.to_string()))
     }
 }
 
@@ -648,15 +767,36 @@ pub trait GraphWalk<'a> {
     /// The target node for `edge`.
     fn target(&'a self, edge: &Self::Edge) -> Self::Node;
 
-    /// Returns all the subgraphs in this graph.
+    /// Returns the top-level subgraphs in this graph.
     fn subgraphs(&'a self) -> Subgraphs<'a, Self::Subgraph> {
         std::borrow::Cow::Borrowed(&[])
     }
 
-    /// Returns all the subgraphs in this graph.
+    /// Returns the nodes in the subgraph `s`.
+    ///
+    /// Nodes that are also in one of the subgraphs nested within `s` may be
+    /// included or not; either way, they are emitted only in the innermost
+    /// subgraph.
     fn subgraph_nodes(&'a self, _s: &Self::Subgraph) -> Nodes<'a, Self::Node> {
         std::borrow::Cow::Borrowed(&[])
     }
+
+    /// Returns the edges declared within the subgraph `s`, as their
+    /// positions in the list returned by `edges`.
+    ///
+    /// These are emitted inside the subgraph, so that its `edge` defaults
+    /// apply to them, rather than at the top level; as in DOT, their
+    /// endpoints then also belong to the subgraph. Like nodes, edges listed
+    /// by nested subgraphs are emitted only in the innermost one. Positions
+    /// past the end of `edges` are ignored.
+    fn subgraph_edges(&'a self, _s: &Self::Subgraph) -> Edges<'a, usize> {
+        std::borrow::Cow::Borrowed(&[])
+    }
+
+    /// Returns the subgraphs nested directly within the subgraph `s`.
+    fn subgraph_children(&'a self, _s: &Self::Subgraph) -> Subgraphs<'a, Self::Subgraph> {
+        std::borrow::Cow::Borrowed(&[])
+    }
 }
 
 #[cfg(test)]
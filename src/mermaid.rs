@@ -0,0 +1,505 @@
+//! Rendering to [Mermaid](https://mermaid.js.org/) flowcharts.
+//!
+//! [`render`] writes a Mermaid `flowchart` rather than DOT from the same
+//! [`Labeller`] + [`GraphWalk`] implementation as [`crate::render()`], for
+//! platforms that draw Mermaid diagrams but not Graphviz ones. Node ids that
+//! Mermaid cannot take as they are, such as `end` or ids with spaces, are
+//! replaced by similar ones; the labels still show the original ids.
+//!
+//! ```rust
+//! use dotwalk::graph::{DotGraph, Edge, Node};
+//! use dotwalk::{mermaid, Attr, Color, GraphKind, Id, RankDir, Shape, Style, Text};
+//!
+//! let mut graph = DotGraph::new(Id::new("g").ok(), GraphKind::Directed)
+//!     .with_attr(Attr::RankDir(RankDir::LeftRight));
+//! let a = graph.add_node(Node::new(Id::new("a").unwrap()).with_label(Text::label("Start")));
+//! let b = graph.add_node(
+//!     Node::new(Id::new("b").unwrap())
+//!         .with_attr(Attr::Shape(Shape::Diamond))
+//!         .with_attr(Attr::Color(Color::named("red"))),
+//! );
+//! graph.add_edge(
+//!     Edge::new(a, b)
+//!         .with_label(Text::label("go"))
+//!         .with_attr(Attr::Style(Style::Dashed.into())),
+//! );
+//!
+//! let mut output = Vec::new();
+//! mermaid::render(&graph, &mut output).unwrap();
+//! assert_eq!(
+//!     String::from_utf8(output).unwrap(),
+//!     r#"flowchart LR
+//!     a["Start"]
+//!     b{"b"}
+//!     a -.->|go| b
+//!     style b stroke:red
+//! "#
+//! );
+//! ```
+//!
+//! # Differences from the DOT output
+//!
+//! Mermaid draws less than Graphviz, so some of the graph is lost or
+//! approximated:
+//!
+//! * Nodes without a shape are drawn as rectangles, Mermaid's default, rather
+//!   than as ellipses. Shapes that Mermaid lacks are drawn as the nearest
+//!   one, such as a hexagon for an octagon, or as a rectangle; record labels
+//!   are shown as text.
+//! * As in Graphviz, only subgraphs whose ids start with `cluster` are drawn.
+//!   The other subgraphs only give their nodes and edges defaults.
+//! * Ports and compass points are dropped.
+//! * Arrowheads are `dot` (`o`), `tee` (`x`), or an arrow for all others.
+//!   An edge with arrowheads at both ends gets the one at its head at both
+//!   ends, and an edge with an arrowhead only at its tail is drawn reversed.
+//! * Dashed and dotted edges are both drawn dotted, and bold edges thick.
+//! * Colors in schemes other than X11, such as the Brewer schemes, are
+//!   dropped, and color lists are drawn with their first color.
+//! * HTML-like labels are reduced to their text, with a line for each
+//!   `<BR/>` or table row.
+//! * The other attributes, the font, the theme and the legend of the
+//!   [`RenderOptions`] are ignored, and so is compact output, which
+//!   Mermaid does not allow.
+
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+
+use crate::attrs::Dir;
+use crate::render::{
+    check_ids, resolve_edge_attrs, resolve_node_attrs, resolve_subgraph_attrs, styles, unique_id,
+    walk_subgraph, Emitted, Printer, SubgraphVisitor,
+};
+use crate::{
+    Arrow, ArrowVertex, Attr, Attrs, GraphKind, GraphWalk, Id, Labeller, RankDir, RenderError,
+    RenderOptions, Shape, Style, Stylesheet,
+};
+
+/// Renders graph `g` into the writer `w` as a Mermaid flowchart.
+pub fn render<'a, N, E, S, G, W>(g: &'a G, w: &mut W) -> Result<(), RenderError>
+where
+    N: Clone + 'a,
+    E: Clone + 'a,
+    S: Clone + 'a,
+    G: Labeller<'a, Node = N, Edge = E, Subgraph = S>
+        + GraphWalk<'a, Node = N, Edge = E, Subgraph = S>,
+    W: Write,
+{
+    render_opts(g, w, RenderOptions::default())
+}
+
+/// Renders graph `g` into the writer `w` as a Mermaid flowchart, leaving out
+/// the labels, styles, colors or arrows as `options` says, and indenting
+/// and ending lines as it says.
+pub fn render_opts<'a, N, E, S, G, W>(
+    g: &'a G,
+    w: &mut W,
+    options: impl Into<RenderOptions>,
+) -> Result<(), RenderError>
+where
+    N: Clone + 'a,
+    E: Clone + 'a,
+    S: Clone + 'a,
+    G: Labeller<'a, Node = N, Edge = E, Subgraph = S>
+        + GraphWalk<'a, Node = N, Edge = E, Subgraph = S>,
+    W: Write,
+{
+    let options = options.into().with_compact(false);
+    check_ids(g)?;
+
+    let mut flowchart = Flowchart {
+        graph: g,
+        stylesheet: g.stylesheet(),
+        options: &options,
+        p: Printer::new(Vec::new(), &options),
+        ids: HashMap::new(),
+        used: HashSet::new(),
+        links: 0,
+        styles: Vec::new(),
+    };
+    for n in g.nodes().iter() {
+        flowchart.name(&g.node_id(n));
+    }
+
+    let p = &mut flowchart.p;
+    let graph_attrs = g.graph_attrs();
+    if let Some(Attr::Label(label)) = graph_attrs.get("label") {
+        if !label.is_empty() {
+            let title = label.plain_lines(&g.graph_id()).join(" ");
+            let title = title.replace('\\', "\\\\").replace('"', "\\\"");
+            p.line(0, "---")?;
+            p.line(0, format_args!("title: \"{title}\""))?;
+            p.line(0, "---")?;
+        }
+    }
+    let rank_dir = match graph_attrs.get("rankdir") {
+        Some(Attr::RankDir(rank_dir)) => Some(*rank_dir),
+        _ => g.rank_dir(),
+    };
+    let direction = match rank_dir {
+        None | Some(RankDir::TopBottom) => "TD",
+        Some(RankDir::LeftRight) => "LR",
+        Some(RankDir::BottomTop) => "BT",
+        Some(RankDir::RightLeft) => "RL",
+    };
+    p.line(0, format_args!("flowchart {direction}"))?;
+
+    let node_defaults = g.node_defaults();
+    let edge_defaults = g.edge_defaults();
+    let edges = g.edges();
+    let mut emitted = Emitted::default();
+    for s in g.subgraphs().iter() {
+        let walked = walk_subgraph(
+            g,
+            &mut flowchart,
+            s,
+            1,
+            &edges,
+            &node_defaults,
+            &edge_defaults,
+        )?;
+        emitted.extend(walked);
+    }
+    for n in g.nodes().iter() {
+        if !emitted.nodes.contains(&g.node_id(n)) {
+            flowchart.visit_node(n, 1, &node_defaults)?;
+        }
+    }
+    for (i, e) in edges.iter().enumerate() {
+        if !emitted.edges.contains(&i) {
+            flowchart.visit_edge(e, 1, &edge_defaults)?;
+        }
+    }
+    let Flowchart { mut p, styles, .. } = flowchart;
+    for style in &styles {
+        p.line(1, style)?;
+    }
+
+    w.write_all(&p.finish()?)?;
+    Ok(())
+}
+
+/// The state of a flowchart being written.
+struct Flowchart<'a, 'o, G> {
+    graph: &'a G,
+    stylesheet: Stylesheet<'a>,
+    options: &'o RenderOptions,
+    p: Printer<'o, Vec<u8>>,
+    /// The Mermaid id of each node.
+    ids: HashMap<Id<'a>, String>,
+    /// The Mermaid ids of the nodes and subgraphs.
+    used: HashSet<String>,
+    /// The number of edges written, which Mermaid styles them by.
+    links: usize,
+    /// The `style`, `linkStyle` and `click` statements, written at the end.
+    styles: Vec<String>,
+}
+
+/// Words that Mermaid does not take as ids.
+const KEYWORDS: [&str; 12] = [
+    "end",
+    "graph",
+    "flowchart",
+    "subgraph",
+    "direction",
+    "style",
+    "linkstyle",
+    "classdef",
+    "class",
+    "click",
+    "call",
+    "href",
+];
+
+impl<'a, N, E, S, G> Flowchart<'a, '_, G>
+where
+    N: Clone + 'a,
+    E: Clone + 'a,
+    S: Clone + 'a,
+    G: Labeller<'a, Node = N, Edge = E, Subgraph = S>
+        + GraphWalk<'a, Node = N, Edge = E, Subgraph = S>,
+{
+    /// Returns the Mermaid id of the node `id`, choosing one the first time
+    /// the node is seen, as nodes of subgraphs need not be in `nodes`.
+    fn name(&mut self, id: &Id<'a>) -> String {
+        if let Some(name) = self.ids.get(id) {
+            return name.clone();
+        }
+        let name = unique_id(id, &mut self.used, &KEYWORDS);
+        self.ids.insert(id.clone(), name.clone());
+        name
+    }
+
+    /// Returns the link for the edge `e`, which takes its attributes from
+    /// `defaults` unless it sets them itself, with the style of the link.
+    fn edge(&mut self, e: &E, defaults: &Attrs<'a>) -> (String, String) {
+        let g = self.graph;
+        let mut source = self.name(&g.node_id(&g.source(e)));
+        let mut target = self.name(&g.node_id(&g.target(e)));
+        let mut attrs = defaults.clone();
+        attrs.extend(resolve_edge_attrs(g, &self.stylesheet, e, self.options));
+
+        let styles = styles(&attrs);
+        let (body, plain) = if styles.contains(Style::Bold) {
+            ("==", "===")
+        } else if styles.contains(Style::Dashed) || styles.contains(Style::Dotted) {
+            ("-.-", "-.-")
+        } else {
+            ("--", "---")
+        };
+
+        let dir = match attrs.get("dir") {
+            Some(Attr::Dir(dir)) => *dir,
+            _ if g.kind() == GraphKind::Directed => Dir::Forward,
+            _ => Dir::None,
+        };
+        let head = match (dir, attrs.get("arrowhead")) {
+            (Dir::Forward | Dir::Both, Some(Attr::ArrowHead(arrow))) => arrowhead(arrow),
+            (Dir::Forward | Dir::Both, _) => Some('>'),
+            _ => None,
+        };
+        let tail = match (dir, attrs.get("arrowtail")) {
+            (Dir::Back | Dir::Both, Some(Attr::ArrowTail(arrow))) => arrowhead(arrow),
+            (Dir::Back | Dir::Both, _) => Some('>'),
+            _ => None,
+        };
+        let link = match (tail, head) {
+            (None, None) => plain.to_owned(),
+            (Some(tail), None) => {
+                std::mem::swap(&mut source, &mut target);
+                format!("{body}{tail}")
+            }
+            (None, Some(head)) => format!("{body}{head}"),
+            (Some(_), Some(head)) => {
+                let start = if head == '>' { '<' } else { head };
+                format!("{start}{body}{head}")
+            }
+        };
+
+        let label = match attrs.get("label") {
+            Some(Attr::Label(label)) if !label.is_empty() => {
+                let name = format!("{source}->{target}");
+                format!("|{}|", escape(&label.plain_lines(&name), true))
+            }
+            _ => String::new(),
+        };
+        (
+            format!("{source} {link}{label} {target}"),
+            css(&attrs, true),
+        )
+    }
+}
+
+impl<'a, N, E, S, G> SubgraphVisitor<'a, N, E, S> for Flowchart<'a, '_, G>
+where
+    N: Clone + 'a,
+    E: Clone + 'a,
+    S: Clone + 'a,
+    G: Labeller<'a, Node = N, Edge = E, Subgraph = S>
+        + GraphWalk<'a, Node = N, Edge = E, Subgraph = S>,
+{
+    type Error = RenderError;
+
+    /// Writes the subgraph `s` as a Mermaid subgraph if it is a cluster.
+    fn enter(
+        &mut self,
+        s: &S,
+        depth: usize,
+        _: &Attrs<'a>,
+        _: &Attrs<'a>,
+    ) -> Result<usize, RenderError> {
+        let g = self.graph;
+        let id = g.subgraph_id(s);
+        let Some(id) = id.filter(|id| id.starts_with("cluster")) else {
+            return Ok(depth);
+        };
+        let name = unique_id(&id, &mut self.used, &KEYWORDS);
+        let attrs = resolve_subgraph_attrs(g, &self.stylesheet, s, self.options);
+        // A blank title, as Mermaid would show the id otherwise.
+        let label = match attrs.get("label") {
+            Some(Attr::Label(label)) if !label.is_empty() => escape(&label.plain_lines(&id), false),
+            _ => " ".to_owned(),
+        };
+        self.p
+            .line(depth, format_args!("subgraph {name} [\"{label}\"]"))?;
+        let css = css(&attrs, false);
+        if !css.is_empty() {
+            self.styles.push(format!("style {name} {css}"));
+        }
+        Ok(depth + 1)
+    }
+
+    fn visit_node(&mut self, n: &N, depth: usize, defaults: &Attrs<'a>) -> Result<(), RenderError> {
+        let g = self.graph;
+        let id = g.node_id(n);
+        let name = self.name(&id);
+        let mut attrs = defaults.clone();
+        attrs.extend(resolve_node_attrs(g, &self.stylesheet, n, self.options));
+
+        let label = match attrs.get("label") {
+            Some(Attr::Label(label)) => label.plain_lines(&id),
+            _ => vec![id[..].to_owned()],
+        };
+        let shape = match attrs.get("shape") {
+            Some(Attr::Shape(shape)) => Some(shape),
+            _ => None,
+        };
+        let styles = styles(&attrs);
+        let (open, close) = delimiters(shape, styles.contains(Style::Rounded));
+        self.p.line(
+            depth,
+            format_args!("{name}{open}\"{}\"{close}", escape(&label, false)),
+        )?;
+
+        let css = css(&attrs, false);
+        if !css.is_empty() {
+            self.styles.push(format!("style {name} {css}"));
+        }
+        if let Some(Attr::Url(url) | Attr::Href(url)) = attrs.get("URL").or(attrs.get("href")) {
+            let url = url.plain_lines(&id).concat().replace('"', "%22");
+            let tooltip = match attrs.get("tooltip") {
+                Some(Attr::Tooltip(tooltip)) => {
+                    format!(" \"{}\"", escape(&tooltip.plain_lines(&id), false))
+                }
+                _ => String::new(),
+            };
+            self.styles
+                .push(format!("click {name} href \"{url}\"{tooltip}"));
+        }
+        Ok(())
+    }
+
+    /// Writes the link for the edge `e`, and saves its style for the end.
+    fn visit_edge(&mut self, e: &E, depth: usize, defaults: &Attrs<'a>) -> Result<(), RenderError> {
+        let (link, css) = self.edge(e, defaults);
+        self.p.line(depth, link)?;
+        if !css.is_empty() {
+            self.styles.push(format!("linkStyle {} {css}", self.links));
+        }
+        self.links += 1;
+        Ok(())
+    }
+
+    fn exit(&mut self, depth: usize, inner: usize) -> Result<(), RenderError> {
+        if inner > depth {
+            self.p.line(depth, "end")?;
+        }
+        Ok(())
+    }
+}
+
+/// The brackets around the label of a node with the shape `shape`.
+fn delimiters(shape: Option<&Shape>, rounded: bool) -> (&'static str, &'static str) {
+    match shape {
+        Some(Shape::Ellipse | Shape::Oval | Shape::Egg) => ("([", "])"),
+        Some(Shape::Circle | Shape::Point) => ("((", "))"),
+        Some(Shape::DoubleCircle) => ("(((", ")))"),
+        Some(Shape::Diamond | Shape::MDiamond) => ("{", "}"),
+        Some(
+            Shape::Hexagon
+            | Shape::Septagon
+            | Shape::Octagon
+            | Shape::DoubleOctagon
+            | Shape::TripleOctagon,
+        ) => ("{{", "}}"),
+        Some(Shape::Cylinder) => ("[(", ")]"),
+        Some(Shape::Component | Shape::Box3d) => ("[[", "]]"),
+        Some(Shape::Parallelogram) => ("[/", "/]"),
+        Some(Shape::Trapezium | Shape::Triangle | Shape::House) => ("[/", "\\]"),
+        Some(Shape::InvTrapezium | Shape::InvTriangle | Shape::InvHouse) => ("[\\", "/]"),
+        Some(Shape::RArrow | Shape::Cds | Shape::Signature) => (">", "]"),
+        Some(Shape::MRecord) => ("(", ")"),
+        Some(Shape::Polygon(polygon)) => match polygon.sides {
+            3 => ("[/", "\\]"),
+            4 if polygon.skew > 0.0 => ("[/", "/]"),
+            4 if polygon.skew < 0.0 => ("[\\", "\\]"),
+            // A positive distortion makes the top wider than the bottom.
+            4 if polygon.distortion > 0.0 => ("[\\", "/]"),
+            4 if polygon.distortion < 0.0 => ("[/", "\\]"),
+            5.. => ("{{", "}}"),
+            _ if rounded => ("(", ")"),
+            _ => ("[", "]"),
+        },
+        _ if rounded => ("(", ")"),
+        _ => ("[", "]"),
+    }
+}
+
+/// The Mermaid arrowhead closest to `arrow`, or `None` for no arrowhead.
+fn arrowhead(arrow: &Arrow) -> Option<char> {
+    match arrow.arrows.first() {
+        Some(ArrowVertex::None) => None,
+        Some(ArrowVertex::Dot(_)) => Some('o'),
+        Some(ArrowVertex::Tee(_)) => Some('x'),
+        _ => Some('>'),
+    }
+}
+
+/// The CSS for the colors, pen width and dashes among `attrs`, such as
+/// `fill:yellow,stroke:red`, for a link if `link` is set.
+fn css(attrs: &Attrs<'_>, link: bool) -> String {
+    let styles = styles(attrs);
+    let color = match attrs.get("color") {
+        Some(Attr::Color(color)) => color.to_css(),
+        _ => None,
+    };
+    let mut css = Vec::new();
+    if !link {
+        // Graphviz fills with the outline color if there is no fill color.
+        let fill = match attrs.get("fillcolor") {
+            Some(Attr::FillColor(fill)) => fill.to_css(),
+            _ if styles.contains(Style::Filled) => color.clone(),
+            _ => None,
+        };
+        if let Some(fill) = fill {
+            css.push(format!("fill:{fill}"));
+        }
+    }
+    if let Some(color) = color {
+        css.push(format!("stroke:{color}"));
+    }
+    if let Some(Attr::FontColor(color)) = attrs.get("fontcolor") {
+        if let Some(color) = color.to_css() {
+            css.push(format!("color:{color}"));
+        }
+    }
+    let width = match attrs.get("penwidth") {
+        Some(Attr::PenWidth(width)) => Some(*width),
+        _ if styles.line_width().is_some() => styles.line_width(),
+        _ if styles.contains(Style::Bold) && !link => Some(2.0),
+        _ => None,
+    };
+    if let Some(width) = width {
+        css.push(format!("stroke-width:{width}px"));
+    }
+    if !link {
+        if styles.contains(Style::Dashed) {
+            css.push("stroke-dasharray:5 5".to_owned());
+        } else if styles.contains(Style::Dotted) {
+            css.push("stroke-dasharray:2 2".to_owned());
+        }
+    }
+    css.join(",")
+}
+
+/// Escapes `lines` for a quoted Mermaid label, or for an edge label between
+/// `|`s if `link` is set, and joins them with line breaks.
+fn escape(lines: &[String], link: bool) -> String {
+    let mut out = String::new();
+    for (i, line) in lines.iter().enumerate() {
+        if i > 0 {
+            out.push_str("<br>");
+        }
+        for c in line.chars() {
+            match c {
+                '#' => out.push_str("#35;"),
+                '"' => out.push_str("#quot;"),
+                '<' => out.push_str("#lt;"),
+                '>' => out.push_str("#gt;"),
+                '&' => out.push_str("#amp;"),
+                '|' if link => out.push_str("#124;"),
+                c => out.push(c),
+            }
+        }
+    }
+    out
+}
@@ -0,0 +1,294 @@
+//! Colors picked by key or by value.
+//!
+//! A [`Palette`] is a list of visually distinct colors, such as the
+//! [Brewer](https://colorbrewer2.org/) qualitative schemes or the
+//! colorblind-safe [Okabe-Ito](https://jfly.uni-koeln.de/color/) colors, and
+//! gives any [`Hash`] key, such as a crate name or a thread id, the same
+//! color every time. A [`Scale`] maps numbers, such as timings, to colors
+//! along a sequential or diverging gradient.
+//!
+//! Both return a [`Color`] that can be returned straight from
+//! [`Labeller::node_color`](crate::Labeller::node_color) and
+//! [`Labeller::edge_color`](crate::Labeller::edge_color).
+//!
+//! ```rust
+//! use dotwalk::palette::{Palette, Scale};
+//! use dotwalk::Color;
+//!
+//! let palette = Palette::okabe_ito();
+//! assert_eq!(palette.color_for("serde"), palette.color_for("serde"));
+//! assert_eq!(Palette::set1().color(2), Color::brewer("set19", 3));
+//!
+//! let heat = Scale::heat().with_domain(0.0, 200.0);
+//! assert_eq!(heat.color(0.0), Color::rgb(0xff, 0xff, 0xcc));
+//! assert_eq!(heat.color(500.0), Color::rgb(0x80, 0x00, 0x26));
+//! ```
+
+use std::borrow::Cow;
+use std::hash::{Hash, Hasher};
+
+use crate::Color;
+
+/// A list of distinct colors for telling categories apart.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Palette {
+    colors: Vec<Color<'static>>,
+}
+
+impl Palette {
+    /// A palette of the colors `colors`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `colors` is empty.
+    pub fn new(colors: impl IntoIterator<Item = Color<'static>>) -> Self {
+        let colors: Vec<_> = colors.into_iter().collect();
+        assert!(!colors.is_empty(), "a palette needs at least one color");
+        Palette { colors }
+    }
+
+    /// The `len` colors of the Graphviz Brewer scheme `scheme`, such as
+    /// `set19` or `paired12`, which are written as `/set19/1` and so on.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `len` is zero.
+    pub fn brewer(scheme: impl Into<Cow<'static, str>>, len: u32) -> Self {
+        let scheme = scheme.into();
+        Palette::new((1..=len).map(|i| Color::brewer(scheme.clone(), i)))
+    }
+
+    /// The nine colors of the Brewer `Set1` scheme.
+    pub fn set1() -> Self {
+        Palette::brewer("set19", 9)
+    }
+
+    /// The eight colors of the Brewer `Set2` scheme.
+    pub fn set2() -> Self {
+        Palette::brewer("set28", 8)
+    }
+
+    /// The twelve colors of the Brewer `Set3` scheme.
+    pub fn set3() -> Self {
+        Palette::brewer("set312", 12)
+    }
+
+    /// The eight colors of the Brewer `Dark2` scheme.
+    pub fn dark2() -> Self {
+        Palette::brewer("dark28", 8)
+    }
+
+    /// The nine colors of the Brewer `Pastel1` scheme.
+    pub fn pastel1() -> Self {
+        Palette::brewer("pastel19", 9)
+    }
+
+    /// The twelve colors of the Brewer `Paired` scheme, in light and dark
+    /// pairs.
+    pub fn paired() -> Self {
+        Palette::brewer("paired12", 12)
+    }
+
+    /// The ten colors of the Tableau 10 palette.
+    pub fn tableau10() -> Self {
+        Palette::rgb(&[
+            0x4e79a7, 0xf28e2b, 0xe15759, 0x76b7b2, 0x59a14f, 0xedc948, 0xb07aa1, 0xff9da7,
+            0x9c755f, 0xbab0ac,
+        ])
+    }
+
+    /// The eight Okabe-Ito colors, which stay distinct for the common kinds
+    /// of color blindness.
+    pub fn okabe_ito() -> Self {
+        Palette::rgb(&[
+            0xe69f00, 0x56b4e9, 0x009e73, 0xf0e442, 0x0072b2, 0xd55e00, 0xcc79a7, 0x000000,
+        ])
+    }
+
+    fn rgb(colors: &[u32]) -> Self {
+        Palette::new(colors.iter().map(|&rgb| {
+            let [_, r, g, b] = rgb.to_be_bytes();
+            Color::rgb(r, g, b)
+        }))
+    }
+
+    /// The number of colors.
+    pub fn len(&self) -> usize {
+        self.colors.len()
+    }
+
+    /// Always `false`, as a palette has at least one color.
+    pub fn is_empty(&self) -> bool {
+        self.colors.is_empty()
+    }
+
+    pub fn colors(&self) -> &[Color<'static>] {
+        &self.colors
+    }
+
+    /// The color at `index`, starting over from the first color once the
+    /// palette runs out.
+    pub fn color(&self, index: usize) -> Color<'static> {
+        self.colors[index % self.colors.len()].clone()
+    }
+
+    /// The color for `key`, which is the same for equal keys on every run
+    /// and platform. Keys share a color once there are more of them than
+    /// colors, so use [`Palette::color`] with an index where every key must
+    /// get its own color.
+    pub fn color_for<K: Hash + ?Sized>(&self, key: &K) -> Color<'static> {
+        let mut hasher = StableHasher::default();
+        key.hash(&mut hasher);
+        self.color((hasher.finish() % self.colors.len() as u64) as usize)
+    }
+}
+
+/// 64-bit FNV-1a, which unlike the standard library hasher is documented to
+/// stay the same between Rust releases.
+struct StableHasher(u64);
+
+impl Default for StableHasher {
+    fn default() -> Self {
+        StableHasher(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for StableHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = (self.0 ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+
+    // Integers are hashed as little-endian, and sizes as 64 bits, so that
+    // they do not depend on the platform.
+
+    fn write_u16(&mut self, i: u16) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u128(&mut self, i: u128) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64);
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+/// A gradient through evenly spaced colors, mapping numbers in a domain to
+/// colors.
+///
+/// A sequential scale, such as [`Scale::heat`], runs from light to dark. A
+/// diverging scale, such as [`Scale::red_blue`], runs from one dark color
+/// through a light middle to another, and is usually given a midpoint with
+/// [`Scale::with_midpoint`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Scale {
+    stops: Vec<(u8, u8, u8)>,
+    min: f64,
+    max: f64,
+    midpoint: Option<f64>,
+}
+
+impl Scale {
+    /// A scale through the colors `stops` over the domain 0 to 1.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `stops` is empty.
+    pub fn new(stops: impl IntoIterator<Item = (u8, u8, u8)>) -> Self {
+        let stops: Vec<_> = stops.into_iter().collect();
+        assert!(!stops.is_empty(), "a scale needs at least one color");
+        Scale {
+            stops,
+            min: 0.0,
+            max: 1.0,
+            midpoint: None,
+        }
+    }
+
+    fn rgb(stops: &[u32]) -> Self {
+        Scale::new(stops.iter().map(|&rgb| {
+            let [_, r, g, b] = rgb.to_be_bytes();
+            (r, g, b)
+        }))
+    }
+
+    /// The sequential viridis scale, from dark purple to yellow.
+    pub fn viridis() -> Self {
+        Scale::rgb(&[0x440154, 0x3b528b, 0x21918c, 0x5ec962, 0xfde725])
+    }
+
+    /// The sequential Brewer `Blues` scale, from white to dark blue.
+    pub fn blues() -> Self {
+        Scale::rgb(&[0xf7fbff, 0xc6dbef, 0x6baed6, 0x2171b5, 0x08306b])
+    }
+
+    /// The sequential Brewer `YlOrRd` scale, from pale yellow to dark red.
+    pub fn heat() -> Self {
+        Scale::rgb(&[0xffffcc, 0xfed976, 0xfd8d3c, 0xe31a1c, 0x800026])
+    }
+
+    /// The diverging Brewer `RdBu` scale, from red through white to blue.
+    pub fn red_blue() -> Self {
+        Scale::rgb(&[
+            0xb2182b, 0xef8a62, 0xfddbc7, 0xf7f7f7, 0xd1e5f0, 0x67a9cf, 0x2166ac,
+        ])
+    }
+
+    /// Maps `min` to the first color and `max` to the last.
+    pub fn with_domain(self, min: f64, max: f64) -> Self {
+        Scale { min, max, ..self }
+    }
+
+    /// Maps `midpoint` to the middle of the scale, rather than the middle of
+    /// the domain, so that values on either side of it are spread over
+    /// their own half of the colors.
+    pub fn with_midpoint(self, midpoint: f64) -> Self {
+        Scale {
+            midpoint: Some(midpoint),
+            ..self
+        }
+    }
+
+    /// The color for `value`. Values outside of the domain get the color at
+    /// its nearest end, and NaN gets the first color.
+    pub fn color(&self, value: f64) -> Color<'static> {
+        let t = match self.midpoint {
+            _ if value.is_nan() => 0.0,
+            Some(mid) if value < mid => 0.5 * fraction(self.min, mid, value),
+            Some(mid) => 0.5 + 0.5 * fraction(mid, self.max, value),
+            None => fraction(self.min, self.max, value),
+        };
+
+        let last = self.stops.len() - 1;
+        let position = t * last as f64;
+        let i = (position.floor() as usize).min(last.saturating_sub(1));
+        let (r0, g0, b0) = self.stops[i];
+        let (r1, g1, b1) = self.stops[(i + 1).min(last)];
+        let f = position - i as f64;
+        let mix = |a: u8, b: u8| (f64::from(a) + (f64::from(b) - f64::from(a)) * f).round() as u8;
+        Color::rgb(mix(r0, r1), mix(g0, g1), mix(b0, b1))
+    }
+}
+
+/// How far `value` is from `start` to `end`, from 0 to 1.
+fn fraction(start: f64, end: f64, value: f64) -> f64 {
+    match end == start {
+        true => 0.5,
+        false => ((value - start) / (end - start)).clamp(0.0, 1.0),
+    }
+}
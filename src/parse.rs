@@ -0,0 +1,717 @@
+//! Parse text in the [DOT language](https://www.graphviz.org/doc/info/lang.html)
+//! into a typed syntax tree.
+//!
+//! The tree mirrors the abstract grammar of the language: a [`Graph`] holds
+//! a list of [`Stmt`]s, which are node, edge and attribute statements,
+//! `ID = ID` assignments, or nested [`Subgraph`]s. Comments and `#`
+//! preprocessor output lines are skipped, and double-quoted strings joined
+//! with `+` are concatenated.
+//!
+//! Identifiers borrow from the input whenever possible, and keep the form
+//! they were written in (see [`IdKind`]). Quoted strings keep their content
+//! exactly as written, except that `\"` is unescaped and escaped newlines
+//! are removed, so attribute values are represented as [`Text::Esc`] (or
+//! [`Text::Html`] for HTML strings).
+//!
+//! ```rust
+//! use dotwalk as dot;
+//! use dot::parse::{AttrTarget, Stmt};
+//!
+//! let graph = dot::parse(r#"digraph g { node [shape=box]; a -> b -> c }"#).unwrap();
+//!
+//! assert_eq!(graph.kind, dot::GraphKind::Directed);
+//! assert_eq!(graph.id.as_deref().map(|id| &id[..]), Some("g"));
+//! assert!(matches!(&graph.stmts[0], Stmt::Attr(s) if s.target == AttrTarget::Node));
+//! assert!(matches!(&graph.stmts[1], Stmt::Edge(s) if s.chain.len() == 3));
+//! ```
+
+use std::borrow::Cow;
+
+use crate::types::{self, IdKind};
+use crate::{Arrow, ArrowError, CompassPoint, GraphKind, Id, Text};
+
+/// Parses `input` as a single DOT graph.
+pub fn parse(input: &str) -> Result<Graph<'_>, ParseError> {
+    let mut parser = Parser::new(input);
+    let graph = parser.graph()?;
+    match parser.next()? {
+        None => Ok(graph),
+        Some((token, offset)) => Err(parser.unexpected(Some((token, offset)), "end of input")),
+    }
+}
+
+/// A `graph` or `digraph`, the root of the syntax tree.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Graph<'a> {
+    /// Whether the graph was declared `strict`, forbidding multi-edges.
+    pub strict: bool,
+    pub kind: GraphKind,
+    pub id: Option<Id<'a>>,
+    pub stmts: Vec<Stmt<'a>>,
+}
+
+/// A statement in the body of a graph or subgraph.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Stmt<'a> {
+    Node(NodeStmt<'a>),
+    Edge(EdgeStmt<'a>),
+    Attr(AttrStmt<'a>),
+    /// A graph attribute given as `ID = ID`.
+    Assign(Attribute<'a>),
+    Subgraph(Subgraph<'a>),
+}
+
+/// A `name = value` pair, either in an attribute list or as a statement.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Attribute<'a> {
+    pub name: Id<'a>,
+    pub value: Text<'a>,
+}
+
+/// Looks up the value of the last attribute named `name`, which is the one
+/// that takes effect.
+pub fn find_attr<'b, 'a>(attrs: &'b [Attribute<'a>], name: &str) -> Option<&'b Text<'a>> {
+    attrs
+        .iter()
+        .rev()
+        .find(|attr| attr.name[..] == *name)
+        .map(|attr| &attr.value)
+}
+
+/// A node statement, such as `a:p1:n [label="A"]`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NodeStmt<'a> {
+    pub node: NodeId<'a>,
+    pub attrs: Vec<Attribute<'a>>,
+}
+
+/// A reference to a node, optionally with a port and compass point.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NodeId<'a> {
+    pub id: Id<'a>,
+    pub port: Option<Id<'a>>,
+    pub compass: Option<CompassPoint>,
+}
+
+/// One operand of an edge statement.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Vertex<'a> {
+    Node(NodeId<'a>),
+    Subgraph(Subgraph<'a>),
+}
+
+/// An edge statement, such as `a -> b -> { c d } [color=red]`.
+///
+/// The `chain` holds at least two operands; an edge connects each
+/// consecutive pair.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EdgeStmt<'a> {
+    pub chain: Vec<Vertex<'a>>,
+    pub attrs: Vec<Attribute<'a>>,
+}
+
+impl EdgeStmt<'_> {
+    /// The parsed `arrowhead` attribute, if one is present.
+    pub fn arrowhead(&self) -> Option<Result<Arrow, ArrowError>> {
+        self.arrow("arrowhead")
+    }
+
+    /// The parsed `arrowtail` attribute, if one is present.
+    pub fn arrowtail(&self) -> Option<Result<Arrow, ArrowError>> {
+        self.arrow("arrowtail")
+    }
+
+    fn arrow(&self, name: &str) -> Option<Result<Arrow, ArrowError>> {
+        find_attr(&self.attrs, name).map(|value| value.clone().into_inner().parse())
+    }
+}
+
+/// The kind of statement that an [`AttrStmt`] sets defaults for.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum AttrTarget {
+    Graph,
+    Node,
+    Edge,
+}
+
+/// An attribute statement, such as `node [shape=box]`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AttrStmt<'a> {
+    pub target: AttrTarget,
+    pub attrs: Vec<Attribute<'a>>,
+}
+
+/// A subgraph, either introduced with the `subgraph` keyword or as an
+/// anonymous `{ ... }` block.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Subgraph<'a> {
+    pub id: Option<Id<'a>>,
+    pub stmts: Vec<Stmt<'a>>,
+}
+
+/// The error returned when the input is not valid DOT.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseError {
+    pub kind: ParseErrorKind,
+    /// The line of the error, starting from 1.
+    pub line: usize,
+    /// The column of the error in characters, starting from 1.
+    pub column: usize,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParseErrorKind {
+    UnexpectedChar(char),
+    UnterminatedString,
+    UnterminatedHtml,
+    UnterminatedComment,
+    /// A token other than `expected` was found.
+    UnexpectedToken {
+        expected: &'static str,
+        found: String,
+    },
+    /// The input ended where `expected` was required.
+    UnexpectedEof {
+        expected: &'static str,
+    },
+    /// An edge operator not matching the graph kind, such as `->` in a `graph`.
+    EdgeOpMismatch(GraphKind),
+}
+
+impl std::error::Error for ParseError {}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}: ", self.line, self.column)?;
+        match &self.kind {
+            ParseErrorKind::UnexpectedChar(c) => write!(f, "unexpected character '{c}'"),
+            ParseErrorKind::UnterminatedString => write!(f, "unterminated string"),
+            ParseErrorKind::UnterminatedHtml => write!(f, "unterminated HTML string"),
+            ParseErrorKind::UnterminatedComment => write!(f, "unterminated comment"),
+            ParseErrorKind::UnexpectedToken { expected, found } => {
+                write!(f, "expected {expected}, found {found}")
+            }
+            ParseErrorKind::UnexpectedEof { expected } => {
+                write!(f, "expected {expected}, found end of input")
+            }
+            ParseErrorKind::EdgeOpMismatch(kind) => write!(
+                f,
+                "edge operator must be '{}' in a {}",
+                kind.as_edge_op(),
+                kind.as_keyword()
+            ),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token<'a> {
+    Strict,
+    Graph,
+    Digraph,
+    Node,
+    Edge,
+    Subgraph,
+    Id(Cow<'a, str>, IdKind),
+    LBrace,
+    RBrace,
+    LBracket,
+    RBracket,
+    Semi,
+    Comma,
+    Equals,
+    Colon,
+    EdgeOp(GraphKind),
+}
+
+impl Token<'_> {
+    fn describe(&self) -> String {
+        match self {
+            Token::Strict => "`strict`".to_owned(),
+            Token::Graph => "`graph`".to_owned(),
+            Token::Digraph => "`digraph`".to_owned(),
+            Token::Node => "`node`".to_owned(),
+            Token::Edge => "`edge`".to_owned(),
+            Token::Subgraph => "`subgraph`".to_owned(),
+            Token::Id(s, _) => format!("ID `{s}`"),
+            Token::LBrace => "`{`".to_owned(),
+            Token::RBrace => "`}`".to_owned(),
+            Token::LBracket => "`[`".to_owned(),
+            Token::RBracket => "`]`".to_owned(),
+            Token::Semi => "`;`".to_owned(),
+            Token::Comma => "`,`".to_owned(),
+            Token::Equals => "`=`".to_owned(),
+            Token::Colon => "`:`".to_owned(),
+            Token::EdgeOp(kind) => format!("`{}`", kind.as_edge_op()),
+        }
+    }
+}
+
+fn keyword_token(word: &str) -> Option<Token<'static>> {
+    Some(match types::keyword(word)? {
+        "strict" => Token::Strict,
+        "graph" => Token::Graph,
+        "digraph" => Token::Digraph,
+        "node" => Token::Node,
+        "edge" => Token::Edge,
+        _ => Token::Subgraph,
+    })
+}
+
+pub(crate) fn compass_point(name: &str) -> Option<CompassPoint> {
+    use CompassPoint as C;
+    Some(match name {
+        "n" => C::North,
+        "ne" => C::NorthEast,
+        "e" => C::East,
+        "se" => C::SouthEast,
+        "s" => C::South,
+        "sw" => C::SouthWest,
+        "w" => C::West,
+        "nw" => C::NorthWest,
+        "c" => C::Center,
+        _ => return None,
+    })
+}
+
+struct Lexer<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl<'a> Lexer<'a> {
+    fn error(&self, offset: usize, kind: ParseErrorKind) -> ParseError {
+        let before = &self.input[..offset];
+        let line = before.matches('\n').count() + 1;
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        let column = before[line_start..].chars().count() + 1;
+        ParseError { kind, line, column }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.input[self.pos..]
+    }
+
+    fn skip_line(&mut self) {
+        self.pos = match self.rest().find('\n') {
+            Some(i) => self.pos + i + 1,
+            None => self.input.len(),
+        };
+    }
+
+    fn skip_trivia(&mut self) -> Result<(), ParseError> {
+        loop {
+            let rest = self.rest();
+            let Some(c) = rest.chars().next() else {
+                return Ok(());
+            };
+            if c.is_whitespace() {
+                self.pos += c.len_utf8();
+            } else if rest.starts_with("//") {
+                self.skip_line();
+            } else if let Some(comment) = rest.strip_prefix("/*") {
+                match comment.find("*/") {
+                    Some(i) => self.pos += i + 4,
+                    None => return Err(self.error(self.pos, ParseErrorKind::UnterminatedComment)),
+                }
+            } else if c == '#' && (self.pos == 0 || self.input[..self.pos].ends_with('\n')) {
+                self.skip_line();
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    fn next_token(&mut self) -> Result<Option<(Token<'a>, usize)>, ParseError> {
+        self.skip_trivia()?;
+        let start = self.pos;
+        let rest = self.rest();
+        let Some(c) = rest.chars().next() else {
+            return Ok(None);
+        };
+        let punct = match c {
+            '{' => Some(Token::LBrace),
+            '}' => Some(Token::RBrace),
+            '[' => Some(Token::LBracket),
+            ']' => Some(Token::RBracket),
+            ';' => Some(Token::Semi),
+            ',' => Some(Token::Comma),
+            '=' => Some(Token::Equals),
+            ':' => Some(Token::Colon),
+            _ => None,
+        };
+        let token = if let Some(token) = punct {
+            self.pos += 1;
+            token
+        } else if rest.starts_with("->") {
+            self.pos += 2;
+            Token::EdgeOp(GraphKind::Directed)
+        } else if rest.starts_with("--") {
+            self.pos += 2;
+            Token::EdgeOp(GraphKind::Undirected)
+        } else if c == '"' {
+            self.quoted()?
+        } else if c == '<' {
+            self.html()?
+        } else if c == '-' || c == '.' || c.is_ascii_digit() {
+            self.numeral()?
+        } else if types::is_ident_start(c) {
+            let len = rest
+                .find(|c| !types::is_ident_char(c))
+                .unwrap_or(rest.len());
+            self.pos += len;
+            let word = &rest[..len];
+            keyword_token(word).unwrap_or(Token::Id(Cow::Borrowed(word), IdKind::Ident))
+        } else {
+            return Err(self.error(start, ParseErrorKind::UnexpectedChar(c)));
+        };
+        Ok(Some((token, start)))
+    }
+
+    fn numeral(&mut self) -> Result<Token<'a>, ParseError> {
+        let rest = self.rest();
+        let Some(len) = types::numeral_len(rest) else {
+            let c = rest.chars().next().unwrap();
+            return Err(self.error(self.pos, ParseErrorKind::UnexpectedChar(c)));
+        };
+        self.pos += len;
+        Ok(Token::Id(Cow::Borrowed(&rest[..len]), IdKind::Numeral))
+    }
+
+    /// Lexes one double-quoted string, and any strings concatenated to it
+    /// with `+`.
+    fn quoted(&mut self) -> Result<Token<'a>, ParseError> {
+        let mut text = self.quoted_part()?;
+        loop {
+            let end = self.pos;
+            self.skip_trivia()?;
+            if !self.rest().starts_with('+') {
+                self.pos = end;
+                break;
+            }
+            self.pos += 1;
+            self.skip_trivia()?;
+            if !self.rest().starts_with('"') {
+                let kind = match self.rest().chars().next() {
+                    Some(c) => ParseErrorKind::UnexpectedChar(c),
+                    None => ParseErrorKind::UnexpectedEof {
+                        expected: "a quoted string",
+                    },
+                };
+                return Err(self.error(self.pos, kind));
+            }
+            text.to_mut().push_str(&self.quoted_part()?);
+        }
+        Ok(Token::Id(text, IdKind::Quoted))
+    }
+
+    fn quoted_part(&mut self) -> Result<Cow<'a, str>, ParseError> {
+        let start = self.pos;
+        let body = &self.input[start + 1..];
+        let mut owned: Option<String> = None;
+        let mut chars = body.char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.pos = start + 1 + i + 1;
+                    return Ok(match owned {
+                        Some(s) => Cow::Owned(s),
+                        None => Cow::Borrowed(&body[..i]),
+                    });
+                }
+                '\\' => {
+                    let s = owned.get_or_insert_with(|| body[..i].to_owned());
+                    match chars.next() {
+                        Some((_, '"')) => s.push('"'),
+                        Some((_, '\n')) => {}
+                        Some((_, '\r')) if body[i + 2..].starts_with('\n') => {
+                            chars.next();
+                        }
+                        Some((_, c)) => {
+                            s.push('\\');
+                            s.push(c);
+                        }
+                        None => s.push('\\'),
+                    }
+                }
+                c => {
+                    if let Some(s) = &mut owned {
+                        s.push(c);
+                    }
+                }
+            }
+        }
+        Err(self.error(start, ParseErrorKind::UnterminatedString))
+    }
+
+    fn html(&mut self) -> Result<Token<'a>, ParseError> {
+        let start = self.pos;
+        let mut depth = 0usize;
+        for (i, c) in self.rest().char_indices() {
+            match c {
+                '<' => depth += 1,
+                '>' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        self.pos = start + i + 1;
+                        let content = &self.input[start + 1..start + i];
+                        return Ok(Token::Id(Cow::Borrowed(content), IdKind::Html));
+                    }
+                }
+                _ => {}
+            }
+        }
+        Err(self.error(start, ParseErrorKind::UnterminatedHtml))
+    }
+}
+
+struct Parser<'a> {
+    lexer: Lexer<'a>,
+    peeked: Option<Option<(Token<'a>, usize)>>,
+}
+
+impl<'a> Parser<'a> {
+    fn new(input: &'a str) -> Self {
+        Parser {
+            lexer: Lexer { input, pos: 0 },
+            peeked: None,
+        }
+    }
+
+    fn next(&mut self) -> Result<Option<(Token<'a>, usize)>, ParseError> {
+        match self.peeked.take() {
+            Some(token) => Ok(token),
+            None => self.lexer.next_token(),
+        }
+    }
+
+    fn peek(&mut self) -> Result<Option<&Token<'a>>, ParseError> {
+        if self.peeked.is_none() {
+            self.peeked = Some(self.lexer.next_token()?);
+        }
+        Ok(self
+            .peeked
+            .as_ref()
+            .unwrap()
+            .as_ref()
+            .map(|(token, _)| token))
+    }
+
+    /// Consumes the next token if it is equal to `token`.
+    fn eat(&mut self, token: &Token<'_>) -> Result<bool, ParseError> {
+        if self.peek()? == Some(token) {
+            self.next()?;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn expect(&mut self, token: &Token<'_>, expected: &'static str) -> Result<(), ParseError> {
+        match self.next()? {
+            Some((found, _)) if found == *token => Ok(()),
+            other => Err(self.unexpected(other, expected)),
+        }
+    }
+
+    fn unexpected(&self, found: Option<(Token<'_>, usize)>, expected: &'static str) -> ParseError {
+        match found {
+            Some((token, offset)) => self.lexer.error(
+                offset,
+                ParseErrorKind::UnexpectedToken {
+                    expected,
+                    found: token.describe(),
+                },
+            ),
+            None => self.lexer.error(
+                self.lexer.input.len(),
+                ParseErrorKind::UnexpectedEof { expected },
+            ),
+        }
+    }
+
+    fn graph(&mut self) -> Result<Graph<'a>, ParseError> {
+        let strict = self.eat(&Token::Strict)?;
+        let kind = match self.next()? {
+            Some((Token::Graph, _)) => GraphKind::Undirected,
+            Some((Token::Digraph, _)) => GraphKind::Directed,
+            other => return Err(self.unexpected(other, "`graph` or `digraph`")),
+        };
+        let id = match self.peek()? {
+            Some(Token::Id(..)) => Some(self.id()?),
+            _ => None,
+        };
+        self.expect(&Token::LBrace, "`{`")?;
+        let stmts = self.stmt_list(kind)?;
+        Ok(Graph {
+            strict,
+            kind,
+            id,
+            stmts,
+        })
+    }
+
+    /// Parses statements up to and including the closing `}`.
+    fn stmt_list(&mut self, kind: GraphKind) -> Result<Vec<Stmt<'a>>, ParseError> {
+        let mut stmts = Vec::new();
+        loop {
+            match self.peek()? {
+                Some(Token::RBrace) => {
+                    self.next()?;
+                    return Ok(stmts);
+                }
+                None => return Err(self.unexpected(None, "`}`")),
+                Some(_) => {}
+            }
+            stmts.push(self.stmt(kind)?);
+            self.eat(&Token::Semi)?;
+        }
+    }
+
+    fn stmt(&mut self, kind: GraphKind) -> Result<Stmt<'a>, ParseError> {
+        let target = match self.peek()? {
+            Some(Token::Graph) => Some(AttrTarget::Graph),
+            Some(Token::Node) => Some(AttrTarget::Node),
+            Some(Token::Edge) => Some(AttrTarget::Edge),
+            _ => None,
+        };
+        if let Some(target) = target {
+            self.next()?;
+            if self.peek()? != Some(&Token::LBracket) {
+                let found = self.next()?;
+                return Err(self.unexpected(found, "`[`"));
+            }
+            let attrs = self.attr_lists()?;
+            return Ok(Stmt::Attr(AttrStmt { target, attrs }));
+        }
+
+        let first = match self.peek()? {
+            Some(Token::Subgraph | Token::LBrace) => Vertex::Subgraph(self.subgraph(kind)?),
+            Some(Token::Id(..)) => {
+                let id = self.id()?;
+                if self.eat(&Token::Equals)? {
+                    let value = self.value()?;
+                    return Ok(Stmt::Assign(Attribute { name: id, value }));
+                }
+                Vertex::Node(self.node_id(id)?)
+            }
+            _ => {
+                let found = self.next()?;
+                return Err(self.unexpected(found, "a statement"));
+            }
+        };
+
+        if let Some(Token::EdgeOp(_)) = self.peek()? {
+            let mut chain = vec![first];
+            while let Some(Token::EdgeOp(op)) = self.peek()? {
+                let op = *op;
+                let (_, offset) = self.next()?.unwrap();
+                if op != kind {
+                    return Err(self
+                        .lexer
+                        .error(offset, ParseErrorKind::EdgeOpMismatch(kind)));
+                }
+                chain.push(match self.peek()? {
+                    Some(Token::Subgraph | Token::LBrace) => Vertex::Subgraph(self.subgraph(kind)?),
+                    Some(Token::Id(..)) => {
+                        let id = self.id()?;
+                        Vertex::Node(self.node_id(id)?)
+                    }
+                    _ => {
+                        let found = self.next()?;
+                        return Err(self.unexpected(found, "a node or subgraph"));
+                    }
+                });
+            }
+            let attrs = self.attr_lists()?;
+            return Ok(Stmt::Edge(EdgeStmt { chain, attrs }));
+        }
+
+        Ok(match first {
+            Vertex::Subgraph(subgraph) => Stmt::Subgraph(subgraph),
+            Vertex::Node(node) => {
+                let attrs = self.attr_lists()?;
+                Stmt::Node(NodeStmt { node, attrs })
+            }
+        })
+    }
+
+    fn subgraph(&mut self, kind: GraphKind) -> Result<Subgraph<'a>, ParseError> {
+        let mut id = None;
+        if self.eat(&Token::Subgraph)? {
+            if let Some(Token::Id(..)) = self.peek()? {
+                id = Some(self.id()?);
+            }
+        }
+        self.expect(&Token::LBrace, "`{`")?;
+        let stmts = self.stmt_list(kind)?;
+        Ok(Subgraph { id, stmts })
+    }
+
+    /// Parses the optional port and compass point following a node `id`.
+    fn node_id(&mut self, id: Id<'a>) -> Result<NodeId<'a>, ParseError> {
+        let mut node = NodeId {
+            id,
+            port: None,
+            compass: None,
+        };
+        if !self.eat(&Token::Colon)? {
+            return Ok(node);
+        }
+        let port = self.id()?;
+        if self.eat(&Token::Colon)? {
+            let (name, offset) = match self.next()? {
+                Some((Token::Id(name, _), offset)) => (name, offset),
+                other => return Err(self.unexpected(other, "a compass point")),
+            };
+            let Some(compass) = compass_point(&name) else {
+                return Err(self.lexer.error(
+                    offset,
+                    ParseErrorKind::UnexpectedToken {
+                        expected: "a compass point",
+                        found: format!("`{name}`"),
+                    },
+                ));
+            };
+            node.port = Some(port);
+            node.compass = Some(compass);
+        } else if let Some(compass) = compass_point(&port) {
+            node.compass = Some(compass);
+        } else {
+            node.port = Some(port);
+        }
+        Ok(node)
+    }
+
+    /// Parses one or more consecutive `[ ... ]` attribute lists, if present.
+    fn attr_lists(&mut self) -> Result<Vec<Attribute<'a>>, ParseError> {
+        let mut attrs = Vec::new();
+        while self.eat(&Token::LBracket)? {
+            while !self.eat(&Token::RBracket)? {
+                let name = self.id()?;
+                self.expect(&Token::Equals, "`=`")?;
+                let value = self.value()?;
+                attrs.push(Attribute { name, value });
+                if !self.eat(&Token::Comma)? {
+                    self.eat(&Token::Semi)?;
+                }
+            }
+        }
+        Ok(attrs)
+    }
+
+    fn id(&mut self) -> Result<Id<'a>, ParseError> {
+        match self.next()? {
+            Some((Token::Id(name, kind), _)) => Ok(Id { name, kind }),
+            other => Err(self.unexpected(other, "an ID")),
+        }
+    }
+
+    fn value(&mut self) -> Result<Text<'a>, ParseError> {
+        match self.next()? {
+            Some((Token::Id(s, IdKind::Html), _)) => Ok(Text::Html(s)),
+            Some((Token::Id(s, _), _)) => Ok(Text::Esc(s)),
+            other => Err(self.unexpected(other, "an ID")),
+        }
+    }
+}
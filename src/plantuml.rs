@@ -0,0 +1,504 @@
+//! Rendering to [PlantUML](https://plantuml.com/) component diagrams.
+//!
+//! [`render`] writes a PlantUML diagram of a graph, for documents that are
+//! built with PlantUML. Nodes become elements such as `rectangle` or
+//! `database`, clusters become `package`s, and edges become arrows with
+//! their labels after a `:`. Node ids that PlantUML cannot take
+//! as they are, such as `note` or ids with spaces, are replaced by similar
+//! ones; the labels still show the original ids.
+//!
+//! ```rust
+//! use dotwalk::graph::{DotGraph, Edge, Node};
+//! use dotwalk::{plantuml, Attr, Color, GraphKind, Id, RankDir, Shape, Style, Text};
+//!
+//! let mut graph = DotGraph::new(Id::new("g").ok(), GraphKind::Directed)
+//!     .with_attr(Attr::RankDir(RankDir::LeftRight));
+//! let a = graph.add_node(Node::new(Id::new("a").unwrap()).with_label(Text::label("Start")));
+//! let b = graph.add_node(
+//!     Node::new(Id::new("b").unwrap())
+//!         .with_attr(Attr::Shape(Shape::Cylinder))
+//!         .with_attr(Attr::Color(Color::named("red"))),
+//! );
+//! graph.add_edge(
+//!     Edge::new(a, b)
+//!         .with_label(Text::label("go"))
+//!         .with_attr(Attr::Style(Style::Dashed.into())),
+//! );
+//!
+//! let mut output = Vec::new();
+//! plantuml::render(&graph, &mut output).unwrap();
+//! assert_eq!(
+//!     String::from_utf8(output).unwrap(),
+//!     r#"@startuml
+//! left to right direction
+//! rectangle "Start" as a
+//! database "b" as b #line:red
+//! a -[dashed]-> b : go
+//! @enduml
+//! "#
+//! );
+//! ```
+//!
+//! # Differences from the DOT output
+//!
+//! PlantUML lays out diagrams with Graphviz, but does not let them say
+//! everything that DOT does, so some of the graph is lost or approximated:
+//!
+//! * Nodes without a shape are drawn as rectangles rather than as ellipses.
+//!   The other shapes become the nearest PlantUML element, such as a
+//!   `database` for a cylinder or a `hexagon` for an octagon, or a
+//!   rectangle; record labels are shown as text.
+//! * As in Graphviz, only subgraphs whose ids start with `cluster` are drawn,
+//!   as packages. The other subgraphs only give their nodes and edges
+//!   defaults.
+//! * Graphs laid out bottom to top or right to left are drawn top to bottom
+//!   or left to right.
+//! * Ports and compass points are dropped.
+//! * Arrowheads become the nearest PlantUML head: an arrow, a hollow
+//!   triangle for `onormal`, a diamond, a circle for `dot`, a square for
+//!   `box`, a crow's foot, or a cross for `tee`.
+//! * The pen width of nodes and clusters is dropped, and rounded corners too.
+//! * Colors in schemes other than X11, such as the Brewer schemes, are
+//!   dropped, transparency is dropped, and color lists are drawn with their
+//!   first color.
+//! * HTML-like labels are reduced to their text, with a line for each
+//!   `<BR/>` or table row.
+//! * The other attributes, the font, the theme and the legend of the
+//!   [`RenderOptions`] are ignored, and so is compact output.
+
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+
+use crate::attrs::Dir;
+use crate::render::{
+    check_ids, resolve_edge_attrs, resolve_node_attrs, resolve_subgraph_attrs, styles, unique_id,
+    walk_subgraph, Emitted, Printer, SubgraphVisitor,
+};
+use crate::{
+    Arrow, ArrowVertex, Attr, Attrs, Color, GraphKind, GraphWalk, Id, Labeller, RankDir,
+    RenderError, RenderOptions, Shape, ShapeFill, Style, Stylesheet,
+};
+
+/// Renders graph `g` into the writer `w` as a PlantUML diagram.
+pub fn render<'a, N, E, S, G, W>(g: &'a G, w: &mut W) -> Result<(), RenderError>
+where
+    N: Clone + 'a,
+    E: Clone + 'a,
+    S: Clone + 'a,
+    G: Labeller<'a, Node = N, Edge = E, Subgraph = S>
+        + GraphWalk<'a, Node = N, Edge = E, Subgraph = S>,
+    W: Write,
+{
+    render_opts(g, w, RenderOptions::default())
+}
+
+/// Renders graph `g` into the writer `w` as a PlantUML diagram, leaving out
+/// the labels, styles, colors or arrows as `options` says, and indenting
+/// and ending lines as it says.
+pub fn render_opts<'a, N, E, S, G, W>(
+    g: &'a G,
+    w: &mut W,
+    options: impl Into<RenderOptions>,
+) -> Result<(), RenderError>
+where
+    N: Clone + 'a,
+    E: Clone + 'a,
+    S: Clone + 'a,
+    G: Labeller<'a, Node = N, Edge = E, Subgraph = S>
+        + GraphWalk<'a, Node = N, Edge = E, Subgraph = S>,
+    W: Write,
+{
+    let options = options.into().with_compact(false);
+    check_ids(g)?;
+
+    let mut diagram = Diagram {
+        graph: g,
+        stylesheet: g.stylesheet(),
+        options: &options,
+        p: Printer::new(Vec::new(), &options),
+        ids: HashMap::new(),
+        used: HashSet::new(),
+    };
+    for n in g.nodes().iter() {
+        diagram.name(&g.node_id(n));
+    }
+
+    let p = &mut diagram.p;
+    p.line(0, "@startuml")?;
+    let graph_attrs = g.graph_attrs();
+    let rank_dir = match graph_attrs.get("rankdir") {
+        Some(Attr::RankDir(rank_dir)) => Some(*rank_dir),
+        _ => g.rank_dir(),
+    };
+    if let Some(RankDir::LeftRight | RankDir::RightLeft) = rank_dir {
+        p.line(0, "left to right direction")?;
+    }
+    if let Some(Attr::Label(label)) = graph_attrs.get("label") {
+        if !label.is_empty() {
+            let title = escape(&label.plain_lines(&g.graph_id()));
+            p.line(0, format_args!("title {title}"))?;
+        }
+    }
+
+    let node_defaults = g.node_defaults();
+    let edge_defaults = g.edge_defaults();
+    let edges = g.edges();
+    let mut emitted = Emitted::default();
+    for s in g.subgraphs().iter() {
+        emitted.extend(walk_subgraph(
+            g,
+            &mut diagram,
+            s,
+            0,
+            &edges,
+            &node_defaults,
+            &edge_defaults,
+        )?);
+    }
+    for n in g.nodes().iter() {
+        if !emitted.nodes.contains(&g.node_id(n)) {
+            diagram.visit_node(n, 0, &node_defaults)?;
+        }
+    }
+    for (i, e) in edges.iter().enumerate() {
+        if !emitted.edges.contains(&i) {
+            diagram.visit_edge(e, 0, &edge_defaults)?;
+        }
+    }
+    diagram.p.line(0, "@enduml")?;
+
+    w.write_all(&diagram.p.finish()?)?;
+    Ok(())
+}
+
+/// The state of a diagram being written.
+struct Diagram<'a, 'o, G> {
+    graph: &'a G,
+    stylesheet: Stylesheet<'a>,
+    options: &'o RenderOptions,
+    p: Printer<'o, Vec<u8>>,
+    /// The PlantUML alias of each node.
+    ids: HashMap<Id<'a>, String>,
+    /// The PlantUML aliases of the nodes and packages.
+    used: HashSet<String>,
+}
+
+/// Words that PlantUML would read as the start of another statement.
+const KEYWORDS: [&str; 36] = [
+    "actor",
+    "agent",
+    "artifact",
+    "as",
+    "boundary",
+    "card",
+    "circle",
+    "cloud",
+    "collections",
+    "component",
+    "control",
+    "database",
+    "entity",
+    "file",
+    "folder",
+    "frame",
+    "hexagon",
+    "hide",
+    "interface",
+    "label",
+    "left",
+    "node",
+    "note",
+    "package",
+    "person",
+    "queue",
+    "rectangle",
+    "remove",
+    "show",
+    "skinparam",
+    "stack",
+    "storage",
+    "title",
+    "together",
+    "top",
+    "usecase",
+];
+
+impl<'a, N, E, S, G> Diagram<'a, '_, G>
+where
+    N: Clone + 'a,
+    E: Clone + 'a,
+    S: Clone + 'a,
+    G: Labeller<'a, Node = N, Edge = E, Subgraph = S>
+        + GraphWalk<'a, Node = N, Edge = E, Subgraph = S>,
+{
+    /// Returns the PlantUML id of the node `id`, choosing one the first time
+    /// the node is seen, as nodes of subgraphs need not be in `nodes`.
+    fn name(&mut self, id: &Id<'a>) -> String {
+        if let Some(name) = self.ids.get(id) {
+            return name.clone();
+        }
+        let name = unique_id(id, &mut self.used, &KEYWORDS);
+        self.ids.insert(id.clone(), name.clone());
+        name
+    }
+
+    /// Returns the arrow for the edge `e`, which takes its attributes from
+    /// `defaults` unless it sets them itself.
+    fn edge(&mut self, e: &E, defaults: &Attrs<'a>) -> String {
+        let g = self.graph;
+        let source = self.name(&g.node_id(&g.source(e)));
+        let target = self.name(&g.node_id(&g.target(e)));
+        let mut attrs = defaults.clone();
+        attrs.extend(resolve_edge_attrs(g, &self.stylesheet, e, self.options));
+
+        let dir = match attrs.get("dir") {
+            Some(Attr::Dir(dir)) => *dir,
+            _ if g.kind() == GraphKind::Directed => Dir::Forward,
+            _ => Dir::None,
+        };
+        let head = match (dir, attrs.get("arrowhead")) {
+            (Dir::Forward | Dir::Both, Some(Attr::ArrowHead(arrow))) => arrowhead(arrow, true),
+            (Dir::Forward | Dir::Both, _) => ">",
+            _ => "",
+        };
+        let tail = match (dir, attrs.get("arrowtail")) {
+            (Dir::Back | Dir::Both, Some(Attr::ArrowTail(arrow))) => arrowhead(arrow, false),
+            (Dir::Back | Dir::Both, _) => "<",
+            _ => "",
+        };
+
+        let styles = styles(&attrs);
+        let mut style = Vec::new();
+        if let Some(Attr::Color(color)) = attrs.get("color") {
+            style.extend(plantuml_color(color).map(|color| format!("#{color}")));
+        }
+        if styles.contains(Style::Dashed) {
+            style.push("dashed".to_owned());
+        } else if styles.contains(Style::Dotted) {
+            style.push("dotted".to_owned());
+        } else if styles.contains(Style::Bold) {
+            style.push("bold".to_owned());
+        }
+        let width = match attrs.get("penwidth") {
+            Some(Attr::PenWidth(width)) => Some(*width),
+            _ => styles.line_width(),
+        };
+        style.extend(width.map(|width| format!("thickness={width}")));
+        let style = match style.is_empty() {
+            true => String::new(),
+            false => format!("[{}]", style.join(",")),
+        };
+
+        let label = match attrs.get("label") {
+            Some(Attr::Label(label)) if !label.is_empty() => {
+                let name = format!("{source}->{target}");
+                format!(" : {}", escape(&label.plain_lines(&name)))
+            }
+            _ => String::new(),
+        };
+        format!("{source} {tail}-{style}-{head} {target}{label}")
+    }
+}
+
+impl<'a, N, E, S, G> SubgraphVisitor<'a, N, E, S> for Diagram<'a, '_, G>
+where
+    N: Clone + 'a,
+    E: Clone + 'a,
+    S: Clone + 'a,
+    G: Labeller<'a, Node = N, Edge = E, Subgraph = S>
+        + GraphWalk<'a, Node = N, Edge = E, Subgraph = S>,
+{
+    type Error = RenderError;
+
+    /// Writes the subgraph `s` as a package if it is a cluster.
+    fn enter(
+        &mut self,
+        s: &S,
+        depth: usize,
+        _: &Attrs<'a>,
+        _: &Attrs<'a>,
+    ) -> Result<usize, RenderError> {
+        let g = self.graph;
+        let id = g.subgraph_id(s);
+        let Some(id) = id.filter(|id| id.starts_with("cluster")) else {
+            return Ok(depth);
+        };
+        let name = unique_id(&id, &mut self.used, &KEYWORDS);
+        let attrs = resolve_subgraph_attrs(g, &self.stylesheet, s, self.options);
+        // A blank title, as PlantUML would show the alias otherwise.
+        let label = match attrs.get("label") {
+            Some(Attr::Label(label)) if !label.is_empty() => escape(&label.plain_lines(&id)),
+            _ => " ".to_owned(),
+        };
+        let colors = colors(&attrs);
+        self.p.line(
+            depth,
+            format_args!("package \"{label}\" as {name}{colors} {{"),
+        )?;
+        Ok(depth + 1)
+    }
+
+    fn visit_node(&mut self, n: &N, depth: usize, defaults: &Attrs<'a>) -> Result<(), RenderError> {
+        let g = self.graph;
+        let id = g.node_id(n);
+        let name = self.name(&id);
+        let mut attrs = defaults.clone();
+        attrs.extend(resolve_node_attrs(g, &self.stylesheet, n, self.options));
+
+        let label = match attrs.get("label") {
+            Some(Attr::Label(label)) => label.plain_lines(&id),
+            _ => vec![id[..].to_owned()],
+        };
+        let shape = match attrs.get("shape") {
+            Some(Attr::Shape(shape)) => Some(shape),
+            _ => None,
+        };
+        let mut line = format!("{} \"{}\" as {name}", element(shape), escape(&label));
+        line.push_str(&link(&attrs, &id));
+        line.push_str(&colors(&attrs));
+        self.p.line(depth, line)?;
+        Ok(())
+    }
+
+    fn visit_edge(&mut self, e: &E, depth: usize, defaults: &Attrs<'a>) -> Result<(), RenderError> {
+        let arrow = self.edge(e, defaults);
+        self.p.line(depth, arrow)?;
+        Ok(())
+    }
+
+    fn exit(&mut self, depth: usize, inner: usize) -> Result<(), RenderError> {
+        if inner > depth {
+            self.p.line(depth, "}")?;
+        }
+        Ok(())
+    }
+}
+
+/// The PlantUML element closest to a node with the shape `shape`.
+fn element(shape: Option<&Shape>) -> &'static str {
+    match shape {
+        Some(Shape::Ellipse | Shape::Oval | Shape::Egg) => "usecase",
+        Some(Shape::Circle | Shape::Point | Shape::DoubleCircle | Shape::MCircle) => "circle",
+        Some(Shape::Cylinder) => "database",
+        Some(
+            Shape::Hexagon
+            | Shape::Septagon
+            | Shape::Octagon
+            | Shape::DoubleOctagon
+            | Shape::TripleOctagon,
+        ) => "hexagon",
+        Some(Shape::Folder | Shape::Tab) => "folder",
+        Some(Shape::Note) => "file",
+        Some(Shape::Component) => "component",
+        Some(Shape::Box3d) => "node",
+        Some(Shape::PlainText | Shape::Plain | Shape::None | Shape::Underline) => "label",
+        Some(Shape::Polygon(polygon)) if polygon.sides >= 6 => "hexagon",
+        _ => "rectangle",
+    }
+}
+
+/// The PlantUML head closest to `arrow`, at the head of the arrow if `head`
+/// is set and at its tail otherwise.
+fn arrowhead(arrow: &Arrow, head: bool) -> &'static str {
+    let (at_head, at_tail) = match arrow.arrows.first() {
+        None => (">", "<"),
+        Some(ArrowVertex::None) => ("", ""),
+        Some(ArrowVertex::Normal(ShapeFill::Open, _)) => ("|>", "<|"),
+        Some(ArrowVertex::Diamond(ShapeFill::Filled, _)) => ("*", "*"),
+        Some(ArrowVertex::Diamond(ShapeFill::Open, _)) => ("o", "o"),
+        Some(ArrowVertex::Dot(_)) => ("0", "0"),
+        Some(ArrowVertex::Box(..)) => ("#", "#"),
+        Some(ArrowVertex::Crow(_)) => ("}", "{"),
+        Some(ArrowVertex::Tee(_)) => ("x", "x"),
+        Some(_) => (">", "<"),
+    };
+    match head {
+        true => at_head,
+        false => at_tail,
+    }
+}
+
+/// The color as PlantUML writes it after a `#` or `:`, such as `red` or
+/// `ff0000`.
+fn plantuml_color(color: &Color<'_>) -> Option<String> {
+    let css = color.to_css()?;
+    Some(match css.strip_prefix('#') {
+        Some(hex) => hex.get(..6).unwrap_or(hex).to_owned(),
+        None => css,
+    })
+}
+
+/// The inline style for the colors and dashes among `attrs`, such as
+/// ` #yellow;line:red;line.dashed`, or an empty string.
+fn colors(attrs: &Attrs<'_>) -> String {
+    let styles = styles(attrs);
+    let color = match attrs.get("color") {
+        Some(Attr::Color(color)) => plantuml_color(color),
+        _ => None,
+    };
+    let fill = match attrs.get("fillcolor") {
+        Some(Attr::FillColor(fill)) => plantuml_color(fill),
+        _ if styles.contains(Style::Filled) => color.clone(),
+        _ => None,
+    };
+    let mut style = Vec::new();
+    style.extend(fill);
+    style.extend(color.map(|color| format!("line:{color}")));
+    if styles.contains(Style::Dashed) {
+        style.push("line.dashed".to_owned());
+    } else if styles.contains(Style::Dotted) {
+        style.push("line.dotted".to_owned());
+    } else if styles.contains(Style::Bold) {
+        style.push("line.bold".to_owned());
+    }
+    if let Some(Attr::FontColor(color)) = attrs.get("fontcolor") {
+        style.extend(plantuml_color(color).map(|color| format!("text:{color}")));
+    }
+    match style.is_empty() {
+        true => String::new(),
+        false => format!(" #{}", style.join(";")),
+    }
+}
+
+/// The PlantUML link to the URL among `attrs` of the node `id`, with its
+/// tooltip, such as ` [[https://example.com{Example}]]`, or an empty string.
+fn link(attrs: &Attrs<'_>, id: &str) -> String {
+    let Some(Attr::Url(url) | Attr::Href(url)) = attrs.get("URL").or(attrs.get("href")) else {
+        return String::new();
+    };
+    let url = url.plain_lines(id).concat();
+    let url: String = url
+        .chars()
+        .map(|c| match c {
+            '[' | ']' | '{' | '}' | ' ' => format!("%{:02X}", c as u32),
+            c => c.to_string(),
+        })
+        .collect();
+    let tooltip = match attrs.get("tooltip") {
+        Some(Attr::Tooltip(tooltip)) => {
+            let tooltip = tooltip.plain_lines(id).join(" ");
+            format!("{{{}}}", tooltip.replace(['{', '}', '[', ']'], ""))
+        }
+        _ => String::new(),
+    };
+    format!(" [[{url}{tooltip}]]")
+}
+
+/// Escapes `lines` for a quoted PlantUML name or a label, and joins them
+/// with line breaks.
+fn escape(lines: &[String]) -> String {
+    let mut out = String::new();
+    for (i, line) in lines.iter().enumerate() {
+        if i > 0 {
+            out.push_str("\\n");
+        }
+        for c in line.chars() {
+            match c {
+                '\\' => out.push_str("\\\\"),
+                '"' => out.push_str("&#34;"),
+                c => out.push(c),
+            }
+        }
+    }
+    out
+}
@@ -0,0 +1,233 @@
+//! Labels for the `record` and `Mrecord` shapes.
+//!
+//! A [`Record`] builds the field syntax of a
+//! [record label](https://www.graphviz.org/doc/info/shapes.html#record),
+//! such as `<in> a|{b|<out> c}`, escaping the text of each field. Fields can
+//! be named by a port [`Id`], which edges can then start or end at through
+//! [`Labeller::edge_start_port`](crate::Labeller::edge_start_port) and
+//! [`Labeller::edge_end_port`](crate::Labeller::edge_end_port).
+//!
+//! ```rust
+//! use dotwalk::record::Record;
+//! use dotwalk::{Id, Text};
+//!
+//! let next = Id::new("next").unwrap();
+//! let node = Record::horizontal()
+//!     .with_field("Node<T>")
+//!     .with_record(
+//!         Record::vertical()
+//!             .with_field("value: T")
+//!             .with_port(next.clone(), "next: Option<Box<Node<T>>>"),
+//!     );
+//! assert_eq!(
+//!     node.to_string(),
+//!     r"Node\<T\>|{value: T|<next> next: Option\<Box\<Node\<T\>\>\>}"
+//! );
+//! assert_eq!(node.port("next"), Some(&next));
+//! let label: Text<'_> = node.into();
+//! ```
+
+use std::borrow::Cow;
+
+use crate::{Id, Text};
+
+/// The direction that the fields of a [`Record`] are laid out in.
+///
+/// This is for graphs drawn from top to bottom, or bottom to top. Graphviz
+/// swaps the directions of records in graphs drawn from left to right, or
+/// right to left.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum RecordDir {
+    Horizontal,
+    Vertical,
+}
+
+impl RecordDir {
+    fn flip(self) -> Self {
+        match self {
+            RecordDir::Horizontal => RecordDir::Vertical,
+            RecordDir::Vertical => RecordDir::Horizontal,
+        }
+    }
+}
+
+/// A field of a [`Record`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum Field<'a> {
+    /// Text, optionally named by a port.
+    Text {
+        port: Option<Id<'a>>,
+        text: Cow<'a, str>,
+    },
+    /// Fields grouped together, laid out in their own direction.
+    Record(Record<'a>),
+}
+
+/// The fields of a record label, laid out side by side or one above the
+/// other.
+///
+/// A record converts into a [`Text`] label, to be used with
+/// [`Shape::Record`](crate::Shape::Record) or
+/// [`Shape::MRecord`](crate::Shape::MRecord).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Record<'a> {
+    dir: RecordDir,
+    fields: Vec<Field<'a>>,
+}
+
+impl<'a> Record<'a> {
+    pub fn new(dir: RecordDir) -> Self {
+        Record {
+            dir,
+            fields: Vec::new(),
+        }
+    }
+
+    /// A record whose fields are side by side.
+    pub fn horizontal() -> Self {
+        Record::new(RecordDir::Horizontal)
+    }
+
+    /// A record whose fields are one above the other.
+    pub fn vertical() -> Self {
+        Record::new(RecordDir::Vertical)
+    }
+
+    /// Adds a field with the text `text`.
+    pub fn with_field(mut self, text: impl Into<Cow<'a, str>>) -> Self {
+        self.fields.push(Field::Text {
+            port: None,
+            text: text.into(),
+        });
+        self
+    }
+
+    /// Adds a field with the text `text`, named by the port `port`.
+    pub fn with_port(mut self, port: Id<'a>, text: impl Into<Cow<'a, str>>) -> Self {
+        self.fields.push(Field::Text {
+            port: Some(port),
+            text: text.into(),
+        });
+        self
+    }
+
+    /// Adds the fields of `record` as a group, laid out in the direction of
+    /// `record`.
+    pub fn with_record(mut self, record: Record<'a>) -> Self {
+        self.fields.push(Field::Record(record));
+        self
+    }
+
+    pub fn dir(&self) -> RecordDir {
+        self.dir
+    }
+
+    pub fn fields(&self) -> &[Field<'a>] {
+        &self.fields
+    }
+
+    /// The ports of the fields, including those of nested records, in
+    /// order.
+    pub fn ports(&self) -> Vec<&Id<'a>> {
+        let mut ports = Vec::new();
+        for field in &self.fields {
+            match field {
+                Field::Text { port, .. } => ports.extend(port),
+                Field::Record(record) => ports.extend(record.ports()),
+            }
+        }
+        ports
+    }
+
+    /// The port named `name`, if a field has it.
+    pub fn port(&self, name: &str) -> Option<&Id<'a>> {
+        self.ports().into_iter().find(|port| port[..] == *name)
+    }
+
+    /// Converts the record into one that owns its text and ports.
+    pub fn into_owned(self) -> Record<'static> {
+        Record {
+            dir: self.dir,
+            fields: self
+                .fields
+                .into_iter()
+                .map(|field| match field {
+                    Field::Text { port, text } => Field::Text {
+                        port: port.map(Id::into_owned),
+                        text: Cow::Owned(text.into_owned()),
+                    },
+                    Field::Record(record) => Field::Record(record.into_owned()),
+                })
+                .collect(),
+        }
+    }
+
+    /// Writes the fields, which are laid out in the direction `dir` by the
+    /// enclosing braces, if any.
+    fn write(&self, f: &mut std::fmt::Formatter<'_>, dir: RecordDir) -> std::fmt::Result {
+        if self.dir != dir {
+            f.write_str("{")?;
+            self.write(f, dir.flip())?;
+            return f.write_str("}");
+        }
+        for (i, field) in self.fields.iter().enumerate() {
+            if i > 0 {
+                f.write_str("|")?;
+            }
+            match field {
+                Field::Text { port, text } => {
+                    if let Some(port) = port {
+                        write!(f, "<{}> ", escape_field(port))?;
+                    }
+                    f.write_str(&escape_field(text))?;
+                }
+                Field::Record(record) if record.dir == dir => {
+                    // Braces are needed to keep the fields of a nested
+                    // record in the same direction together.
+                    f.write_str("{{")?;
+                    record.write(f, dir)?;
+                    f.write_str("}}")?;
+                }
+                Field::Record(record) => {
+                    f.write_str("{")?;
+                    record.write(f, dir.flip())?;
+                    f.write_str("}")?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for Record<'_> {
+    /// Writes the record label syntax, without the escaping of a DOT
+    /// string.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.write(f, RecordDir::Horizontal)
+    }
+}
+
+impl<'a> From<Record<'a>> for Text<'a> {
+    fn from(record: Record<'a>) -> Self {
+        Text::Esc(record.to_string().into())
+    }
+}
+
+/// Escapes the characters of `text` that have a meaning in record labels,
+/// and the spaces that Graphviz would otherwise drop, which are those at
+/// either end or next to another space.
+fn escape_field(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    let mut after_space = true;
+    while let Some(c) = chars.next() {
+        match c {
+            '{' | '}' | '|' | '<' | '>' | '\\' => out.push('\\'),
+            ' ' if after_space || matches!(chars.peek(), None | Some(' ')) => out.push('\\'),
+            _ => {}
+        }
+        out.push(c);
+        after_space = c == ' ';
+    }
+    out
+}
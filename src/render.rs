@@ -1,11 +1,20 @@
+use std::collections::HashSet;
+use std::fmt::Display;
 use std::io;
 use std::io::Write;
 
-use crate::{Edges, GraphKind, GraphWalk, Labeller, Nodes, Style, Subgraphs};
+use crate::attrs::Dir;
+use crate::html::{self, HtmlError};
+use crate::legend::Legend;
+use crate::stylesheet::ElementKind;
+use crate::{
+    Attr, Attrs, Classes, Edges, GraphKind, GraphWalk, Id, Labeller, Nodes, Styles, Stylesheet,
+    Subgraphs, Text, Theme,
+};
 
 /// Renders graph `g` into the writer `w` in DOT syntax.
 /// (Simple wrapper around `render_opts` that passes a default set of options.)
-pub fn render<'a, N, E, S, G, W>(g: &'a G, w: &mut W) -> io::Result<()>
+pub fn render<'a, N, E, S, G, W>(g: &'a G, w: &mut W) -> Result<(), RenderError>
 where
     N: Clone + 'a,
     E: Clone + 'a,
@@ -14,10 +23,12 @@ where
         + GraphWalk<'a, Node = N, Edge = E, Subgraph = S>,
     W: Write,
 {
-    render_opts(g, w, &[])
+    render_opts(g, w, RenderOptions::default())
 }
 
-#[derive(Clone, PartialEq, Eq, Debug)]
+/// A single rendering option. A list of these can be converted into
+/// [`RenderOptions`], which is what [`render_opts`] takes.
+#[derive(Clone, PartialEq, Debug)]
 pub enum RenderOption {
     NoEdgeLabels,
     NoNodeLabels,
@@ -27,17 +38,395 @@ pub enum RenderOption {
     NoNodeColors,
 
     Fontname(String),
+    /// Same as [`RenderOption::Theme`] with [`Theme::dark`].
     DarkTheme,
+    Theme(Box<Theme>),
     NoArrows,
 }
 
+/// Options for [`render_opts`], built up from [`RenderOptions::default`] with
+/// the `with_*` methods.
+///
+/// By default, everything that the graph provides is rendered, indented by
+/// four spaces with one statement per line.
+#[derive(Clone, PartialEq, Debug)]
+pub struct RenderOptions {
+    node_labels: bool,
+    edge_labels: bool,
+    pub(crate) node_styles: bool,
+    pub(crate) edge_styles: bool,
+    pub(crate) node_colors: bool,
+    pub(crate) edge_colors: bool,
+    pub(crate) arrows: bool,
+    legend: bool,
+    fontname: Option<String>,
+    font_size: Option<f64>,
+    theme: Option<Theme>,
+    indent: String,
+    line_ending: LineEnding,
+    attr_order: AttrOrder,
+    compact: bool,
+    html_check: bool,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        RenderOptions {
+            node_labels: true,
+            edge_labels: true,
+            node_styles: true,
+            edge_styles: true,
+            node_colors: true,
+            edge_colors: true,
+            arrows: true,
+            legend: false,
+            fontname: None,
+            font_size: None,
+            theme: None,
+            indent: "    ".to_owned(),
+            line_ending: LineEnding::Lf,
+            attr_order: AttrOrder::Insertion,
+            compact: false,
+            html_check: false,
+        }
+    }
+}
+
+impl RenderOptions {
+    /// Sets whether the labels of nodes and subgraphs are rendered.
+    pub fn with_node_labels(self, node_labels: bool) -> Self {
+        Self {
+            node_labels,
+            ..self
+        }
+    }
+
+    /// Sets whether the labels of edges are rendered.
+    pub fn with_edge_labels(self, edge_labels: bool) -> Self {
+        Self {
+            edge_labels,
+            ..self
+        }
+    }
+
+    /// Sets whether the styles of nodes and subgraphs are rendered.
+    pub fn with_node_styles(self, node_styles: bool) -> Self {
+        Self {
+            node_styles,
+            ..self
+        }
+    }
+
+    /// Sets whether the styles of edges are rendered.
+    pub fn with_edge_styles(self, edge_styles: bool) -> Self {
+        Self {
+            edge_styles,
+            ..self
+        }
+    }
+
+    /// Sets whether the colors of nodes and subgraphs are rendered.
+    pub fn with_node_colors(self, node_colors: bool) -> Self {
+        Self {
+            node_colors,
+            ..self
+        }
+    }
+
+    /// Sets whether the colors of edges are rendered.
+    pub fn with_edge_colors(self, edge_colors: bool) -> Self {
+        Self {
+            edge_colors,
+            ..self
+        }
+    }
+
+    /// Sets whether the arrows of edges are rendered.
+    pub fn with_arrows(self, arrows: bool) -> Self {
+        Self { arrows, ..self }
+    }
+
+    /// Sets whether a legend is added, explaining the classes, styles,
+    /// colors and arrows that the nodes and edges are drawn with.
+    ///
+    /// The legend is a `cluster_legend` subgraph, see the
+    /// [`legend`](crate::legend) module.
+    pub fn with_legend(self, legend: bool) -> Self {
+        Self { legend, ..self }
+    }
+
+    /// Sets the default font of the graph, nodes and edges.
+    pub fn with_fontname(self, fontname: impl Into<String>) -> Self {
+        Self {
+            fontname: Some(fontname.into()),
+            ..self
+        }
+    }
+
+    /// Sets the default font size, in points, of the graph, nodes and edges.
+    pub fn with_font_size(self, font_size: f64) -> Self {
+        Self {
+            font_size: Some(font_size),
+            ..self
+        }
+    }
+
+    /// Sets the colors and font that the graph is drawn with.
+    ///
+    /// The font set by [`with_fontname`](Self::with_fontname) takes
+    /// precedence over that of the theme.
+    pub fn with_theme(self, theme: Theme) -> Self {
+        Self {
+            theme: Some(theme),
+            ..self
+        }
+    }
+
+    /// Sets whether the graph is drawn in white on black, with
+    /// [`Theme::dark`], replacing any other theme.
+    pub fn with_dark_theme(self, dark_theme: bool) -> Self {
+        Self {
+            theme: dark_theme.then(Theme::dark),
+            ..self
+        }
+    }
+
+    /// Sets the string that each level of nesting is indented by.
+    pub fn with_indent(self, indent: impl Into<String>) -> Self {
+        Self {
+            indent: indent.into(),
+            ..self
+        }
+    }
+
+    /// Sets the line ending written after each statement.
+    pub fn with_line_ending(self, line_ending: LineEnding) -> Self {
+        Self {
+            line_ending,
+            ..self
+        }
+    }
+
+    /// Sets the order in which the attributes of each element are written.
+    pub fn with_attr_order(self, attr_order: AttrOrder) -> Self {
+        Self { attr_order, ..self }
+    }
+
+    /// Sets whether the whole graph is written on a single line, without
+    /// indentation.
+    pub fn with_compact(self, compact: bool) -> Self {
+        Self { compact, ..self }
+    }
+
+    /// Sets whether HTML-like labels are checked against the Graphviz
+    /// grammar with [`html::check`], failing with
+    /// [`RenderError::InvalidHtml`] rather than leaving the error to
+    /// Graphviz.
+    pub fn with_html_check(self, html_check: bool) -> Self {
+        Self { html_check, ..self }
+    }
+
+    /// Applies a single `option`.
+    pub fn with_option(self, option: &RenderOption) -> Self {
+        match option {
+            RenderOption::NoEdgeLabels => self.with_edge_labels(false),
+            RenderOption::NoNodeLabels => self.with_node_labels(false),
+            RenderOption::NoEdgeStyles => self.with_edge_styles(false),
+            RenderOption::NoEdgeColors => self.with_edge_colors(false),
+            RenderOption::NoNodeStyles => self.with_node_styles(false),
+            RenderOption::NoNodeColors => self.with_node_colors(false),
+            RenderOption::Fontname(fontname) => self.with_fontname(fontname.as_str()),
+            RenderOption::DarkTheme => self.with_dark_theme(true),
+            RenderOption::Theme(theme) => self.with_theme(Theme::clone(theme)),
+            RenderOption::NoArrows => self.with_arrows(false),
+        }
+    }
+}
+
+impl From<&[RenderOption]> for RenderOptions {
+    fn from(options: &[RenderOption]) -> Self {
+        options
+            .iter()
+            .fold(RenderOptions::default(), RenderOptions::with_option)
+    }
+}
+
+impl<const N: usize> From<&[RenderOption; N]> for RenderOptions {
+    fn from(options: &[RenderOption; N]) -> Self {
+        Self::from(&options[..])
+    }
+}
+
+impl From<&RenderOptions> for RenderOptions {
+    fn from(options: &RenderOptions) -> Self {
+        options.clone()
+    }
+}
+
+/// The line ending written after each statement.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum LineEnding {
+    /// `\n`
+    #[default]
+    Lf,
+    /// `\r\n`
+    CrLf,
+}
+
+impl LineEnding {
+    pub const fn as_static_str(self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::CrLf => "\r\n",
+        }
+    }
+}
+
+/// The order in which the attributes of an element are written.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum AttrOrder {
+    /// In the order they were given: first those from the dedicated
+    /// `Labeller` methods, such as `node_label`, then those from the
+    /// `*_attrs` methods.
+    #[default]
+    Insertion,
+    /// Sorted by name.
+    Sorted,
+}
+
+/// Writes the statements of the output, indented and terminated as the
+/// options say.
+pub(crate) struct Printer<'o, W> {
+    w: W,
+    options: &'o RenderOptions,
+    started: bool,
+}
+
+impl<'o, W: Write> Printer<'o, W> {
+    pub(crate) fn new(w: W, options: &'o RenderOptions) -> Self {
+        Printer {
+            w,
+            options,
+            started: false,
+        }
+    }
+
+    /// Writes `line` at nesting level `depth`.
+    pub(crate) fn line(&mut self, depth: usize, line: impl Display) -> io::Result<()> {
+        if self.options.compact {
+            if self.started {
+                self.w.write_all(b" ")?;
+            }
+            write!(self.w, "{line}")?;
+        } else {
+            for _ in 0..depth {
+                self.w.write_all(self.options.indent.as_bytes())?;
+            }
+            write!(self.w, "{line}{}", self.options.line_ending.as_static_str())?;
+        }
+        self.started = true;
+        Ok(())
+    }
+
+    /// Ends the last line, which in compact mode is left open, and returns
+    /// the writer.
+    pub(crate) fn finish(mut self) -> io::Result<W> {
+        if self.options.compact && self.started {
+            self.w
+                .write_all(self.options.line_ending.as_static_str().as_bytes())?;
+        }
+        Ok(self.w)
+    }
+}
+
+/// Error returned when a graph cannot be rendered.
+#[derive(Debug)]
+pub enum RenderError {
+    /// Writing the output failed.
+    Io(io::Error),
+    /// The edge `edge` (such as `"a -> b"`) has an endpoint that is not
+    /// among the nodes of the graph.
+    UnknownNode { edge: String, node: Id<'static> },
+    /// Several nodes of the graph have the same id.
+    DuplicateNode(Id<'static>),
+    /// The attribute `attr` of `element` has a value, `value` as written in
+    /// DOT, that Graphviz does not accept, as explained by `reason`.
+    InvalidAttribute {
+        element: String,
+        attr: String,
+        value: String,
+        reason: &'static str,
+    },
+    /// Several subgraphs have the same id.
+    DuplicateSubgraph(Id<'static>),
+    /// The attribute `attr` of `element` is an HTML-like label that does not
+    /// follow the Graphviz grammar, as explained by `error`. Only checked
+    /// with [`RenderOptions::with_html_check`].
+    InvalidHtml {
+        element: String,
+        attr: String,
+        error: HtmlError,
+    },
+}
+
+impl std::error::Error for RenderError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RenderError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for RenderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RenderError::Io(err) => write!(f, "Failed to write graph: {err}"),
+            RenderError::UnknownNode { edge, node } => {
+                write!(f, "Edge {edge} refers to unknown node {node}")
+            }
+            RenderError::DuplicateNode(id) => write!(f, "Node {id} is declared more than once"),
+            RenderError::InvalidAttribute {
+                element,
+                attr,
+                value,
+                reason,
+            } => write!(f, "Attribute {attr}={value} of {element} {reason}"),
+            RenderError::DuplicateSubgraph(id) => {
+                write!(f, "Subgraph {id} is declared more than once")
+            }
+            RenderError::InvalidHtml {
+                element,
+                attr,
+                error,
+            } => write!(
+                f,
+                "Attribute {attr} of {element} is not valid HTML: {error}"
+            ),
+        }
+    }
+}
+
+impl From<io::Error> for RenderError {
+    fn from(err: io::Error) -> Self {
+        RenderError::Io(err)
+    }
+}
+
 /// Renders graph `g` into the writer `w` in DOT syntax.
 /// (Main entry point for the library.)
+///
+/// `options` is a [`RenderOptions`], or anything that converts into one,
+/// such as a list of [`RenderOption`]s.
+///
+/// The graph is checked for duplicate node and subgraph ids, edges to
+/// unknown nodes and invalid attribute values as it is rendered. Nothing is
+/// written to `w` if there are any.
 pub fn render_opts<'a, N, E, S, G, W>(
     g: &'a G,
     w: &mut W,
-    options: &[RenderOption],
-) -> io::Result<()>
+    options: impl Into<RenderOptions>,
+) -> Result<(), RenderError>
 where
     N: Clone + 'a,
     E: Clone + 'a,
@@ -46,60 +435,178 @@ where
         + GraphWalk<'a, Node = N, Edge = E, Subgraph = S>,
     W: Write,
 {
-    writeln!(w, "{} {} {{", g.kind().as_keyword(), *g.graph_id())?;
+    let options = options.into();
+    // The samples in the legend are labelled with their descriptions, even
+    // if the labels of the graph itself are left out.
+    let legend_options = options
+        .clone()
+        .with_node_labels(true)
+        .with_edge_labels(true);
+    check_ids(g)?;
+
+    let mut p = Printer::new(Vec::new(), &options);
+    let id = g.graph_id();
+    if id.is_empty() {
+        p.line(0, format_args!("{} {{", g.kind().as_keyword()))?;
+    } else {
+        p.line(0, format_args!("{} {} {{", g.kind().as_keyword(), id))?;
+    }
 
     if g.kind() == GraphKind::Directed {
         if let Some(rankdir) = g.rank_dir() {
-            writeln!(w, "    rankdir=\"{}\";", rankdir.as_static_str())?;
+            p.line(1, format_args!("rankdir=\"{}\";", rankdir.as_static_str()))?;
         }
     }
 
-    for (name, value) in g.graph_attrs().iter() {
-        writeln!(w, "    {name}={value}")?;
+    let attrs = g.graph_attrs();
+    check_attrs(&options, || "the graph".to_owned(), &attrs)?;
+    for attr in ordered(&attrs, &options) {
+        p.line(1, format_args!("{attr};"))?;
     }
 
     // Global graph properties
-    let mut graph_attrs = Vec::new();
-    let mut content_attrs = Vec::new();
-    let font;
-    let fontname = options.iter().find_map(|option| {
-        if let RenderOption::Fontname(fontname) = option {
-            Some(fontname)
-        } else {
-            None
+    let mut graph_attrs = Attrs::new();
+    let mut node_attrs = Attrs::new();
+    let mut edge_attrs = Attrs::new();
+    if let Some(theme) = &options.theme {
+        graph_attrs.extend(theme.graph_attrs());
+        node_attrs.extend(theme.node_attrs());
+        edge_attrs.extend(theme.edge_attrs());
+    }
+    if let Some(fontname) = &options.fontname {
+        let font = Attr::FontName(Text::label(fontname.as_str()));
+        graph_attrs.set(font.clone());
+        node_attrs.set(font.clone());
+        edge_attrs.set(font);
+    }
+    if let Some(font_size) = options.font_size {
+        for attrs in [&mut graph_attrs, &mut node_attrs, &mut edge_attrs] {
+            attrs.set(Attr::FontSize(font_size));
         }
-    });
-    if let Some(fontname) = fontname {
-        font = format!(r#"fontname="{fontname}""#);
-        graph_attrs.push(&font[..]);
-        content_attrs.push(&font[..]);
     }
-    if options.contains(&RenderOption::DarkTheme) {
-        graph_attrs.push(r#"bgcolor="black""#);
-        graph_attrs.push(r#"fontcolor="white""#);
-        content_attrs.push(r#"color="white""#);
-        content_attrs.push(r#"fontcolor="white""#);
+    node_attrs.extend(g.node_defaults());
+    edge_attrs.extend(g.edge_defaults());
+
+    write_attr_stmt(&mut p, 1, "graph", &graph_attrs)?;
+    write_attr_stmt(&mut p, 1, "node", &node_attrs)?;
+    write_attr_stmt(&mut p, 1, "edge", &edge_attrs)?;
+
+    let stylesheet = g.stylesheet();
+    let edges = g.edges();
+    let mut emitted = Emitted::default();
+    for s in g.subgraphs().iter() {
+        emitted.extend(render_subgraph(&mut p, g, &stylesheet, s, 1, &edges)?);
+    }
+    write_nodes(&mut p, g, &stylesheet, &g.nodes())?;
+    for (i, e) in edges.iter().enumerate() {
+        if !emitted.edges.contains(&i) {
+            let stmt = edge_stmt(g, &stylesheet, e, &options)?;
+            p.line(1, format_args!("{stmt};"))?;
+        }
     }
-    if !(graph_attrs.is_empty() && content_attrs.is_empty()) {
-        writeln!(w, r#"    graph[{}];"#, graph_attrs.join(" "))?;
-        let content_attrs_str = content_attrs.join(" ");
-        writeln!(w, r#"    node[{content_attrs_str}];"#)?;
-        writeln!(w, r#"    edge[{content_attrs_str}];"#)?;
+
+    if options.legend {
+        if let Some(legend) = Legend::new(g, &options) {
+            let stylesheet = legend.stylesheet();
+            p.options = &legend_options;
+            render_subgraph(&mut p, &legend, &stylesheet, &(), 1, &legend.edges())?;
+            write_nodes(&mut p, &legend, &stylesheet, &legend.nodes())?;
+            p.options = &options;
+        }
     }
 
-    render_subgraphs(w, g, &g.subgraphs(), options)?;
-    render_nodes(w, g, &g.nodes(), options)?;
-    render_edges(w, g, &g.edges(), options)?;
+    p.line(0, "}")?;
 
-    writeln!(w, "}}")
+    w.write_all(&p.finish()?)?;
+    Ok(())
+}
+
+/// Checks that the node and subgraph ids are unique, and that every edge
+/// connects two of the nodes.
+pub(crate) fn check_ids<'a, N, E, S, G>(g: &'a G) -> Result<(), RenderError>
+where
+    N: Clone + 'a,
+    E: Clone + 'a,
+    S: Clone + 'a,
+    G: Labeller<'a, Node = N, Edge = E, Subgraph = S>
+        + GraphWalk<'a, Node = N, Edge = E, Subgraph = S>,
+{
+    let mut nodes = HashSet::new();
+    for n in g.nodes().iter() {
+        let id = g.node_id(n);
+        if let Some(id) = nodes.replace(id) {
+            return Err(RenderError::DuplicateNode(id.into_owned()));
+        }
+    }
+
+    for e in g.edges().iter() {
+        let source = g.node_id(&g.source(e));
+        let target = g.node_id(&g.target(e));
+        for node in [&source, &target] {
+            if !nodes.contains(node) {
+                return Err(RenderError::UnknownNode {
+                    edge: format!("{source} {} {target}", g.kind().as_edge_op()),
+                    node: node.clone().into_owned(),
+                });
+            }
+        }
+    }
+
+    let mut subgraphs = HashSet::new();
+    let mut stack = g.subgraphs().into_owned();
+    while let Some(s) = stack.pop() {
+        if let Some(id) = g.subgraph_id(&s) {
+            if let Some(id) = subgraphs.replace(id) {
+                return Err(RenderError::DuplicateSubgraph(id.into_owned()));
+            }
+        }
+        stack.extend(g.subgraph_children(&s).iter().cloned());
+    }
+    Ok(())
+}
+
+/// Checks the values of `attrs`, which belong to the element described by
+/// `element`, and their HTML-like labels if `options` asks for it.
+fn check_attrs(
+    options: &RenderOptions,
+    element: impl FnOnce() -> String,
+    attrs: &Attrs<'_>,
+) -> Result<(), RenderError> {
+    for attr in attrs {
+        if let Err(reason) = attr.check() {
+            return Err(RenderError::InvalidAttribute {
+                element: element(),
+                attr: attr.name().to_owned(),
+                value: attr.value_to_string(),
+                reason,
+            });
+        }
+        if !options.html_check {
+            continue;
+        }
+        if let Attr::Label(Text::Html(html))
+        | Attr::XLabel(Text::Html(html))
+        | Attr::HeadLabel(Text::Html(html))
+        | Attr::TailLabel(Text::Html(html)) = attr
+        {
+            if let Err(error) = html::check(html) {
+                return Err(RenderError::InvalidHtml {
+                    element: element(),
+                    attr: attr.name().to_owned(),
+                    error,
+                });
+            }
+        }
+    }
+    Ok(())
 }
 
 pub fn render_nodes<'a, N, E, S, G, W>(
     w: &mut W,
     graph: &'a G,
     nodes: &Nodes<'a, N>,
-    options: &[RenderOption],
-) -> io::Result<()>
+    options: &RenderOptions,
+) -> Result<(), RenderError>
 where
     W: Write,
     N: Clone + 'a,
@@ -108,48 +615,142 @@ where
     G: Labeller<'a, Node = N, Edge = E, Subgraph = S>
         + GraphWalk<'a, Node = N, Edge = E, Subgraph = S>,
 {
-    let mut text = Vec::new();
+    let mut p = Printer::new(w, options);
+    write_nodes(&mut p, graph, &graph.stylesheet(), nodes)?;
+    p.finish()?;
+    Ok(())
+}
+
+fn write_nodes<'a, N, E, S, G, W>(
+    p: &mut Printer<'_, W>,
+    graph: &'a G,
+    stylesheet: &Stylesheet<'a>,
+    nodes: &Nodes<'a, N>,
+) -> Result<(), RenderError>
+where
+    W: Write,
+    N: Clone + 'a,
+    E: Clone + 'a,
+    S: Clone + 'a,
+    G: Labeller<'a, Node = N, Edge = E, Subgraph = S>
+        + GraphWalk<'a, Node = N, Edge = E, Subgraph = S>,
+{
+    let options = p.options;
     for n in nodes.iter() {
-        write!(text, "    {}", *graph.node_id(n)).unwrap();
+        let id = graph.node_id(n);
+        let attrs = resolve_node_attrs(graph, stylesheet, n, options);
+        check_attrs(options, || format!("node {id}"), &attrs)?;
+        p.line(1, format_args!("{id}{};", attr_list(&attrs, options)))?;
+    }
+    Ok(())
+}
 
-        if !options.contains(&RenderOption::NoNodeLabels) {
-            let label = &graph.node_label(n).to_escaped_string();
-            write!(text, "[label={label}]").unwrap();
-        }
+/// Returns all the attributes of the node `n`, from `stylesheet`, the
+/// `Labeller` methods that `options` leaves in, and `node_attrs`, in order
+/// of precedence.
+pub(crate) fn resolve_node_attrs<'a, N, E, S, G>(
+    graph: &'a G,
+    stylesheet: &Stylesheet<'a>,
+    n: &N,
+    options: &RenderOptions,
+) -> Attrs<'a>
+where
+    N: Clone + 'a,
+    E: Clone + 'a,
+    S: Clone + 'a,
+    G: Labeller<'a, Node = N, Edge = E, Subgraph = S>
+        + GraphWalk<'a, Node = N, Edge = E, Subgraph = S>,
+{
+    let mut attrs = resolve(stylesheet, ElementKind::Node, || graph.node_classes(n));
 
-        let style = graph.node_style(n);
-        if !options.contains(&RenderOption::NoNodeStyles) && style != Style::None {
-            write!(text, "[style=\"{}\"]", style.as_static_str()).unwrap();
-        }
+    if options.node_labels {
+        attrs.set(Attr::Label(graph.node_label(n)));
+    }
 
-        if !options.contains(&RenderOption::NoNodeColors) {
-            if let Some(color) = graph.node_color(n) {
-                write!(text, "[color={}]", color.to_escaped_string()).unwrap();
-            }
-        }
+    let style = graph.node_style(n);
+    if options.node_styles && !style.is_empty() {
+        attrs.set(Attr::Style(style));
+    }
 
-        if let Some(shape) = graph.node_shape(n) {
-            write!(text, "[shape={}]", &shape.to_escaped_string()).unwrap();
+    if options.node_colors {
+        if let Some(color) = graph.node_color(n) {
+            attrs.set(Attr::Color(color));
         }
+    }
 
-        for (name, value) in graph.node_attrs(n).into_iter() {
-            write!(text, "[{name}={value}]").unwrap();
-        }
+    if let Some(shape) = graph.node_shape(n) {
+        attrs.extend(shape.attrs());
+    }
 
-        writeln!(text, ";").unwrap();
+    attrs.extend(graph.node_attrs(n));
+    attrs
+}
 
-        w.write_all(&text)?;
-        text.clear();
+/// Writes an attribute statement, such as `node[shape="box"];`, that sets
+/// the defaults for `target`, unless `attrs` is empty.
+fn write_attr_stmt<W: Write>(
+    p: &mut Printer<'_, W>,
+    depth: usize,
+    target: &str,
+    attrs: &Attrs<'_>,
+) -> Result<(), RenderError> {
+    if attrs.is_empty() {
+        return Ok(());
     }
+    check_attrs(p.options, || format!("the {target} defaults"), attrs)?;
+    p.line(
+        depth,
+        format_args!("{target}{};", attr_list(attrs, p.options)),
+    )?;
     Ok(())
 }
 
+/// Returns `attrs` in the order that `options` asks for.
+pub(crate) fn ordered<'b, 'a>(attrs: &'b Attrs<'a>, options: &RenderOptions) -> Vec<&'b Attr<'a>> {
+    let mut attrs: Vec<_> = attrs.iter().collect();
+    if options.attr_order == AttrOrder::Sorted {
+        attrs.sort_by_key(|attr| attr.name());
+    }
+    attrs
+}
+
+/// Returns the attributes that `stylesheet` gives an element of the kind
+/// `kind`, only asking for its `classes` if there are any rules.
+fn resolve<'a>(
+    stylesheet: &Stylesheet<'a>,
+    kind: ElementKind,
+    classes: impl FnOnce() -> Classes<'a>,
+) -> Attrs<'a> {
+    if stylesheet.is_empty() {
+        return Attrs::new();
+    }
+    stylesheet.resolve(kind, &classes())
+}
+
+/// Formats `attrs` as a single attribute list, `[a=b, c=d]`, or as the
+/// empty string if there are none.
+fn attr_list(attrs: &Attrs<'_>, options: &RenderOptions) -> String {
+    if attrs.is_empty() {
+        return String::new();
+    }
+    let attrs: Vec<_> = ordered(attrs, options)
+        .into_iter()
+        .map(Attr::to_string)
+        .collect();
+    let separator = if options.compact { "," } else { ", " };
+    format!("[{}]", attrs.join(separator))
+}
+
+/// Renders `subgraphs`, and recursively the subgraphs nested within them.
+///
+/// A node or edge listed by several nested subgraphs is only emitted
+/// within the innermost one.
 pub fn render_subgraphs<'a, N, E, S, G, W>(
     w: &mut W,
     graph: &'a G,
     subgraphs: &Subgraphs<'a, S>,
-    options: &[RenderOption],
-) -> io::Result<()>
+    options: &RenderOptions,
+) -> Result<(), RenderError>
 where
     W: Write,
     N: Clone + 'a,
@@ -158,59 +759,334 @@ where
     G: Labeller<'a, Node = N, Edge = E, Subgraph = S>
         + GraphWalk<'a, Node = N, Edge = E, Subgraph = S>,
 {
-    let mut text = Vec::new();
+    let mut p = Printer::new(w, options);
+    let stylesheet = graph.stylesheet();
+    let edges = graph.edges();
     for s in subgraphs.iter() {
-        write!(text, "subgraph").unwrap();
+        render_subgraph(&mut p, graph, &stylesheet, s, 1, &edges)?;
+    }
+    p.finish()?;
+    Ok(())
+}
 
-        if let Some(id) = graph.subgraph_id(s) {
-            write!(text, " {}", *id).unwrap();
-        }
+/// The nodes and edges emitted within a subgraph, including those within
+/// the subgraphs nested in it. Edges are kept as their positions in
+/// [`GraphWalk::edges`].
+#[derive(Default)]
+pub(crate) struct Emitted<'a> {
+    pub(crate) nodes: HashSet<Id<'a>>,
+    pub(crate) edges: HashSet<usize>,
+}
+
+impl<'a> Emitted<'a> {
+    pub(crate) fn extend(&mut self, other: Emitted<'a>) {
+        self.nodes.extend(other.nodes);
+        self.edges.extend(other.edges);
+    }
+}
+
+/// The output of [`walk_subgraph`], which visits the subgraphs of a graph
+/// the same way for every output format.
+pub(crate) trait SubgraphVisitor<'a, N, E, S> {
+    type Error;
+
+    /// Starts the subgraph `s` at nesting level `depth`, returning the
+    /// nesting level of its contents. `node_defaults` and `edge_defaults`
+    /// are the defaults that `s` itself sets, from its shape and its `node`
+    /// and `edge` defaults.
+    fn enter(
+        &mut self,
+        s: &S,
+        depth: usize,
+        node_defaults: &Attrs<'a>,
+        edge_defaults: &Attrs<'a>,
+    ) -> Result<usize, Self::Error>;
+
+    /// Visits the node `n`, which takes its attributes from `defaults` unless
+    /// it sets them itself.
+    fn visit_node(&mut self, n: &N, depth: usize, defaults: &Attrs<'a>) -> Result<(), Self::Error>;
 
-        writeln!(text, " {{").unwrap();
+    /// Visits the edge `e`, which takes its attributes from `defaults` unless
+    /// it sets them itself.
+    fn visit_edge(&mut self, e: &E, depth: usize, defaults: &Attrs<'a>) -> Result<(), Self::Error>;
 
-        if !options.contains(&RenderOption::NoNodeLabels) {
-            let label = &graph.subgraph_label(s).to_escaped_string();
-            writeln!(text, "    label={label};").unwrap();
+    /// Ends the subgraph that was started at `depth`, with its contents at
+    /// `inner`.
+    fn exit(&mut self, depth: usize, inner: usize) -> Result<(), Self::Error>;
+}
+
+/// Visits the subgraph `s` at nesting level `depth`, and recursively the
+/// subgraphs nested within it, returning all the nodes and edges that were
+/// visited within it.
+///
+/// A node or edge listed by several nested subgraphs is only visited within
+/// the innermost one. `edges` are the edges of the graph, which
+/// [`GraphWalk::subgraph_edges`] gives positions in. `node_defaults` and
+/// `edge_defaults` are the defaults in effect around `s`; its nodes and
+/// edges are visited with those of `s`, and of the subgraphs between, on
+/// top.
+pub(crate) fn walk_subgraph<'a, N, E, S, G, V>(
+    graph: &'a G,
+    visitor: &mut V,
+    s: &S,
+    depth: usize,
+    edges: &[E],
+    node_defaults: &Attrs<'a>,
+    edge_defaults: &Attrs<'a>,
+) -> Result<Emitted<'a>, V::Error>
+where
+    N: Clone + 'a,
+    E: Clone + 'a,
+    S: Clone + 'a,
+    G: Labeller<'a, Node = N, Edge = E, Subgraph = S>
+        + GraphWalk<'a, Node = N, Edge = E, Subgraph = S>,
+    V: SubgraphVisitor<'a, N, E, S>,
+{
+    let mut own_node_defaults = Attrs::new();
+    if let Some(shape) = graph.subgraph_shape(s) {
+        own_node_defaults.extend(shape.attrs());
+    }
+    own_node_defaults.extend(graph.subgraph_node_defaults(s));
+    let own_edge_defaults = graph.subgraph_edge_defaults(s);
+    let inner = visitor.enter(s, depth, &own_node_defaults, &own_edge_defaults)?;
+
+    let mut node_defaults = node_defaults.clone();
+    node_defaults.extend(own_node_defaults);
+    let mut edge_defaults = edge_defaults.clone();
+    edge_defaults.extend(own_edge_defaults);
+
+    let mut nested = Emitted::default();
+    for child in graph.subgraph_children(s).iter() {
+        nested.extend(walk_subgraph(
+            graph,
+            visitor,
+            child,
+            inner,
+            edges,
+            &node_defaults,
+            &edge_defaults,
+        )?);
+    }
+
+    let mut emitted = Emitted::default();
+    for n in graph.subgraph_nodes(s).iter() {
+        let id = graph.node_id(n);
+        if !nested.nodes.contains(&id) && !emitted.nodes.contains(&id) {
+            visitor.visit_node(n, inner, &node_defaults)?;
+            emitted.nodes.insert(id);
         }
+    }
 
-        let style = graph.subgraph_style(s);
-        let var_name = style != Style::None;
-        if !options.contains(&RenderOption::NoNodeStyles) && var_name {
-            writeln!(text, "    style=\"{}\";", style.as_static_str()).unwrap();
+    for &i in graph.subgraph_edges(s).iter() {
+        let Some(e) = edges.get(i) else {
+            continue;
+        };
+        if !nested.edges.contains(&i) && emitted.edges.insert(i) {
+            visitor.visit_edge(e, inner, &edge_defaults)?;
         }
+    }
+    emitted.extend(nested);
 
-        if !options.contains(&RenderOption::NoNodeColors) {
-            if let Some(color) = graph.subgraph_color(s) {
-                writeln!(text, "    color={};", color.to_escaped_string()).unwrap();
-            }
+    visitor.exit(depth, inner)?;
+    Ok(emitted)
+}
+
+/// The styles among `attrs`.
+pub(crate) fn styles(attrs: &Attrs<'_>) -> Styles {
+    match attrs.get("style") {
+        Some(Attr::Style(styles)) => styles.clone(),
+        _ => Styles::new(),
+    }
+}
+
+/// Returns an id like `id` for output formats other than DOT, made of ASCII
+/// letters, digits and underscores, that is not in `used` or `keywords`
+/// yet, and adds it to `used`.
+pub(crate) fn unique_id(id: &str, used: &mut HashSet<String>, keywords: &[&str]) -> String {
+    let mut name: String = id
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    if name.is_empty() || keywords.contains(&name.to_ascii_lowercase().as_str()) {
+        name.push('_');
+    }
+    let mut unique = name.clone();
+    let mut i = 1;
+    while used.contains(&unique) {
+        unique = format!("{name}_{i}");
+        i += 1;
+    }
+    used.insert(unique.clone());
+    unique
+}
+
+/// Renders the subgraph `s` at nesting level `depth`, returning all the
+/// nodes and edges that were emitted within it. `edges` are the edges of the
+/// graph.
+fn render_subgraph<'a, N, E, S, G, W>(
+    p: &mut Printer<'_, W>,
+    graph: &'a G,
+    stylesheet: &Stylesheet<'a>,
+    s: &S,
+    depth: usize,
+    edges: &[E],
+) -> Result<Emitted<'a>, RenderError>
+where
+    W: Write,
+    N: Clone + 'a,
+    E: Clone + 'a,
+    S: Clone + 'a,
+    G: Labeller<'a, Node = N, Edge = E, Subgraph = S>
+        + GraphWalk<'a, Node = N, Edge = E, Subgraph = S>,
+{
+    let mut writer = SubgraphWriter {
+        p,
+        graph,
+        stylesheet,
+    };
+    // DOT applies the defaults itself, from the `node` and `edge` statements.
+    let (node_defaults, edge_defaults) = (Attrs::new(), Attrs::new());
+    walk_subgraph(
+        graph,
+        &mut writer,
+        s,
+        depth,
+        edges,
+        &node_defaults,
+        &edge_defaults,
+    )
+}
+
+/// Writes subgraphs in DOT as [`walk_subgraph`] visits them.
+struct SubgraphWriter<'p, 'o, 'a, W, G> {
+    p: &'p mut Printer<'o, W>,
+    graph: &'a G,
+    stylesheet: &'p Stylesheet<'a>,
+}
+
+impl<'a, N, E, S, G, W> SubgraphVisitor<'a, N, E, S> for SubgraphWriter<'_, '_, 'a, W, G>
+where
+    W: Write,
+    N: Clone + 'a,
+    E: Clone + 'a,
+    S: Clone + 'a,
+    G: Labeller<'a, Node = N, Edge = E, Subgraph = S>
+        + GraphWalk<'a, Node = N, Edge = E, Subgraph = S>,
+{
+    type Error = RenderError;
+
+    fn enter(
+        &mut self,
+        s: &S,
+        depth: usize,
+        node_defaults: &Attrs<'a>,
+        edge_defaults: &Attrs<'a>,
+    ) -> Result<usize, RenderError> {
+        let (p, graph) = (&mut *self.p, self.graph);
+        let options = p.options;
+        let id = graph.subgraph_id(s);
+        match &id {
+            Some(id) => p.line(depth, format_args!("subgraph {id} {{"))?,
+            None => p.line(depth, "subgraph {")?,
         }
 
-        if let Some(shape) = graph.subgraph_shape(s) {
-            writeln!(text, "    shape={};", &shape.to_escaped_string()).unwrap();
+        let theme = options
+            .theme
+            .as_ref()
+            .filter(|_| id.as_ref().is_some_and(|id| id.starts_with("cluster")));
+
+        let mut attrs = Attrs::new();
+        if let Some(theme) = theme {
+            attrs.extend(theme.cluster_attrs());
         }
+        attrs.extend(resolve_subgraph_attrs(graph, self.stylesheet, s, options));
 
-        for (name, value) in graph.subgraph_attrs(s).into_iter() {
-            writeln!(text, "    {name}={value};").unwrap();
+        check_attrs(
+            options,
+            || match &id {
+                Some(id) => format!("subgraph {id}"),
+                None => "an anonymous subgraph".to_owned(),
+            },
+            &attrs,
+        )?;
+        for attr in ordered(&attrs, options) {
+            p.line(depth + 1, format_args!("{attr};"))?;
         }
 
-        for n in graph.subgraph_nodes(s).iter() {
-            writeln!(text, "    {};", *graph.node_id(n)).unwrap();
+        let mut node_attrs = Attrs::new();
+        if let Some(theme) = theme {
+            node_attrs.extend(theme.cluster_node_attrs(depth));
         }
+        node_attrs.extend(node_defaults.clone());
 
-        writeln!(text, "}}").unwrap();
+        write_attr_stmt(p, depth + 1, "node", &node_attrs)?;
+        write_attr_stmt(p, depth + 1, "edge", edge_defaults)?;
+        Ok(depth + 1)
+    }
 
-        w.write_all(&text)?;
-        text.clear();
+    fn visit_node(&mut self, n: &N, depth: usize, _: &Attrs<'a>) -> Result<(), RenderError> {
+        let id = self.graph.node_id(n);
+        self.p.line(depth, format_args!("{id};"))?;
+        Ok(())
     }
-    Ok(())
+
+    fn visit_edge(&mut self, e: &E, depth: usize, _: &Attrs<'a>) -> Result<(), RenderError> {
+        let stmt = edge_stmt(self.graph, self.stylesheet, e, self.p.options)?;
+        self.p.line(depth, format_args!("{stmt};"))?;
+        Ok(())
+    }
+
+    fn exit(&mut self, depth: usize, _: usize) -> Result<(), RenderError> {
+        self.p.line(depth, "}")?;
+        Ok(())
+    }
+}
+
+/// Returns the attributes of the subgraph `s`, from `stylesheet`, the
+/// `Labeller` methods that `options` leaves in, and `subgraph_attrs`, in
+/// order of precedence.
+pub(crate) fn resolve_subgraph_attrs<'a, N, E, S, G>(
+    graph: &'a G,
+    stylesheet: &Stylesheet<'a>,
+    s: &S,
+    options: &RenderOptions,
+) -> Attrs<'a>
+where
+    N: Clone + 'a,
+    E: Clone + 'a,
+    S: Clone + 'a,
+    G: Labeller<'a, Node = N, Edge = E, Subgraph = S>
+        + GraphWalk<'a, Node = N, Edge = E, Subgraph = S>,
+{
+    let mut attrs = resolve(stylesheet, ElementKind::Subgraph, || {
+        graph.subgraph_classes(s)
+    });
+
+    if options.node_labels {
+        attrs.set(Attr::Label(graph.subgraph_label(s)));
+    }
+
+    let style = graph.subgraph_style(s);
+    if options.node_styles && !style.is_empty() {
+        attrs.set(Attr::Style(style));
+    }
+
+    if options.node_colors {
+        if let Some(color) = graph.subgraph_color(s) {
+            attrs.set(Attr::Color(color));
+        }
+    }
+
+    attrs.extend(graph.subgraph_attrs(s));
+    attrs
 }
 
 pub fn render_edges<'a, N, E, S, G, W>(
     w: &mut W,
     graph: &'a G,
     edges: &Edges<'a, E>,
-    options: &[RenderOption],
-) -> io::Result<()>
+    options: &RenderOptions,
+) -> Result<(), RenderError>
 where
     W: Write,
     N: Clone + 'a,
@@ -219,90 +1095,115 @@ where
     G: Labeller<'a, Node = N, Edge = E, Subgraph = S>
         + GraphWalk<'a, Node = N, Edge = E, Subgraph = S>,
 {
-    let mut text = Vec::new();
+    let mut p = Printer::new(w, options);
+    let stylesheet = graph.stylesheet();
     for e in edges.iter() {
-        let start_arrow = graph.edge_start_arrow(e);
-        let end_arrow = graph.edge_end_arrow(e);
-        let start_port = graph
-            .edge_start_port(e)
-            .map(|p| format!(":{}", p.name))
-            .unwrap_or_default();
-        let end_port = graph
-            .edge_end_port(e)
-            .map(|p| format!(":{}", p.name))
-            .unwrap_or_default();
-        let start_point = graph
-            .edge_start_point(e)
-            .map(|p| p.as_static_str())
-            .unwrap_or("");
-        let end_point = graph
-            .edge_end_point(e)
-            .map(|p| p.as_static_str())
-            .unwrap_or("");
-
-        write!(w, "    ")?;
-
-        let source_id = graph.node_id(&graph.source(e));
-        let target_id = graph.node_id(&graph.target(e));
-
-        write!(
-            text,
-            "{}{}{} {} {}{}{}",
-            *source_id,
-            start_port,
-            start_point,
-            graph.kind().as_edge_op(),
-            *target_id,
-            end_port,
-            end_point,
-        )
-        .unwrap();
-
-        if !options.contains(&RenderOption::NoEdgeLabels) {
-            let label = graph.edge_label(e).to_escaped_string();
-            write!(text, "[label={label}]").unwrap();
-        }
-
-        let style = graph.edge_style(e);
-        if !options.contains(&RenderOption::NoEdgeStyles) && style != Style::None {
-            write!(text, "[style=\"{}\"]", style.as_static_str()).unwrap();
-        }
-
-        if !options.contains(&RenderOption::NoEdgeColors) {
-            if let Some(color) = graph.edge_color(e) {
-                write!(text, "[color={}]", color.to_escaped_string()).unwrap();
-            }
-        }
+        p.line(
+            1,
+            format_args!("{};", edge_stmt(graph, &stylesheet, e, options)?),
+        )?;
+    }
+    p.finish()?;
+    Ok(())
+}
 
-        if !options.contains(&RenderOption::NoArrows)
-            && (!start_arrow.is_default() || !end_arrow.is_default())
-        {
-            write!(text, "[").unwrap();
-            if !end_arrow.is_default() {
-                write!(text, "arrowhead=\"{}\"", end_arrow.to_dot_string()).unwrap();
-            }
-            if !start_arrow.is_default() {
-                if *text.last().unwrap() != b'[' {
-                    write!(text, " ").unwrap();
-                }
-                write!(
-                    text,
-                    "dir=\"both\" arrowtail=\"{}\"",
-                    start_arrow.to_dot_string()
-                )
-                .unwrap();
-            }
-            write!(text, "]").unwrap();
-        }
+/// Renders the statement for the edge `e`, without indentation or the
+/// trailing `;`.
+fn edge_stmt<'a, N, E, S, G>(
+    graph: &'a G,
+    stylesheet: &Stylesheet<'a>,
+    e: &E,
+    options: &RenderOptions,
+) -> Result<String, RenderError>
+where
+    N: Clone + 'a,
+    E: Clone + 'a,
+    S: Clone + 'a,
+    G: Labeller<'a, Node = N, Edge = E, Subgraph = S>
+        + GraphWalk<'a, Node = N, Edge = E, Subgraph = S>,
+{
+    let start_port = graph
+        .edge_start_port(e)
+        .map(|p| format!(":{p}"))
+        .unwrap_or_default();
+    let end_port = graph
+        .edge_end_port(e)
+        .map(|p| format!(":{p}"))
+        .unwrap_or_default();
+    let start_point = graph
+        .edge_start_point(e)
+        .map(|p| p.as_static_str())
+        .unwrap_or("");
+    let end_point = graph
+        .edge_end_point(e)
+        .map(|p| p.as_static_str())
+        .unwrap_or("");
+
+    let source_id = graph.node_id(&graph.source(e));
+    let target_id = graph.node_id(&graph.target(e));
+    let op = graph.kind().as_edge_op();
 
-        for (name, value) in graph.edge_attrs(e).into_iter() {
-            write!(text, "{name}={value}").unwrap();
+    let attrs = resolve_edge_attrs(graph, stylesheet, e, options);
+    check_attrs(
+        options,
+        || format!("edge {source_id} {op} {target_id}"),
+        &attrs,
+    )?;
+    Ok(format!(
+        "{source_id}{start_port}{start_point} {op} {target_id}{end_port}{end_point}{}",
+        attr_list(&attrs, options)
+    ))
+}
+
+/// Returns all the attributes of the edge `e`, from `stylesheet`, the
+/// `Labeller` methods that `options` leaves in, and `edge_attrs`, in order
+/// of precedence. The arrows are given as `arrowhead`, `arrowtail` and
+/// `dir` attributes.
+pub(crate) fn resolve_edge_attrs<'a, N, E, S, G>(
+    graph: &'a G,
+    stylesheet: &Stylesheet<'a>,
+    e: &E,
+    options: &RenderOptions,
+) -> Attrs<'a>
+where
+    N: Clone + 'a,
+    E: Clone + 'a,
+    S: Clone + 'a,
+    G: Labeller<'a, Node = N, Edge = E, Subgraph = S>
+        + GraphWalk<'a, Node = N, Edge = E, Subgraph = S>,
+{
+    let mut attrs = resolve(stylesheet, ElementKind::Edge, || graph.edge_classes(e));
+
+    if options.edge_labels {
+        let label = graph.edge_label(e);
+        if !label.is_empty() {
+            attrs.set(Attr::Label(label));
         }
+    }
 
-        writeln!(text, ";").unwrap();
+    let style = graph.edge_style(e);
+    if options.edge_styles && !style.is_empty() {
+        attrs.set(Attr::Style(style));
+    }
 
-        w.write_all(&text)?;
-        text.clear();
+    if options.edge_colors {
+        if let Some(color) = graph.edge_color(e) {
+            attrs.set(Attr::Color(color));
+        }
     }
-    Ok(())
+
+    if options.arrows {
+        let start_arrow = graph.edge_start_arrow(e);
+        let end_arrow = graph.edge_end_arrow(e);
+        if !end_arrow.is_default() {
+            attrs.set(Attr::ArrowHead(end_arrow));
+        }
+        if !start_arrow.is_default() {
+            attrs.set(Attr::Dir(Dir::Both));
+            attrs.set(Attr::ArrowTail(start_arrow));
+        }
+    }
+
+    attrs.extend(graph.edge_attrs(e));
+    attrs
 }
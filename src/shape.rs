@@ -0,0 +1,245 @@
+//! Typed node shapes.
+//!
+//! [`Shape`] names every [node shape](https://www.graphviz.org/doc/info/shapes.html)
+//! that Graphviz draws: the polygon-based shapes such as `box` and
+//! `cylinder`, the `record` and `Mrecord` shapes whose labels are split into
+//! fields, and [`Shape::Polygon`], whose number of sides and other
+//! parameters are given by a [`Polygon`].
+//!
+//! ```rust
+//! use dotwalk::shape::{Polygon, Shape};
+//! use dotwalk::{Attr, Attrs};
+//!
+//! assert_eq!("Mrecord".parse(), Ok(Shape::MRecord));
+//! assert_eq!(
+//!     Shape::Polygon(Polygon::new(5).with_skew(0.2)).attrs(),
+//!     Attrs::from([
+//!         Attr::Shape(Shape::Polygon(Polygon::new(5).with_skew(0.2))),
+//!         Attr::Sides(5),
+//!         Attr::Skew(0.2),
+//!     ])
+//! );
+//! ```
+
+use crate::{Attr, Attrs};
+
+/// Defines [`Shape`] from a table of variants and shape names.
+macro_rules! define_shapes {
+    ( $( $(#[$meta:meta])* $variant:ident = $name:literal, )+ ) => {
+        /// The shape of a node.
+        /// See <https://www.graphviz.org/doc/info/shapes.html> for pictures
+        /// of each.
+        #[derive(Clone, Debug, PartialEq)]
+        pub enum Shape {
+            $( $(#[$meta])* $variant, )+
+            /// A polygon with the parameters of a [`Polygon`].
+            ///
+            /// As the parameters are attributes of their own, only the
+            /// `polygon` name is written for an [`Attr::Shape`]; use
+            /// [`Shape::attrs`] to set them as well.
+            Polygon(Polygon),
+        }
+
+        impl Shape {
+            /// The Graphviz name of the shape, such as `"box"`.
+            pub const fn name(&self) -> &'static str {
+                match self {
+                    $( Shape::$variant => $name, )+
+                    Shape::Polygon(_) => "polygon",
+                }
+            }
+        }
+
+        impl std::str::FromStr for Shape {
+            type Err = ShapeError;
+
+            /// Parses a shape name, such as `"box"` or `"Mrecord"`. A
+            /// `"polygon"` gets the default [`Polygon`] parameters.
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                match s {
+                    $( $name => Ok(Shape::$variant), )+
+                    "polygon" => Ok(Shape::Polygon(Polygon::default())),
+                    _ => Err(ShapeError::Unknown(s.to_owned())),
+                }
+            }
+        }
+    };
+}
+
+define_shapes! {
+    Box = "box",
+    Ellipse = "ellipse",
+    Oval = "oval",
+    Circle = "circle",
+    /// A small filled circle, without a label.
+    Point = "point",
+    Egg = "egg",
+    Triangle = "triangle",
+    /// No outline, with the default margin around the label.
+    PlainText = "plaintext",
+    /// No outline and no margin, so that the node is the size of its label.
+    Plain = "plain",
+    Diamond = "diamond",
+    Trapezium = "trapezium",
+    Parallelogram = "parallelogram",
+    House = "house",
+    Pentagon = "pentagon",
+    Hexagon = "hexagon",
+    Septagon = "septagon",
+    Octagon = "octagon",
+    DoubleCircle = "doublecircle",
+    DoubleOctagon = "doubleoctagon",
+    TripleOctagon = "tripleoctagon",
+    InvTriangle = "invtriangle",
+    InvTrapezium = "invtrapezium",
+    InvHouse = "invhouse",
+    MDiamond = "Mdiamond",
+    MSquare = "Msquare",
+    MCircle = "Mcircle",
+    Rect = "rect",
+    Rectangle = "rectangle",
+    Square = "square",
+    Star = "star",
+    /// No outline.
+    None = "none",
+    Underline = "underline",
+    Cylinder = "cylinder",
+    Note = "note",
+    Tab = "tab",
+    Folder = "folder",
+    Box3d = "box3d",
+    Component = "component",
+    Promoter = "promoter",
+    Cds = "cds",
+    Terminator = "terminator",
+    Utr = "utr",
+    PrimerSite = "primersite",
+    RestrictionSite = "restrictionsite",
+    FivePOverhang = "fivepoverhang",
+    ThreePOverhang = "threepoverhang",
+    NOverhang = "noverhang",
+    Assembly = "assembly",
+    Signature = "signature",
+    Insulator = "insulator",
+    RiboSite = "ribosite",
+    RnaStab = "rnastab",
+    ProteaseSite = "proteasesite",
+    ProteinStab = "proteinstab",
+    RPromoter = "rpromoter",
+    RArrow = "rarrow",
+    LArrow = "larrow",
+    LPromoter = "lpromoter",
+    /// A box split into fields by the label, such as `<in> a|{b|c}`.
+    /// See <https://www.graphviz.org/doc/info/shapes.html#record>.
+    Record = "record",
+    /// A `record` with rounded corners.
+    MRecord = "Mrecord",
+}
+
+impl Shape {
+    /// Returns `true` for `record` and `Mrecord`, whose labels describe
+    /// fields rather than text.
+    pub fn is_record(&self) -> bool {
+        matches!(self, Shape::Record | Shape::MRecord)
+    }
+
+    /// The `shape` attribute, followed by the attributes that give the
+    /// parameters of a [`Shape::Polygon`].
+    pub fn attrs(&self) -> Attrs<'static> {
+        let mut attrs = Attrs::from([Attr::Shape(self.clone())]);
+        if let Shape::Polygon(polygon) = self {
+            attrs.set(Attr::Sides(polygon.sides));
+            if polygon.skew != 0.0 {
+                attrs.set(Attr::Skew(polygon.skew));
+            }
+            if polygon.distortion != 0.0 {
+                attrs.set(Attr::Distortion(polygon.distortion));
+            }
+            if let Some(peripheries) = polygon.peripheries {
+                attrs.set(Attr::Peripheries(peripheries));
+            }
+        }
+        attrs
+    }
+}
+
+impl From<Polygon> for Shape {
+    fn from(polygon: Polygon) -> Self {
+        Shape::Polygon(polygon)
+    }
+}
+
+impl std::fmt::Display for Shape {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// The parameters of a [`Shape::Polygon`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Polygon {
+    /// The number of sides, which must be at least 3.
+    pub sides: u32,
+    /// How much the top of the polygon is shifted to the right relative to
+    /// the bottom, from -1 to 1 and beyond, giving a parallelogram for 4
+    /// sides.
+    pub skew: f64,
+    /// How much wider the top of the polygon is than the bottom, giving a
+    /// trapezium for 4 sides.
+    pub distortion: f64,
+    /// The number of outlines drawn around the polygon, which is 1 unless
+    /// set.
+    pub peripheries: Option<u32>,
+}
+
+impl Default for Polygon {
+    /// A square, which is what Graphviz draws for a plain `polygon`.
+    fn default() -> Self {
+        Polygon::new(4)
+    }
+}
+
+impl Polygon {
+    /// A regular-looking polygon with `sides` sides.
+    pub fn new(sides: u32) -> Self {
+        Polygon {
+            sides,
+            skew: 0.0,
+            distortion: 0.0,
+            peripheries: None,
+        }
+    }
+
+    pub fn with_skew(self, skew: f64) -> Self {
+        Polygon { skew, ..self }
+    }
+
+    pub fn with_distortion(self, distortion: f64) -> Self {
+        Polygon { distortion, ..self }
+    }
+
+    pub fn with_peripheries(self, peripheries: u32) -> Self {
+        Polygon {
+            peripheries: Some(peripheries),
+            ..self
+        }
+    }
+}
+
+/// Error returned when parsing a [`Shape`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ShapeError {
+    /// A name that is not one of the Graphviz shapes. Shape names are case
+    /// sensitive.
+    Unknown(String),
+}
+
+impl std::error::Error for ShapeError {}
+
+impl std::fmt::Display for ShapeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ShapeError::Unknown(s) => write!(f, "Unknown shape '{s}'"),
+        }
+    }
+}
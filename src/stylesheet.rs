@@ -0,0 +1,204 @@
+//! Class-based styling, kept apart from the graph adapters.
+//!
+//! A [`Labeller`](crate::Labeller) can give each node, edge and subgraph a
+//! list of class names, with `node_classes` and the like, and a
+//! [`Stylesheet`] that maps [`Selector`]s over those classes to attributes.
+//! When an element is rendered, the attributes of every rule that matches it
+//! are combined, with more specific rules taking precedence, as in CSS.
+//!
+//! ```rust
+//! use dotwalk::stylesheet::{ElementKind, Selector, Stylesheet};
+//! use dotwalk::{Attr, Attrs, Color, Shape, Style};
+//!
+//! let stylesheet = Stylesheet::new()
+//!     .with_rule(Selector::node(), [Attr::Shape(Shape::Box)])
+//!     .with_rule(Selector::class("error"), [Attr::Color(Color::named("red"))])
+//!     .with_rule(
+//!         Selector::edge().with_class("error"),
+//!         [Attr::Style(Style::Dashed.into())],
+//!     );
+//!
+//! let attrs = stylesheet.resolve(ElementKind::Edge, &["error".into()]);
+//! assert_eq!(
+//!     attrs,
+//!     Attrs::from([Attr::Color(Color::named("red")), Attr::Style(Style::Dashed.into())])
+//! );
+//! ```
+
+use std::borrow::Cow;
+
+use crate::Attrs;
+
+/// The class names of an element.
+pub type Classes<'a> = Vec<Cow<'a, str>>;
+
+/// The kinds of element that a [`Selector`] can be restricted to.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ElementKind {
+    Node,
+    Edge,
+    Subgraph,
+}
+
+impl ElementKind {
+    pub const fn as_static_str(self) -> &'static str {
+        match self {
+            ElementKind::Node => "node",
+            ElementKind::Edge => "edge",
+            ElementKind::Subgraph => "subgraph",
+        }
+    }
+}
+
+/// Selects the elements that a stylesheet rule applies to: those of a kind,
+/// if one is given, that have all of the classes.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Selector<'a> {
+    kind: Option<ElementKind>,
+    classes: Classes<'a>,
+}
+
+impl<'a> Selector<'a> {
+    /// Selects every element.
+    pub fn any() -> Self {
+        Self::default()
+    }
+
+    /// Selects every node.
+    pub fn node() -> Self {
+        Self::kind(ElementKind::Node)
+    }
+
+    /// Selects every edge.
+    pub fn edge() -> Self {
+        Self::kind(ElementKind::Edge)
+    }
+
+    /// Selects every subgraph.
+    pub fn subgraph() -> Self {
+        Self::kind(ElementKind::Subgraph)
+    }
+
+    /// Selects every element of the kind `kind`.
+    pub fn kind(kind: ElementKind) -> Self {
+        Selector {
+            kind: Some(kind),
+            classes: Vec::new(),
+        }
+    }
+
+    /// Selects every element with the class `class`.
+    pub fn class(class: impl Into<Cow<'a, str>>) -> Self {
+        Self::any().with_class(class)
+    }
+
+    /// Additionally requires the class `class`.
+    pub fn with_class(mut self, class: impl Into<Cow<'a, str>>) -> Self {
+        self.classes.push(class.into());
+        self
+    }
+
+    /// Returns `true` if an element of the kind `kind` with the classes
+    /// `classes` is selected.
+    pub fn matches(&self, kind: ElementKind, classes: &[Cow<'_, str>]) -> bool {
+        (self.kind.is_none() || self.kind == Some(kind))
+            && self.classes.iter().all(|class| classes.contains(class))
+    }
+
+    /// Rules with more classes take precedence, then rules restricted to a
+    /// kind of element.
+    fn specificity(&self) -> (usize, bool) {
+        (self.classes.len(), self.kind.is_some())
+    }
+
+    /// Converts the selector into one that owns its class names.
+    pub fn into_owned(self) -> Selector<'static> {
+        Selector {
+            kind: self.kind,
+            classes: self
+                .classes
+                .into_iter()
+                .map(|class| Cow::Owned(class.into_owned()))
+                .collect(),
+        }
+    }
+}
+
+impl std::fmt::Display for Selector<'_> {
+    /// Writes the selector in CSS syntax, such as `node.error`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.kind {
+            Some(kind) => f.write_str(kind.as_static_str())?,
+            None if self.classes.is_empty() => f.write_str("*")?,
+            None => {}
+        }
+        for class in &self.classes {
+            write!(f, ".{class}")?;
+        }
+        Ok(())
+    }
+}
+
+/// An ordered list of rules, each setting attributes on the elements that
+/// its [`Selector`] matches.
+///
+/// Where several rules set the same attribute, the most specific one wins,
+/// and of equally specific ones, the last.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Stylesheet<'a> {
+    rules: Vec<(Selector<'a>, Attrs<'a>)>,
+}
+
+impl<'a> Stylesheet<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a rule setting `attrs` on the elements that `selector` matches.
+    pub fn with_rule(mut self, selector: Selector<'a>, attrs: impl Into<Attrs<'a>>) -> Self {
+        self.add_rule(selector, attrs);
+        self
+    }
+
+    /// Adds a rule setting `attrs` on the elements that `selector` matches.
+    pub fn add_rule(&mut self, selector: Selector<'a>, attrs: impl Into<Attrs<'a>>) {
+        self.rules.push((selector, attrs.into()));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// The rules, in the order they were added.
+    pub fn rules(&self) -> impl Iterator<Item = (&Selector<'a>, &Attrs<'a>)> {
+        self.rules.iter().map(|(selector, attrs)| (selector, attrs))
+    }
+
+    /// Combines the attributes of the rules that match an element of the
+    /// kind `kind` with the classes `classes`.
+    pub fn resolve(&self, kind: ElementKind, classes: &[Cow<'_, str>]) -> Attrs<'a> {
+        let mut matching: Vec<_> = self
+            .rules
+            .iter()
+            .filter(|(selector, _)| selector.matches(kind, classes))
+            .collect();
+        matching.sort_by_key(|(selector, _)| selector.specificity());
+
+        let mut attrs = Attrs::new();
+        for (_, rule) in matching {
+            attrs.extend(rule.iter().cloned());
+        }
+        attrs
+    }
+
+    /// Converts the stylesheet into one that owns its values.
+    pub fn into_owned(self) -> Stylesheet<'static> {
+        Stylesheet {
+            rules: self
+                .rules
+                .into_iter()
+                .map(|(selector, attrs)| (selector.into_owned(), attrs.into_owned()))
+                .collect(),
+        }
+    }
+}
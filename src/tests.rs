@@ -4,7 +4,7 @@ use std::io::prelude::*;
 use NodeLabels::*;
 
 use super::{render, Arrow, ArrowVertex, Edges, GraphWalk, Id, Labeller, Nodes, Side, Style};
-use crate::{GraphKind, RankDir, Subgraphs, Text};
+use crate::{Color, GraphKind, IdError, IdKind, RankDir, Styles, Subgraphs, Text};
 
 /// each node is an index in a vector in the graph.
 type Node = usize;
@@ -160,14 +160,14 @@ impl<'a> Labeller<'a> for LabelledGraph {
     fn edge_label(&'a self, e: &&'a Edge) -> Text<'a> {
         Text::label(e.label)
     }
-    fn node_style(&'a self, n: &Node) -> Style {
-        self.node_styles[*n]
+    fn node_style(&'a self, n: &Node) -> Styles {
+        self.node_styles[*n].into()
     }
-    fn edge_style(&'a self, e: &&'a Edge) -> Style {
-        e.style
+    fn edge_style(&'a self, e: &&'a Edge) -> Styles {
+        e.style.into()
     }
-    fn edge_color(&'a self, e: &&'a Edge) -> Option<Text<'a>> {
-        e.color.map(Text::label)
+    fn edge_color(&'a self, e: &&'a Edge) -> Option<Color<'a>> {
+        e.color.map(Color::named)
     }
     fn edge_end_arrow(&'a self, e: &&'a Edge) -> Arrow {
         e.end_arrow.clone()
@@ -191,19 +191,15 @@ impl<'a> Labeller<'a> for LabelledGraphWithEscStrs {
     fn node_label(&'a self, n: &Node) -> Text<'a> {
         Text::esc(self.graph.node_label(n).into_inner())
     }
-    fn node_color(&'a self, n: &Node) -> Option<Text<'a>> {
-        self.graph
-            .node_color(n)
-            .map(|text| Text::esc(text.into_inner()))
+    fn node_color(&'a self, n: &Node) -> Option<Color<'a>> {
+        self.graph.node_color(n)
     }
     fn edge_label(&'a self, e: &&'a Edge) -> Text<'a> {
         Text::esc(self.graph.edge_label(e).into_inner())
     }
 
-    fn edge_color(&'a self, e: &&'a Edge) -> Option<Text<'a>> {
-        self.graph
-            .edge_color(e)
-            .map(|text| Text::esc(text.into_inner()))
+    fn edge_color(&'a self, e: &&'a Edge) -> Option<Color<'a>> {
+        self.graph.edge_color(e)
     }
 }
 
@@ -289,7 +285,7 @@ fn single_node_with_style() {
     assert_eq!(
         r.unwrap(),
         r#"digraph single_node {
-    N0[label="N0"][style="dashed"];
+    N0[label="N0", style="dashed"];
 }
 "#
     );
@@ -329,7 +325,7 @@ fn single_edge_with_style() {
         r#"digraph single_edge {
     N0[label="N0"];
     N1[label="N1"];
-    N0 -> N1[label="E"][style="bold"][color="red"];
+    N0 -> N1[label="E", style="bold", color="red"];
 }
 "#
     );
@@ -351,8 +347,8 @@ fn test_some_arrow() {
         result.unwrap(),
         r#"digraph test_some_labelled {
     N0[label="A"];
-    N1[label="N1"][style="dotted"];
-    N0 -> N1[label="A-1"][arrowhead="crow"];
+    N1[label="N1", style="dotted"];
+    N0 -> N1[label="A-1", arrowhead="crow"];
 }
 "#
     );
@@ -374,8 +370,8 @@ fn test_some_arrows() {
         result.unwrap(),
         r#"digraph test_some_labelled {
     N0[label="A"];
-    N1[label="N1"][style="dotted"];
-    N0 -> N1[label="A-1"][arrowhead="lcrow" dir="both" arrowtail="tee"];
+    N1[label="N1", style="dotted"];
+    N0 -> N1[label="A-1", arrowhead="lcrow", dir="both", arrowtail="tee"];
 }
 "#
     );
@@ -395,7 +391,7 @@ fn test_some_labelled() {
         result.unwrap(),
         r#"digraph test_some_labelled {
     N0[label="A"];
-    N1[label="N1"][style="dotted"];
+    N1[label="N1", style="dotted"];
     N0 -> N1[label="A-1"];
 }
 "#
@@ -442,10 +438,10 @@ fn hasse_diagram() {
     N1[label="{x}"];
     N2[label="{y}"];
     N3[label="{}"];
-    N0 -> N1[label=""][color="green"];
-    N0 -> N2[label=""][color="blue"];
-    N1 -> N3[label=""][color="red"];
-    N2 -> N3[label=""][color="black"];
+    N0 -> N1[color="green"];
+    N0 -> N2[color="blue"];
+    N1 -> N3[color="red"];
+    N2 -> N3[color="black"];
 }
 "#
     );
@@ -503,10 +499,67 @@ fn simple_id_construction() {
 }
 
 #[test]
-fn badly_formatted_id() {
-    let id2 = Id::new("Weird { struct : ure } !!!");
-    if id2.is_ok() {
-        panic!("graphviz id suddenly allows spaces, brackets and stuff")
+fn quoted_id() {
+    let id2 = Id::new("Weird { struct : ure } \"!!!\"").unwrap();
+    assert_eq!(id2.kind(), IdKind::Quoted);
+    assert_eq!(id2.to_string(), r#""Weird { struct : ure } \"!!!\"""#);
+}
+
+#[test]
+fn id_forms() {
+    let cases = [
+        ("N0", IdKind::Ident, "N0"),
+        ("_café", IdKind::Ident, "_café"),
+        ("3.14", IdKind::Numeral, "3.14"),
+        ("-.5", IdKind::Numeral, "-.5"),
+        ("1abc", IdKind::Quoted, r#""1abc""#),
+        ("my-crate", IdKind::Quoted, r#""my-crate""#),
+        ("std::vec::Vec", IdKind::Quoted, r#""std::vec::Vec""#),
+        (r"C:\\", IdKind::Quoted, r#""C:\\""#),
+    ];
+    for (name, kind, dot) in cases {
+        let id = Id::new(name).unwrap();
+        assert_eq!((id.kind(), &id.to_string()[..]), (kind, dot), "{name}");
+    }
+
+    assert_eq!(Id::html("<b>x</b>").unwrap().to_string(), "<<b>x</b>>");
+    assert_eq!(Id::html("<b>x</b"), Err(IdError::UnbalancedHtml));
+    assert_eq!(Id::quoted("node").unwrap().to_string(), r#""node""#);
+    assert_eq!(Id::quoted("").unwrap().to_string(), r#""""#);
+    assert_eq!(Id::new(""), Err(IdError::EmptyName));
+    assert_eq!(Id::new("a\0b"), Err(IdError::InvalidChar('\0')));
+    assert_eq!(Id::new("a"), Id::quoted("a"));
+    assert_ne!(Id::new("a"), Id::html("a"));
+}
+
+#[test]
+fn quoted_id_round_trip() {
+    use crate::parse::{parse, Stmt};
+
+    // DOT reads `\\` as a pair and drops the backslash of `\"`, so a name
+    // with an odd run of backslashes before a quote or at its end has no
+    // quoted form.
+    for name in [r#"a\"b"#, r#"\""#, r"C:\", r"C:\\\", "a\\\nb"] {
+        assert_eq!(Id::new(name), Err(IdError::DanglingBackslash), "{name}");
+        assert_eq!(Id::quoted(name), Err(IdError::DanglingBackslash), "{name}");
+    }
+    for name in [r"a\b", r#"a"b"#, r#"a\\"b"#, r"C:\\", r"C:\\\\", r"a\\"] {
+        let dot = format!("graph {{ {} }}", Id::new(name).unwrap());
+        let graph = parse(&dot).unwrap_or_else(|e| panic!("{dot}: {e}"));
+        let Stmt::Node(stmt) = &graph.stmts[0] else {
+            panic!("{dot}: expected a node statement");
+        };
+        assert_eq!(&stmt.node.id[..], name, "{dot}");
+    }
+}
+
+#[test]
+fn keyword_ids() {
+    for kw in ["node", "EDGE", "Subgraph", "strict", "graph", "digraph"] {
+        assert_eq!(
+            Id::new(kw),
+            Err(IdError::Keyword(crate::types::keyword(kw).unwrap()))
+        );
     }
 }
 
@@ -519,6 +572,7 @@ struct DefaultStyleGraph {
     nodes: usize,
     edges: Vec<SimpleEdge>,
     subgraphs: Vec<Vec<Node>>,
+    subgraph_edges: Vec<Vec<usize>>,
     rankdir: Option<RankDir>,
 }
 
@@ -537,6 +591,7 @@ impl DefaultStyleGraph {
             nodes,
             edges,
             subgraphs,
+            subgraph_edges: Vec::new(),
             rankdir: None,
         }
     }
@@ -544,6 +599,13 @@ impl DefaultStyleGraph {
     fn with_rankdir(self, rankdir: Option<RankDir>) -> Self {
         Self { rankdir, ..self }
     }
+
+    fn with_subgraph_edges(self, subgraph_edges: Vec<Vec<usize>>) -> Self {
+        Self {
+            subgraph_edges,
+            ..self
+        }
+    }
 }
 
 impl<'a> Labeller<'a> for DefaultStyleGraph {
@@ -595,6 +657,9 @@ impl<'a> GraphWalk<'a> for DefaultStyleGraph {
     fn subgraph_nodes(&'a self, s: &usize) -> Nodes<'a, Node> {
         std::borrow::Cow::Borrowed(&self.subgraphs[*s])
     }
+    fn subgraph_edges(&'a self, s: &usize) -> Edges<'a, usize> {
+        std::borrow::Cow::Borrowed(self.subgraph_edges.get(*s).map_or(&[], |edges| edges))
+    }
 }
 
 fn test_input_default(g: DefaultStyleGraph) -> io::Result<String> {
@@ -621,10 +686,10 @@ fn default_style_graph() {
     N1[label="N1"];
     N2[label="N2"];
     N3[label="N3"];
-    N0 -- N1[label=""];
-    N0 -- N2[label=""];
-    N1 -- N3[label=""];
-    N2 -- N3[label=""];
+    N0 -- N1;
+    N0 -- N2;
+    N1 -- N3;
+    N2 -- N3;
 }
 "#
     );
@@ -646,10 +711,10 @@ fn default_style_digraph() {
     N1[label="N1"];
     N2[label="N2"];
     N3[label="N3"];
-    N0 -> N1[label=""];
-    N0 -> N2[label=""];
-    N1 -> N3[label=""];
-    N2 -> N3[label=""];
+    N0 -> N1;
+    N0 -> N2;
+    N1 -> N3;
+    N2 -> N3;
 }
 "#
     );
@@ -675,8 +740,8 @@ fn digraph_with_rankdir() {
     N1[label="N1"];
     N2[label="N2"];
     N3[label="N3"];
-    N0 -> N1[label=""];
-    N0 -> N2[label=""];
+    N0 -> N1;
+    N0 -> N2;
 }
 "#
     );
@@ -694,25 +759,2817 @@ fn subgraph() {
     assert_eq!(
         r.unwrap(),
         r#"digraph di {
-subgraph cluster_0 {
-    label="";
-    N0;
-    N1;
-}
-subgraph cluster_1 {
-    label="";
-    N2;
-    N3;
-}
+    subgraph cluster_0 {
+        label="";
+        N0;
+        N1;
+    }
+    subgraph cluster_1 {
+        label="";
+        N2;
+        N3;
+    }
     N0[label="N0"];
     N1[label="N1"];
     N2[label="N2"];
     N3[label="N3"];
-    N0 -> N1[label=""];
-    N0 -> N2[label=""];
-    N1 -> N3[label=""];
-    N2 -> N3[label=""];
+    N0 -> N1;
+    N0 -> N2;
+    N1 -> N3;
+    N2 -> N3;
 }
 "#
     );
 }
+
+mod parse {
+    use std::borrow::Cow;
+
+    use crate::parse::{
+        parse, AttrTarget, Attribute, Graph, NodeId, ParseErrorKind, Stmt, Subgraph, Vertex,
+    };
+    use crate::{Arrow, ArrowVertex, CompassPoint, GraphKind, Id, IdKind, ShapeFill, Side, Text};
+
+    fn id(name: &str) -> Id<'_> {
+        Id {
+            name: Cow::Borrowed(name),
+            kind: IdKind::Ident,
+        }
+    }
+
+    fn node(name: &str) -> NodeId<'_> {
+        NodeId {
+            id: id(name),
+            port: None,
+            compass: None,
+        }
+    }
+
+    #[test]
+    fn empty_graph() {
+        assert_eq!(
+            parse("strict graph {}").unwrap(),
+            Graph {
+                strict: true,
+                kind: GraphKind::Undirected,
+                id: None,
+                stmts: vec![],
+            }
+        );
+    }
+
+    #[test]
+    fn statements() {
+        let g = parse(
+            r#"# 1 "generated.dot"
+            DiGraph "my graph" {
+                // line comment
+                rankdir = LR; /* block
+                comment */
+                node [shape=box, color="red"][fontsize=10.5]
+                a:p1:ne -> b:s -> { c; d } [label=<<b>x</b>>];
+                subgraph cluster_0 { e }
+            }
+            "#,
+        )
+        .unwrap();
+
+        assert!(!g.strict);
+        assert_eq!(g.kind, GraphKind::Directed);
+        assert_eq!(g.id, Some(id("my graph")));
+        assert_eq!(g.stmts.len(), 4);
+        assert_eq!(
+            g.stmts[0],
+            Stmt::Assign(Attribute {
+                name: id("rankdir"),
+                value: Text::esc("LR"),
+            })
+        );
+
+        let Stmt::Attr(attrs) = &g.stmts[1] else {
+            panic!("expected attribute statement")
+        };
+        assert_eq!(attrs.target, AttrTarget::Node);
+        let values: Vec<_> = attrs.attrs.iter().map(|a| &a.value).collect();
+        assert_eq!(
+            values,
+            [&Text::esc("box"), &Text::esc("red"), &Text::esc("10.5")]
+        );
+
+        let Stmt::Edge(edge) = &g.stmts[2] else {
+            panic!("expected edge statement")
+        };
+        assert_eq!(
+            edge.chain,
+            [
+                Vertex::Node(NodeId {
+                    id: id("a"),
+                    port: Some(id("p1")),
+                    compass: Some(CompassPoint::NorthEast),
+                }),
+                Vertex::Node(NodeId {
+                    compass: Some(CompassPoint::South),
+                    ..node("b")
+                }),
+                Vertex::Subgraph(Subgraph {
+                    id: None,
+                    stmts: vec![
+                        Stmt::Node(crate::parse::NodeStmt {
+                            node: node("c"),
+                            attrs: vec![],
+                        }),
+                        Stmt::Node(crate::parse::NodeStmt {
+                            node: node("d"),
+                            attrs: vec![],
+                        }),
+                    ],
+                }),
+            ]
+        );
+        assert_eq!(edge.attrs[0].value, Text::html("<b>x</b>"));
+
+        let Stmt::Subgraph(sub) = &g.stmts[3] else {
+            panic!("expected subgraph")
+        };
+        assert_eq!(sub.id, Some(id("cluster_0")));
+    }
+
+    #[test]
+    fn quoted_strings() {
+        let g = parse("graph { a [label=\"say \\\"hi\\\"\\l\" + \" and\\\n bye\"] }").unwrap();
+        let Stmt::Node(n) = &g.stmts[0] else {
+            panic!("expected node statement")
+        };
+        assert_eq!(n.attrs[0].value, Text::esc("say \"hi\"\\l and bye"));
+    }
+
+    #[test]
+    fn numerals() {
+        let g = parse("graph { -1.5 -- .5 -- 3 }").unwrap();
+        let Stmt::Edge(e) = &g.stmts[0] else {
+            panic!("expected edge statement")
+        };
+        let ids: Vec<_> = e
+            .chain
+            .iter()
+            .map(|v| match v {
+                Vertex::Node(n) => &n.id.name[..],
+                Vertex::Subgraph(_) => panic!("expected node"),
+            })
+            .collect();
+        assert_eq!(ids, ["-1.5", ".5", "3"]);
+    }
+
+    #[test]
+    fn edge_arrows() {
+        let g = parse("digraph { a -> b [arrowhead=olbox, arrowtail=invodot] }").unwrap();
+        let Stmt::Edge(e) = &g.stmts[0] else {
+            panic!("expected edge statement")
+        };
+        assert_eq!(
+            e.arrowhead(),
+            Some(Ok(Arrow::from(ArrowVertex::Box(
+                ShapeFill::Open,
+                Side::Left
+            ))))
+        );
+        assert_eq!(
+            e.arrowtail(),
+            Some(Ok(Arrow::from([
+                ArrowVertex::inv(),
+                ArrowVertex::Dot(ShapeFill::Open)
+            ])))
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let err = parse("graph {\n  a -> b\n}").unwrap_err();
+        assert_eq!(
+            err.kind,
+            ParseErrorKind::EdgeOpMismatch(GraphKind::Undirected)
+        );
+        assert_eq!((err.line, err.column), (2, 5));
+
+        let err = parse("digraph { a [label=\"x] }").unwrap_err();
+        assert_eq!(err.kind, ParseErrorKind::UnterminatedString);
+
+        let err = parse("digraph { a } b").unwrap_err();
+        assert_eq!(err.to_string(), "1:15: expected end of input, found ID `b`");
+
+        let err = parse("digraph { node }").unwrap_err();
+        assert_eq!(err.to_string(), "1:16: expected `[`, found `}`");
+    }
+}
+
+mod dot_graph {
+    use crate::graph::{DotGraph, Edge, Node, Subgraph};
+    use crate::render::RenderOption;
+    use crate::{render, Attr, Attrs, Color, GraphKind, Id, Shape, Style, Text};
+
+    fn render_to_string(g: &DotGraph) -> String {
+        let mut writer = Vec::new();
+        render(g, &mut writer).unwrap();
+        String::from_utf8(writer).unwrap()
+    }
+
+    #[test]
+    fn anonymous() {
+        use crate::{IdKind, Labeller};
+
+        let mut g = DotGraph::new(None, GraphKind::Undirected);
+        g.add_node(Node::new(Id::new("a").unwrap()));
+        assert_eq!(g.graph_id().kind(), IdKind::Quoted);
+        assert_eq!(render_to_string(&g), "graph {\n    a[label=\"a\"];\n}\n");
+    }
+
+    #[test]
+    #[should_panic(expected = "edge 0 -> 1 refers to a node that is not in a graph of 1 nodes")]
+    fn edge_to_missing_node() {
+        let mut g = DotGraph::new(None, GraphKind::Directed);
+        let a = g.add_node(Node::new(Id::new("a").unwrap()));
+        g.add_edge(Edge::new(a, crate::graph::NodeIndex(1)));
+    }
+
+    #[test]
+    fn builder() {
+        let mut g = DotGraph::new(Id::new("g").ok(), GraphKind::Directed);
+        let a = g.add_node(Node::new(Id::new("a").unwrap()).with_label(Text::label("A")));
+        let b = g.add_node(Node::new(Id::new("b").unwrap()).with_attr(Attr::Shape(Shape::Box)));
+        assert_eq!(g.add_node(Node::new(Id::new("a").unwrap())), a);
+        g.add_edge(Edge::new(a, b).with_label(Text::label("a to b")));
+        g.add_subgraph(Subgraph::new(Id::new("cluster_0").ok()).with_nodes([b]));
+
+        assert_eq!(
+            render_to_string(&g),
+            r#"digraph g {
+    subgraph cluster_0 {
+        label="";
+        b;
+    }
+    a[label="A"];
+    b[label="b", shape="box"];
+    a -> b[label="a to b"];
+}
+"#
+        );
+    }
+
+    #[test]
+    fn from_parsed() {
+        let g: DotGraph = r#"digraph {
+            node [shape=box]
+            a [label="A"]
+            subgraph cluster_x { node [color=red]; b; c }
+            a -> { b c } -> d:e [style=dashed]
+        }"#
+        .parse()
+        .unwrap();
+
+        assert_eq!(g.node_count(), 4);
+        assert_eq!(g.edge_count(), 4);
+        assert_eq!(g.subgraph_count(), 2);
+
+        let a = g.node(g.find_node("a").unwrap());
+        assert_eq!(a.label, Some(Text::esc("A")));
+        let b = g.node(g.find_node("b").unwrap());
+        assert_eq!(
+            b.attrs,
+            Attrs::from([Attr::Shape(Shape::Box), Attr::Color(Color::named("red"))])
+        );
+        let d = g.node(g.find_node("d").unwrap());
+        assert_eq!(d.attrs, Attrs::from([Attr::Shape(Shape::Box)]));
+
+        assert_eq!(
+            render_to_string(&g),
+            r#"digraph {
+    subgraph cluster_x {
+        label="";
+        b;
+        c;
+    }
+    subgraph {
+        label="";
+        b;
+        c;
+    }
+    a[label="A", shape="box"];
+    b[label="b", shape="box", color="red"];
+    c[label="c", shape="box", color="red"];
+    d[label="d", shape="box"];
+    a -> b[style="dashed"];
+    a -> c[style="dashed"];
+    b -> d:e[style="dashed"];
+    c -> d:e[style="dashed"];
+}
+"#
+        );
+    }
+
+    #[test]
+    fn parsed_defaults_keep_order() {
+        let g: DotGraph = "digraph { node [style=filled, color=red, shape=box]; a [fontsize=8] }"
+            .parse()
+            .unwrap();
+
+        let a = g.node(g.find_node("a").unwrap());
+        assert_eq!(
+            a.attrs,
+            Attrs::from([
+                Attr::Style(Style::Filled.into()),
+                Attr::Color(Color::named("red")),
+                Attr::Shape(Shape::Box),
+                Attr::FontSize(8.0),
+            ])
+        );
+        assert_eq!(
+            render_to_string(&g),
+            r#"digraph {
+    a[label="a", style="filled", color="red", shape="box", fontsize=8];
+}
+"#
+        );
+    }
+
+    #[test]
+    fn round_trip_ids() {
+        let g: DotGraph = r#"graph "my graph" { "std::vec::Vec":"p 1" -- 3.5 -- <<b>x</b>> }"#
+            .parse()
+            .unwrap();
+        assert_eq!(
+            render_to_string(&g),
+            r#"graph "my graph" {
+    "std::vec::Vec"[label="std::vec::Vec"];
+    3.5[label="3.5"];
+    <<b>x</b>>[label="<b>x</b>"];
+    "std::vec::Vec":"p 1" -- 3.5;
+    3.5 -- <<b>x</b>>;
+}
+"#
+        );
+    }
+
+    #[test]
+    fn nested_subgraphs() {
+        let g: DotGraph = r#"digraph {
+            subgraph cluster_crate {
+                label="crate"; root
+                subgraph cluster_mod { label="mod"; a; subgraph cluster_sub { b } }
+                subgraph cluster_other { c }
+            }
+            d
+        }"#
+        .parse()
+        .unwrap();
+
+        assert_eq!(
+            render_to_string(&g),
+            r#"digraph {
+    subgraph cluster_crate {
+        label="crate";
+        subgraph cluster_mod {
+            label="mod";
+            subgraph cluster_sub {
+                label="";
+                b;
+            }
+            a;
+        }
+        subgraph cluster_other {
+            label="";
+            c;
+        }
+        root;
+    }
+    root[label="root"];
+    a[label="a"];
+    b[label="b"];
+    c[label="c"];
+    d[label="d"];
+}
+"#
+        );
+    }
+
+    #[test]
+    fn subgraph_edges() {
+        let mut g: DotGraph = r#"digraph {
+            entry -> head
+            subgraph cluster_loop {
+                head -> body
+                subgraph cluster_body { body -> body }
+            }
+        }"#
+        .parse()
+        .unwrap();
+        let back = g.add_edge(Edge::new(
+            g.find_node("body").unwrap(),
+            g.find_node("head").unwrap(),
+        ));
+        let cluster = g.find_subgraph("cluster_loop").unwrap();
+        let cluster = g.subgraph_mut(cluster);
+        cluster.edges.push(back);
+        cluster.edge_defaults = Attrs::from([Attr::Style(Style::Dashed.into())]);
+
+        assert_eq!(
+            render_to_string(&g),
+            r#"digraph {
+    subgraph cluster_loop {
+        label="";
+        edge[style="dashed"];
+        subgraph cluster_body {
+            label="";
+            body;
+            body -> body;
+        }
+        head;
+        head -> body;
+        body -> head;
+    }
+    entry[label="entry"];
+    head[label="head"];
+    body[label="body"];
+    entry -> head;
+}
+"#
+        );
+    }
+
+    #[test]
+    fn parallel_subgraph_edges() {
+        // The second edge belongs to both clusters; the first, which renders
+        // the same, is only emitted at the top level.
+        let g: DotGraph = r#"digraph {
+            a -> b
+            subgraph cluster_outer { subgraph cluster_inner { a -> b } }
+        }"#
+        .parse()
+        .unwrap();
+
+        assert_eq!(
+            render_to_string(&g),
+            r#"digraph {
+    subgraph cluster_outer {
+        label="";
+        subgraph cluster_inner {
+            label="";
+            a;
+            b;
+            a -> b;
+        }
+    }
+    a[label="a"];
+    b[label="b"];
+    a -> b;
+}
+"#
+        );
+    }
+
+    #[test]
+    fn combined_attr_lists() {
+        let mut g = DotGraph::new(Id::new("g").ok(), GraphKind::Directed);
+        let a = g.add_node(
+            Node::new(Id::new("a").unwrap())
+                .with_attr(Attr::Shape(Shape::Box))
+                .with_attr(Attr::Label(Text::label("override"))),
+        );
+        let b = g.add_node(Node::new(Id::new("b").unwrap()));
+        g.add_edge(
+            Edge::new(a, b)
+                .with_attr(Attr::custom(Id::new("foo").unwrap(), Text::esc("bar")))
+                .with_attr(Attr::custom(Id::new("baz").unwrap(), Text::esc("qux"))),
+        );
+        g.add_edge(Edge::new(b, a).with_label(Text::label("")));
+
+        let output = render_to_string(&g);
+        assert_eq!(
+            output,
+            r#"digraph g {
+    a[label="override", shape="box"];
+    b[label="b"];
+    a -> b[foo="bar", baz="qux"];
+    b -> a;
+}
+"#
+        );
+        let parsed: DotGraph = output.parse().unwrap();
+        assert_eq!(parsed.edge_count(), 2);
+        assert_eq!(parsed.edge(crate::graph::EdgeIndex(0)).attrs.len(), 2);
+    }
+
+    #[test]
+    fn defaults() {
+        let mut g = DotGraph::new(Id::new("g").ok(), GraphKind::Directed)
+            .with_node_defaults(Attrs::from([
+                Attr::Shape(Shape::Box),
+                Attr::FontName(Text::label("Fira Sans")),
+            ]))
+            .with_edge_defaults(Attrs::from([Attr::PenWidth(0.5)]));
+        let a = g.add_node(Node::new(Id::new("a").unwrap()));
+        let b = g.add_node(Node::new(Id::new("b").unwrap()));
+        g.add_edge(Edge::new(a, b));
+        g.add_subgraph(
+            Subgraph::new(Id::new("cluster_0").ok())
+                .with_nodes([b])
+                .with_node_defaults(Attrs::from([Attr::Color(Color::named("red"))])),
+        );
+
+        let mut writer = Vec::new();
+        crate::render_opts(&g, &mut writer, &[RenderOption::Fontname("mono".into())]).unwrap();
+        assert_eq!(
+            String::from_utf8(writer).unwrap(),
+            r#"digraph g {
+    graph[fontname="mono"];
+    node[fontname="Fira Sans", shape="box"];
+    edge[fontname="mono", penwidth=0.5];
+    subgraph cluster_0 {
+        label="";
+        node[color="red"];
+        b;
+    }
+    a[label="a"];
+    b[label="b"];
+    a -> b;
+}
+"#
+        );
+    }
+
+    #[test]
+    fn strict_merges_edges() {
+        let g: DotGraph = "strict graph { a -- b; b -- a [color=red]; a -- b -- a }"
+            .parse()
+            .unwrap();
+        assert_eq!(g.edge_count(), 1);
+        assert_eq!(
+            g.edge(crate::graph::EdgeIndex(0)).attrs,
+            Attrs::from([Attr::Color(Color::named("red"))])
+        );
+    }
+}
+
+mod attrs {
+    use crate::attrs::{Dir, Rank};
+    use crate::graph::{DotGraph, Node};
+    use crate::{render, Arrow, Attr, Attrs, Color, GraphKind, Id, RankDir, Style, Text};
+
+    #[test]
+    fn values() {
+        assert_eq!(Attr::PenWidth(2.5).to_string(), "penwidth=2.5");
+        assert_eq!(Attr::FontSize(12.0).to_string(), "fontsize=12");
+        assert_eq!(Attr::MinLen(2).to_string(), "minlen=2");
+        assert_eq!(Attr::Constraint(false).to_string(), "constraint=false");
+        assert_eq!(
+            Attr::Style(Style::Dashed.into()).to_string(),
+            r#"style="dashed""#
+        );
+        assert_eq!(Attr::Dir(Dir::Both).to_string(), r#"dir="both""#);
+        assert_eq!(
+            Attr::RankDir(RankDir::LeftRight).to_string(),
+            r#"rankdir="LR""#
+        );
+        assert_eq!(
+            Attr::ArrowHead(Arrow::none()).to_string(),
+            r#"arrowhead="none""#
+        );
+        assert_eq!(
+            Attr::Url(Text::label("https://example.com/a b")).to_string(),
+            r#"URL="https://example.com/a b""#
+        );
+        assert_eq!(
+            Attr::LHead(Id::new("cluster 0").unwrap()).to_string(),
+            r#"lhead="cluster 0""#
+        );
+    }
+
+    #[test]
+    fn escaping() {
+        let tooltip = Attr::Tooltip(Text::label(r#"say "hi" \ bye"#));
+        assert_eq!(tooltip.to_string(), r#"tooltip="say \"hi\" \\ bye""#);
+        let label = Attr::Label(Text::label("caf\u{e9}\nau lait"));
+        assert_eq!(label.to_string(), r#"label="café\nau lait""#);
+        let custom = Attr::custom(Id::new("data-x").unwrap(), Text::esc(r#"a "b""#));
+        assert_eq!(custom.to_string(), r#""data-x"="a \"b\"""#);
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(Attr::parse("penwidth", Text::esc("2")), Attr::PenWidth(2.0));
+        assert_eq!(
+            Attr::parse("constraint", Text::esc("no")),
+            Attr::Constraint(false)
+        );
+        assert_eq!(
+            Attr::parse("rank", Text::esc("same")),
+            Attr::Rank(Rank::Same)
+        );
+        assert_eq!(
+            Attr::parse("arrowhead", Text::esc("odot")),
+            Attr::ArrowHead("odot".parse().unwrap())
+        );
+        assert_eq!(
+            Attr::parse("penwidth", Text::esc("wide")),
+            Attr::Custom(Id::new("penwidth").unwrap(), Text::esc("wide"))
+        );
+        assert_eq!(
+            Attr::parse("node", Text::esc("x")).to_string(),
+            r#""node"="x""#
+        );
+    }
+
+    #[test]
+    fn render_node_attrs() {
+        let mut g = DotGraph::new(Id::new("g").ok(), GraphKind::Directed);
+        g.add_node(
+            Node::new(Id::new("a").unwrap())
+                .with_attr(Attr::Tooltip(Text::label("the \"first\" node")))
+                .with_attr(Attr::PenWidth(2.0))
+                .with_attr(Attr::PenWidth(3.0)),
+        );
+        let mut writer = Vec::new();
+        render(&g, &mut writer).unwrap();
+        assert_eq!(
+            String::from_utf8(writer).unwrap(),
+            r#"digraph g {
+    a[label="a", tooltip="the \"first\" node", penwidth=3];
+}
+"#
+        );
+    }
+
+    #[test]
+    fn ordered() {
+        let mut attrs = Attrs::new()
+            .with(Attr::Weight(2.0))
+            .with(Attr::Color(Color::named("red")))
+            .with(Attr::Tooltip(Text::label("t")));
+        assert_eq!(attrs.set(Attr::Weight(5.0)), Some(Attr::Weight(2.0)));
+        assert_eq!(
+            attrs.remove("color"),
+            Some(Attr::Color(Color::named("red")))
+        );
+        attrs.extend([Attr::MinLen(1), Attr::Color(Color::named("blue"))]);
+
+        let names: Vec<_> = attrs.iter().map(Attr::name).collect();
+        assert_eq!(names, ["weight", "tooltip", "minlen", "color"]);
+        assert_eq!(attrs.get("weight"), Some(&Attr::Weight(5.0)));
+        assert!(!attrs.contains("penwidth"));
+    }
+
+    #[test]
+    fn render_in_order() {
+        let g: DotGraph = r#"digraph g {
+            a [z=1, y=2, x=3, tooltip="t", fontsize=10, color=red]
+        }"#
+        .parse()
+        .unwrap();
+        let expected = r#"digraph g {
+    a[label="a", z="1", y="2", x="3", tooltip="t", fontsize=10, color="red"];
+}
+"#;
+        for _ in 0..8 {
+            let mut writer = Vec::new();
+            render(&g, &mut writer).unwrap();
+            assert_eq!(String::from_utf8(writer).unwrap(), expected);
+        }
+    }
+}
+
+mod render_errors {
+    use super::{edge, LabelledGraph, UnlabelledNodes};
+    use crate::graph::{DotGraph, Node, Subgraph};
+    use crate::render::RenderError;
+    use crate::{render, Attr, GraphKind, GraphWalk, Id, Labeller, Nodes, Style, Text};
+
+    fn render_err<'a, G>(g: &'a G) -> RenderError
+    where
+        G: Labeller<'a, Node = usize, Edge = (usize, usize), Subgraph = ()>
+            + GraphWalk<'a, Node = usize, Edge = (usize, usize), Subgraph = ()>,
+    {
+        let mut writer = Vec::new();
+        let err = render(g, &mut writer).unwrap_err();
+        assert!(writer.is_empty());
+        err
+    }
+
+    /// Nodes named by `names`, which need not be unique.
+    struct Named(Vec<&'static str>, Vec<(usize, usize)>);
+
+    impl<'a> Labeller<'a> for Named {
+        type Node = usize;
+        type Edge = (usize, usize);
+        type Subgraph = ();
+
+        fn graph_id(&'a self) -> Id<'a> {
+            Id::new("g").unwrap()
+        }
+
+        fn node_id(&'a self, n: &usize) -> Id<'a> {
+            Id::new(self.0[*n]).unwrap()
+        }
+    }
+
+    impl<'a> GraphWalk<'a> for Named {
+        type Node = usize;
+        type Edge = (usize, usize);
+        type Subgraph = ();
+
+        fn nodes(&'a self) -> Nodes<'a, usize> {
+            (0..self.0.len()).collect()
+        }
+
+        fn edges(&'a self) -> crate::Edges<'a, (usize, usize)> {
+            self.1[..].into()
+        }
+
+        fn source(&'a self, e: &(usize, usize)) -> usize {
+            e.0
+        }
+
+        fn target(&'a self, e: &(usize, usize)) -> usize {
+            e.1
+        }
+    }
+
+    #[test]
+    fn duplicate_node() {
+        let err = render_err(&Named(vec!["a", "b", "a"], vec![]));
+        assert!(matches!(&err, RenderError::DuplicateNode(id) if &id[..] == "a"));
+        assert_eq!(err.to_string(), "Node a is declared more than once");
+    }
+
+    #[test]
+    fn unknown_node() {
+        let g = LabelledGraph::new(
+            "g",
+            UnlabelledNodes(2),
+            vec![edge(0, 5, "", Style::None, None)],
+            None,
+        );
+        let mut writer = Vec::new();
+        let err = render(&g, &mut writer).unwrap_err();
+        assert!(writer.is_empty());
+        assert_eq!(err.to_string(), "Edge N0 -> N5 refers to unknown node N5");
+    }
+
+    #[test]
+    fn invalid_attribute() {
+        let mut g = DotGraph::new(Id::new("g").ok(), GraphKind::Directed);
+        g.add_node(Node::new(Id::new("a").unwrap()).with_attr(Attr::PenWidth(-1.0)));
+        g.add_node(Node::new(Id::new("b").unwrap()).with_attr(Attr::Tooltip(Text::html("<b"))));
+
+        let mut writer = Vec::new();
+        let err = render(&g, &mut writer).unwrap_err();
+        assert!(writer.is_empty());
+        assert_eq!(
+            err.to_string(),
+            "Attribute penwidth=-1 of node a is negative"
+        );
+
+        g.node_mut(g.find_node("a").unwrap())
+            .attrs
+            .remove("penwidth");
+        let err = render(&g, &mut writer).unwrap_err();
+        assert!(matches!(
+            err,
+            RenderError::InvalidAttribute {
+                element,
+                attr,
+                value,
+                reason: "has unbalanced angle brackets",
+            } if element == "node b" && attr == "tooltip" && value == "<<b>"
+        ));
+    }
+
+    #[test]
+    fn duplicate_subgraph() {
+        let mut g = DotGraph::new(Id::new("g").ok(), GraphKind::Directed);
+        let inner = g.add_subgraph(Subgraph::new(Id::new("cluster_0").ok()));
+        g.add_subgraph(Subgraph::new(Id::new("cluster_1").ok()).with_subgraphs([inner]));
+        g.add_subgraph(Subgraph::new(Id::new("cluster_0").ok()));
+
+        let mut writer = Vec::new();
+        let err = render(&g, &mut writer).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Subgraph cluster_0 is declared more than once"
+        );
+    }
+}
+
+mod validate {
+    use super::{edge, LabelledGraph, UnlabelledNodes};
+    use crate::graph::{DotGraph, Edge, EdgeIndex, Node, Subgraph};
+    use crate::stylesheet::{Selector, Stylesheet};
+    use crate::validate::{validate, Diagnostic};
+    use crate::{Arrow, ArrowVertex, Attr, Attrs, GraphKind, Id, Shape, Style, Text};
+
+    #[test]
+    fn valid() {
+        let g: DotGraph = r#"digraph {
+            a [shape=record, label="<in> a|<out> \<b\>"]
+            b [label=<<table><tr><td PORT="p">b</td></tr></table>>]
+            subgraph cluster_x { style=filled; a; subgraph inner { a } }
+            a:out -> b:p [arrowhead=dotodot]
+            b -> a:in:w
+        }"#
+        .parse()
+        .unwrap();
+        assert_eq!(validate(&g), vec![]);
+    }
+
+    #[test]
+    fn problems() {
+        let mut g: DotGraph = r#"digraph {
+            a [shape=record, label="<in> a|<out>"]
+            b [label="b"]
+            subgraph x { label="X"; a }
+            subgraph cluster_y { a }
+            a:out -> b:p [penwidth=-1]
+        }"#
+        .parse()
+        .unwrap();
+        let arrow = Arrow {
+            arrows: vec![ArrowVertex::vee(); 5],
+        };
+        g.edge_mut(EdgeIndex(0)).attrs.set(Attr::ArrowHead(arrow));
+
+        let diagnostics = validate(&g);
+        assert_eq!(
+            diagnostics,
+            vec![
+                Diagnostic::UndefinedPort {
+                    edge: "a -> b".to_owned(),
+                    node: Id::new("b").unwrap(),
+                    port: Id::new("p").unwrap(),
+                },
+                Diagnostic::TooManyArrowShapes {
+                    edge: "a -> b".to_owned(),
+                    shapes: 5,
+                },
+                Diagnostic::InvalidAttribute {
+                    element: "edge a -> b".to_owned(),
+                    attr: "penwidth".to_owned(),
+                    value: "-1".to_owned(),
+                    reason: "is negative",
+                },
+                Diagnostic::NotACluster {
+                    subgraph: "subgraph x".to_owned(),
+                },
+                Diagnostic::NodeInSeveralSubgraphs {
+                    node: Id::new("a").unwrap(),
+                    subgraphs: ["subgraph x".to_owned(), "subgraph cluster_y".to_owned()],
+                },
+            ]
+        );
+        let messages: Vec<_> = diagnostics.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            messages,
+            [
+                "Edge a -> b refers to port p, which node b does not define",
+                "Edge a -> b has an arrow with 5 shapes, but at most four are allowed",
+                "Attribute penwidth=-1 of edge a -> b is negative",
+                "subgraph x is styled like a cluster, but its id does not start with \"cluster\"",
+                "Node a is in both subgraph x and subgraph cluster_y",
+            ]
+        );
+    }
+
+    #[test]
+    fn ports_from_defaults() {
+        let stylesheet =
+            Stylesheet::new().with_rule(Selector::class("record"), [Attr::Shape(Shape::Record)]);
+        let mut g = DotGraph::new(None, GraphKind::Directed)
+            .with_node_defaults(Attrs::from([Attr::Shape(Shape::Record)]))
+            .with_stylesheet(stylesheet);
+        let record = |id| Node::new(Id::new(id).unwrap()).with_label(Text::esc("<p> x"));
+        let a = g.add_node(record("a"));
+        let b = g.add_node(record("b"));
+        let c = g.add_node(record("c").with_class("record"));
+        g.add_subgraph(
+            Subgraph::new(Id::new("cluster_boxes").ok())
+                .with_node_defaults(Attrs::from([Attr::Shape(Shape::Box)]))
+                .with_nodes([b, c]),
+        );
+        let port = || Id::new("p").ok();
+        g.add_edge(Edge::new(a, b).with_source_port(port(), None));
+        g.add_edge(Edge::new(b, c).with_source_port(port(), None));
+        g.add_edge(Edge::new(c, a).with_source_port(port(), None));
+
+        // `a` is a record by the graph defaults, and `c` by the stylesheet,
+        // but the subgraph makes `b` a box.
+        assert_eq!(
+            validate(&g),
+            vec![Diagnostic::UndefinedPort {
+                edge: "b -> c".to_owned(),
+                node: Id::new("b").unwrap(),
+                port: Id::new("p").unwrap(),
+            }]
+        );
+    }
+
+    #[test]
+    fn unknown_nodes() {
+        let g = LabelledGraph::new(
+            "g",
+            UnlabelledNodes(2),
+            vec![
+                edge(0, 5, "", Style::None, None),
+                edge(6, 1, "", Style::None, None),
+            ],
+            None,
+        );
+        let nodes: Vec<_> = validate(&g)
+            .into_iter()
+            .map(|d| match d {
+                Diagnostic::UnknownNode { node, .. } => node,
+                d => panic!("unexpected diagnostic {d}"),
+            })
+            .collect();
+        assert_eq!(nodes, [Id::new("N5").unwrap(), Id::new("N6").unwrap()]);
+    }
+}
+
+mod render_options {
+    use crate::graph::{DotGraph, Edge, Node, Subgraph};
+    use crate::render::{AttrOrder, LineEnding, RenderOption, RenderOptions};
+    use crate::{render_opts, Attr, Color, GraphKind, Id, Style, Text, Theme};
+
+    fn graph() -> DotGraph {
+        let mut g = DotGraph::new(Id::new("g").ok(), GraphKind::Directed);
+        let a = g.add_node(
+            Node::new(Id::new("a").unwrap())
+                .with_attr(Attr::Style(Style::Filled.into()))
+                .with_attr(Attr::Color(Color::named("red"))),
+        );
+        let b = g.add_node(Node::new(Id::new("b").unwrap()));
+        g.add_edge(Edge::new(a, b).with_label(Text::label("x")));
+        g.add_subgraph(Subgraph::new(Id::new("cluster_0").ok()).with_nodes([b]));
+        g
+    }
+
+    fn render_to_string(g: &DotGraph, options: impl Into<RenderOptions>) -> String {
+        let mut writer = Vec::new();
+        render_opts(g, &mut writer, options).unwrap();
+        String::from_utf8(writer).unwrap()
+    }
+
+    #[test]
+    fn from_option_list() {
+        let options = [RenderOption::NoEdgeLabels, RenderOption::NoNodeLabels];
+        assert_eq!(
+            RenderOptions::from(&options),
+            RenderOptions::default()
+                .with_edge_labels(false)
+                .with_node_labels(false)
+        );
+        assert_eq!(
+            render_to_string(&graph(), &options),
+            r#"digraph g {
+    subgraph cluster_0 {
+        b;
+    }
+    a[style="filled", color="red"];
+    b;
+    a -> b;
+}
+"#
+        );
+    }
+
+    #[test]
+    fn layout() {
+        let options = RenderOptions::default()
+            .with_indent("\t")
+            .with_line_ending(LineEnding::CrLf)
+            .with_attr_order(AttrOrder::Sorted)
+            .with_font_size(9.0);
+        assert_eq!(
+            render_to_string(&graph(), options),
+            "digraph g {\r\n\
+             \tgraph[fontsize=9];\r\n\
+             \tnode[fontsize=9];\r\n\
+             \tedge[fontsize=9];\r\n\
+             \tsubgraph cluster_0 {\r\n\
+             \t\tlabel=\"\";\r\n\
+             \t\tb;\r\n\
+             \t}\r\n\
+             \ta[color=\"red\", label=\"a\", style=\"filled\"];\r\n\
+             \tb[label=\"b\"];\r\n\
+             \ta -> b[label=\"x\"];\r\n\
+             }\r\n"
+        );
+    }
+
+    #[test]
+    fn compact() {
+        let options = RenderOptions::default().with_compact(true);
+        assert_eq!(
+            render_to_string(&graph(), &options),
+            "digraph g { subgraph cluster_0 { label=\"\"; b; } \
+             a[label=\"a\",style=\"filled\",color=\"red\"]; b[label=\"b\"]; a -> b[label=\"x\"]; }\n"
+        );
+    }
+
+    #[test]
+    fn dark_theme() {
+        let options = [RenderOption::DarkTheme, RenderOption::NoEdgeLabels];
+        assert_eq!(
+            render_to_string(&graph(), &options),
+            r#"digraph g {
+    graph[bgcolor="black", fontcolor="white"];
+    node[color="white", fontcolor="white"];
+    edge[color="white", fontcolor="white"];
+    subgraph cluster_0 {
+        label="";
+        b;
+    }
+    a[label="a", style="filled", color="red"];
+    b[label="b"];
+    a -> b;
+}
+"#
+        );
+    }
+
+    #[test]
+    fn custom_theme() {
+        let mut g = graph();
+        let inner = g.add_subgraph(Subgraph::new(Id::new("cluster_inner").ok()));
+        g.subgraph_mut(crate::graph::SubgraphIndex(0))
+            .subgraphs
+            .push(inner);
+        let theme = Theme::new()
+            .with_background(Color::rgb(0xfd, 0xf6, 0xe3))
+            .with_foreground(Color::rgb(0x58, 0x6e, 0x75))
+            .with_fontname("Inter")
+            .with_node_fills([
+                Color::named("white"),
+                Color::named("gray90"),
+                Color::named("gray80"),
+            ])
+            .with_edge_color(Color::named("gray50"))
+            .with_cluster_fill(Color::rgb(0xee, 0xe8, 0xd5));
+        let options = RenderOptions::default()
+            .with_theme(theme)
+            .with_fontname("mono")
+            .with_node_labels(false)
+            .with_edge_labels(false);
+        assert_eq!(
+            render_to_string(&g, options),
+            r##"digraph g {
+    graph[bgcolor="#fdf6e3", fontcolor="#586e75", fontname="mono"];
+    node[color="#586e75", fontcolor="#586e75", fontname="mono", style="filled", fillcolor="white"];
+    edge[color="gray50", fontcolor="#586e75", fontname="mono"];
+    subgraph cluster_0 {
+        style="filled";
+        fillcolor="#eee8d5";
+        node[fillcolor="gray90"];
+        subgraph cluster_inner {
+            style="filled";
+            fillcolor="#eee8d5";
+            node[fillcolor="gray80"];
+        }
+        b;
+    }
+    a[style="filled", color="red"];
+    b;
+    a -> b;
+}
+"##
+        );
+    }
+
+    #[test]
+    fn nested_node_fills() {
+        let theme = Theme::new().with_node_fills([
+            Color::named("white"),
+            Color::named("gray90"),
+            Color::named("gray80"),
+        ]);
+        let fills: Vec<_> = (1..=4)
+            .map(|depth| theme.cluster_node_attrs(depth).get("fillcolor").cloned())
+            .collect();
+        assert_eq!(
+            fills,
+            ["gray90", "gray80", "gray90", "gray80"]
+                .map(|fill| Some(Attr::FillColor(Color::named(fill))))
+        );
+
+        // With two colors, nodes in nested clusters keep the second one
+        // rather than going back to the fill of the nodes outside.
+        let theme = Theme::solarized_light();
+        let top = theme.node_attrs().get("fillcolor").cloned();
+        for depth in 1..=3 {
+            let fill = theme.cluster_node_attrs(depth).get("fillcolor").cloned();
+            assert!(fill.is_some() && fill != top, "{depth}: {fill:?}");
+        }
+    }
+
+    #[test]
+    fn theme_presets() {
+        for theme in [
+            Theme::light(),
+            Theme::dark(),
+            Theme::solarized_light(),
+            Theme::solarized_dark(),
+            Theme::high_contrast(),
+            Theme::print(),
+        ] {
+            let options = RenderOptions::default().with_theme(theme.clone());
+            let output = render_to_string(&graph(), options);
+            let mut lines = output.lines().skip(1);
+            for attrs in [theme.graph_attrs(), theme.node_attrs(), theme.edge_attrs()] {
+                let line = lines.next().unwrap();
+                for attr in &attrs {
+                    assert!(line.contains(&attr.to_string()), "{attr} not in {line}");
+                }
+            }
+        }
+    }
+}
+
+mod stylesheet {
+    use crate::graph::{DotGraph, Edge, Node, Subgraph};
+    use crate::stylesheet::{ElementKind, Selector, Stylesheet};
+    use crate::validate::{validate, Diagnostic};
+    use crate::{render, Attr, Attrs, Color, GraphKind, Id, Shape, Style, Text};
+
+    fn stylesheet() -> Stylesheet<'static> {
+        Stylesheet::new()
+            .with_rule(
+                Selector::class("error").with_class("fatal"),
+                [Attr::PenWidth(3.0)],
+            )
+            .with_rule(
+                Selector::node().with_class("error"),
+                [Attr::FillColor(Color::named("pink"))],
+            )
+            .with_rule(
+                Selector::class("error"),
+                [
+                    Attr::Color(Color::named("red")),
+                    Attr::FillColor(Color::named("orange")),
+                ],
+            )
+            .with_rule(
+                Selector::node(),
+                [Attr::Shape(Shape::Box), Attr::Color(Color::named("gray"))],
+            )
+            .with_rule(Selector::any(), [Attr::Tooltip(Text::label("any"))])
+    }
+
+    #[test]
+    fn resolve() {
+        let sheet = stylesheet();
+        assert_eq!(
+            sheet.resolve(ElementKind::Node, &["error".into(), "fatal".into()]),
+            Attrs::from([
+                Attr::Tooltip(Text::label("any")),
+                Attr::Shape(Shape::Box),
+                Attr::Color(Color::named("red")),
+                Attr::FillColor(Color::named("pink")),
+                Attr::PenWidth(3.0),
+            ])
+        );
+        assert_eq!(
+            sheet.resolve(ElementKind::Edge, &["error".into()]),
+            Attrs::from([
+                Attr::Tooltip(Text::label("any")),
+                Attr::Color(Color::named("red")),
+                Attr::FillColor(Color::named("orange")),
+            ])
+        );
+        assert_eq!(
+            sheet.resolve(ElementKind::Subgraph, &["warning".into()]),
+            Attrs::from([Attr::Tooltip(Text::label("any"))])
+        );
+
+        let selectors: Vec<_> = sheet.rules().map(|(s, _)| s.to_string()).collect();
+        assert_eq!(
+            selectors,
+            [".error.fatal", "node.error", ".error", "node", "*"]
+        );
+    }
+
+    #[test]
+    fn render_classes() {
+        let mut g = DotGraph::new(Id::new("g").ok(), GraphKind::Directed).with_stylesheet(
+            Stylesheet::new()
+                .with_rule(Selector::node(), [Attr::Shape(Shape::Box)])
+                .with_rule(Selector::class("hot"), [Attr::Color(Color::named("red"))])
+                .with_rule(
+                    Selector::subgraph().with_class("group"),
+                    [Attr::Style(Style::Filled.into())],
+                ),
+        );
+        let a = g.add_node(Node::new(Id::new("a").unwrap()).with_class("hot"));
+        let b = g.add_node(
+            Node::new(Id::new("b").unwrap())
+                .with_class("hot")
+                .with_attr(Attr::Shape(Shape::Circle)),
+        );
+        g.add_edge(Edge::new(a, b).with_class("hot"));
+        g.add_subgraph(
+            Subgraph::new(Id::new("cluster_0").ok())
+                .with_class("group")
+                .with_nodes([b]),
+        );
+
+        let mut writer = Vec::new();
+        render(&g, &mut writer).unwrap();
+        assert_eq!(
+            String::from_utf8(writer).unwrap(),
+            r#"digraph g {
+    subgraph cluster_0 {
+        style="filled";
+        label="";
+        b;
+    }
+    a[shape="box", color="red", label="a"];
+    b[shape="circle", color="red", label="b"];
+    a -> b[color="red"];
+}
+"#
+        );
+    }
+
+    #[test]
+    fn validate_rules() {
+        let g = DotGraph::new(Id::new("g").ok(), GraphKind::Directed).with_stylesheet(
+            Stylesheet::new()
+                .with_rule(Selector::edge().with_class("thin"), [Attr::PenWidth(-1.0)]),
+        );
+        assert_eq!(
+            validate(&g),
+            vec![Diagnostic::InvalidAttribute {
+                element: "the stylesheet rule edge.thin".to_owned(),
+                attr: "penwidth".to_owned(),
+                value: "-1".to_owned(),
+                reason: "is negative",
+            }]
+        );
+    }
+}
+
+mod legend {
+    use super::{edge, edge_with_arrows, LabelledGraph, UnlabelledNodes};
+    use crate::graph::{DotGraph, Node, Subgraph};
+    use crate::legend::legend_entries;
+    use crate::stylesheet::ElementKind;
+    use crate::{
+        render_opts, Arrow, ArrowVertex, Color, GraphKind, Id, LegendEntry, RenderOptions, Style,
+        Styles,
+    };
+
+    fn graph() -> LabelledGraph {
+        LabelledGraph::new(
+            "g",
+            UnlabelledNodes(3),
+            vec![
+                edge(0, 1, "", Style::Dashed, Some("red")),
+                edge(1, 2, "", Style::Dashed, Some("red")),
+                edge_with_arrows(
+                    2,
+                    0,
+                    "",
+                    Style::None,
+                    None,
+                    Arrow::default(),
+                    Arrow::from(ArrowVertex::inv()),
+                ),
+                edge(0, 2, "", Style::None, None),
+            ],
+            Some(vec![Style::Filled, Style::None, Style::Filled]),
+        )
+    }
+
+    #[test]
+    fn entries() {
+        let g = graph();
+        let entries = legend_entries(&g, &RenderOptions::default());
+        assert_eq!(
+            entries,
+            vec![
+                LegendEntry {
+                    kind: ElementKind::Node,
+                    classes: vec![],
+                    style: Style::Filled.into(),
+                    color: None,
+                    start_arrow: Arrow::default(),
+                    end_arrow: Arrow::default(),
+                },
+                LegendEntry {
+                    kind: ElementKind::Edge,
+                    classes: vec![],
+                    style: Style::Dashed.into(),
+                    color: Some(Color::named("red")),
+                    start_arrow: Arrow::default(),
+                    end_arrow: Arrow::default(),
+                },
+                LegendEntry {
+                    kind: ElementKind::Edge,
+                    classes: vec![],
+                    style: Styles::new(),
+                    color: None,
+                    start_arrow: Arrow::default(),
+                    end_arrow: Arrow::from(ArrowVertex::inv()),
+                },
+            ]
+        );
+        assert_eq!(entries[1].to_string(), "dashed, red");
+
+        let options = RenderOptions::default()
+            .with_edge_styles(false)
+            .with_edge_colors(false)
+            .with_arrows(false);
+        assert_eq!(legend_entries(&g, &options).len(), 1);
+    }
+
+    #[test]
+    fn start_arrows() {
+        let inv = || Arrow::from(ArrowVertex::inv());
+        let g = LabelledGraph::new(
+            "g",
+            UnlabelledNodes(2),
+            vec![
+                edge_with_arrows(0, 1, "", Style::None, None, Arrow::default(), inv()),
+                edge_with_arrows(
+                    1,
+                    0,
+                    "",
+                    Style::None,
+                    None,
+                    ArrowVertex::tee().into(),
+                    inv(),
+                ),
+            ],
+            None,
+        );
+        let entries = legend_entries(&g, &RenderOptions::default());
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].to_string(), "inv");
+        assert_eq!(entries[1].to_string(), "tail tee, inv");
+
+        let mut writer = Vec::new();
+        let options = RenderOptions::default()
+            .with_node_labels(false)
+            .with_legend(true);
+        render_opts(&g, &mut writer, options).unwrap();
+        let output = String::from_utf8(writer).unwrap();
+        assert!(
+            output.contains(
+                r#"legend_1_tail -> legend_1_head[label="tail tee, inv", arrowhead="inv", dir="both", arrowtail="tee"];"#
+            ),
+            "{output}"
+        );
+    }
+
+    #[test]
+    fn render() {
+        let mut writer = Vec::new();
+        let options = RenderOptions::default()
+            .with_node_labels(false)
+            .with_legend(true);
+        render_opts(&graph(), &mut writer, options).unwrap();
+        assert_eq!(
+            String::from_utf8(writer).unwrap(),
+            r#"digraph g {
+    N0[style="filled"];
+    N1;
+    N2[style="filled"];
+    N0 -> N1[style="dashed", color="red"];
+    N1 -> N2[style="dashed", color="red"];
+    N2 -> N0[arrowhead="inv"];
+    N0 -> N2;
+    subgraph cluster_legend {
+        label="Legend";
+        legend_0;
+        legend_1_tail;
+        legend_1_head;
+        legend_2_tail;
+        legend_2_head;
+        legend_1_tail -> legend_1_head[label="dashed, red", style="dashed", color="red"];
+        legend_2_tail -> legend_2_head[label="inv", arrowhead="inv"];
+    }
+    legend_0[label="filled", style="filled"];
+    legend_1_tail[label="", shape="point"];
+    legend_1_head[label="", shape="point"];
+    legend_2_tail[label="", shape="point"];
+    legend_2_head[label="", shape="point"];
+}
+"#
+        );
+    }
+
+    #[test]
+    fn ids_not_used_by_the_graph() {
+        let mut g = DotGraph::new(None, GraphKind::Directed);
+        let n = g.add_node(Node::new(Id::new("legend_0").unwrap()).with_class("error"));
+        g.add_subgraph(Subgraph::new(Id::new("cluster_legend").ok()).with_nodes([n]));
+        let mut writer = Vec::new();
+        render_opts(&g, &mut writer, RenderOptions::default().with_legend(true)).unwrap();
+        assert_eq!(
+            String::from_utf8(writer).unwrap(),
+            r#"digraph {
+    subgraph cluster_legend {
+        label="";
+        legend_0;
+    }
+    legend_0[label="legend_0"];
+    subgraph cluster_legend_1 {
+        label="Legend";
+        legend_0_1;
+    }
+    legend_0_1[label="error"];
+}
+"#
+        );
+    }
+}
+
+mod color {
+    use crate::color::ColorError;
+    use crate::graph::{DotGraph, Node};
+    use crate::render::RenderError;
+    use crate::{render, Attr, Color, GraphKind, Id};
+
+    #[test]
+    fn parse() {
+        assert_eq!("red".parse(), Ok(Color::named("red")));
+        assert_eq!("#ff8000".parse(), Ok(Color::rgb(255, 128, 0)));
+        assert_eq!("#FF800080".parse(), Ok(Color::rgba(255, 128, 0, 128)));
+        assert_eq!("0.5 1,0.25".parse(), Ok(Color::hsv(0.5, 1.0, 0.25)));
+        assert_eq!("/blues9/3".parse(), Ok(Color::brewer("blues9", 3)));
+        assert_eq!(
+            "red;0.25:blue".parse(),
+            Ok(Color::list([
+                (Color::named("red"), Some(0.25)),
+                (Color::named("blue"), None),
+            ]))
+        );
+
+        assert_eq!("".parse::<Color>(), Err(ColorError::Empty));
+        assert_eq!(
+            "#ff00".parse::<Color>(),
+            Err(ColorError::InvalidHex("#ff00".to_owned()))
+        );
+        assert_eq!(
+            "1 2".parse::<Color>(),
+            Err(ColorError::InvalidHsv("1 2".to_owned()))
+        );
+        assert_eq!(
+            "light blue".parse::<Color>(),
+            Err(ColorError::InvalidName("light blue".to_owned()))
+        );
+        assert_eq!(
+            "red;x:blue".parse::<Color>().unwrap_err().to_string(),
+            "Invalid color weight 'x'"
+        );
+    }
+
+    #[test]
+    fn display() {
+        for text in [
+            "red",
+            "#ff8000",
+            "#ff800080",
+            "0.5,1,0.25",
+            "/svg/coral",
+            "red;0.25:blue",
+        ] {
+            assert_eq!(text.parse::<Color>().unwrap().to_string(), text);
+        }
+        assert_eq!(
+            Attr::FillColor(Color::list([
+                (Color::named("red"), None),
+                (Color::rgb(0, 0, 255), None)
+            ]))
+            .to_string(),
+            r##"fillcolor="red:#0000ff""##
+        );
+        assert_eq!(
+            Attr::parse("color", crate::Text::esc("red")),
+            Attr::Color(Color::named("red"))
+        );
+    }
+
+    #[test]
+    fn check() {
+        assert_eq!(Color::named("gray50").check(), Ok(()));
+        assert_eq!(Color::brewer("set312", 12).check(), Ok(()));
+        assert!(Color::named("not-a-color").check().is_err());
+        assert!(Color::hsv(0.5, 1.5, 1.0).check().is_err());
+        assert!(Color::list([]).check().is_err());
+        assert!(Color::list(vec![(Color::named("red"), Some(1.0 / 3.0)); 3])
+            .check()
+            .is_ok());
+        assert_eq!(
+            Color::list([
+                (Color::named("red"), Some(0.75)),
+                (Color::named("blue"), Some(0.5)),
+            ])
+            .check(),
+            Err("has color weights that add up to more than 1")
+        );
+    }
+
+    #[test]
+    fn render_invalid() {
+        let mut g = DotGraph::new(Id::new("g").ok(), GraphKind::Directed);
+        g.add_node(
+            Node::new(Id::new("a").unwrap()).with_attr(Attr::FontColor(Color::list([
+                (Color::named("red"), None),
+                (Color::named("blue"), None),
+            ]))),
+        );
+
+        let mut writer = Vec::new();
+        let err = render(&g, &mut writer).unwrap_err();
+        assert!(matches!(
+            err,
+            RenderError::InvalidAttribute { element, reason: "cannot be a color list", .. }
+                if element == "node a"
+        ));
+    }
+}
+
+mod palette {
+    use super::{LabelledGraph, UnlabelledNodes};
+    use crate::palette::{Palette, Scale};
+    use crate::{render, Color, GraphWalk, Labeller};
+
+    #[test]
+    fn color_for() {
+        let palette = Palette::tableau10();
+        assert_eq!(palette.len(), 10);
+        assert_eq!(palette.color(12), palette.color(2));
+        assert_eq!(
+            palette.color_for("core"),
+            palette.color_for(&String::from("core"))
+        );
+        assert_eq!(palette.color_for(&7u32), palette.color_for(&7u32));
+        // Fixed so that a change to the hashing, which would recolor every
+        // graph, does not go unnoticed.
+        assert_eq!(palette.color_for("core"), palette.color(7));
+
+        let colors: Vec<_> = (0..4).map(|i| Palette::set2().color_for(&i)).collect();
+        assert!(colors.iter().all(|c| c.check().is_ok()));
+        assert_eq!(
+            Palette::paired().colors()[11],
+            Color::brewer("paired12", 12)
+        );
+    }
+
+    #[test]
+    fn scale() {
+        let scale = Scale::new([(0, 0, 0), (200, 100, 0)]).with_domain(10.0, 20.0);
+        assert_eq!(scale.color(10.0), Color::rgb(0, 0, 0));
+        assert_eq!(scale.color(15.0), Color::rgb(100, 50, 0));
+        assert_eq!(scale.color(25.0), Color::rgb(200, 100, 0));
+        assert_eq!(scale.color(f64::NAN), Color::rgb(0, 0, 0));
+        assert_eq!(Scale::new([(9, 9, 9)]).color(0.5), Color::rgb(9, 9, 9));
+
+        let diverging = Scale::red_blue().with_domain(-1.0, 10.0).with_midpoint(0.0);
+        assert_eq!(diverging.color(0.0), Color::rgb(0xf7, 0xf7, 0xf7));
+        assert_eq!(diverging.color(-1.0), Color::rgb(0xb2, 0x18, 0x2b));
+        assert_eq!(diverging.color(10.0), Color::rgb(0x21, 0x66, 0xac));
+        assert_eq!(Scale::viridis().color(0.5), Color::rgb(0x21, 0x91, 0x8c));
+    }
+
+    #[test]
+    fn node_color() {
+        struct Owners(LabelledGraph, Palette);
+
+        impl<'a> Labeller<'a> for Owners {
+            type Node = usize;
+            type Edge = &'a super::Edge;
+            type Subgraph = ();
+
+            fn graph_id(&'a self) -> crate::Id<'a> {
+                self.0.graph_id()
+            }
+
+            fn node_id(&'a self, n: &usize) -> crate::Id<'a> {
+                self.0.node_id(n)
+            }
+
+            fn node_color(&'a self, n: &usize) -> Option<Color<'a>> {
+                Some(self.1.color_for(&(n % 2)))
+            }
+        }
+
+        impl<'a> GraphWalk<'a> for Owners {
+            type Node = usize;
+            type Edge = &'a super::Edge;
+            type Subgraph = ();
+
+            fn nodes(&'a self) -> crate::Nodes<'a, usize> {
+                self.0.nodes()
+            }
+
+            fn edges(&'a self) -> crate::Edges<'a, &'a super::Edge> {
+                self.0.edges()
+            }
+
+            fn source(&'a self, e: &&'a super::Edge) -> usize {
+                self.0.source(e)
+            }
+
+            fn target(&'a self, e: &&'a super::Edge) -> usize {
+                self.0.target(e)
+            }
+        }
+
+        let g = Owners(
+            LabelledGraph::new("g", UnlabelledNodes(3), vec![], None),
+            Palette::set1(),
+        );
+        let mut writer = Vec::new();
+        render(&g, &mut writer).unwrap();
+        let even = Palette::set1().color_for(&0usize);
+        let odd = Palette::set1().color_for(&1usize);
+        assert_eq!(
+            String::from_utf8(writer).unwrap(),
+            format!(
+                r#"digraph g {{
+    N0[label="N0", color="{even}"];
+    N1[label="N1", color="{odd}"];
+    N2[label="N2", color="{even}"];
+}}
+"#
+            )
+        );
+    }
+}
+
+mod styles {
+    use crate::render::RenderError;
+    use crate::{
+        render, render_opts, Attr, GraphWalk, Id, Labeller, Nodes, RenderOptions, Style,
+        StyleError, Styles,
+    };
+
+    struct Styled(Vec<Styles>);
+
+    impl<'a> Labeller<'a> for Styled {
+        type Node = usize;
+        type Edge = (usize, usize);
+        type Subgraph = ();
+
+        fn graph_id(&'a self) -> Id<'a> {
+            Id::new("g").unwrap()
+        }
+
+        fn node_id(&'a self, n: &usize) -> Id<'a> {
+            Id::new(format!("N{n}")).unwrap()
+        }
+
+        fn node_style(&'a self, n: &usize) -> Styles {
+            self.0[*n].clone()
+        }
+    }
+
+    impl<'a> GraphWalk<'a> for Styled {
+        type Node = usize;
+        type Edge = (usize, usize);
+        type Subgraph = ();
+
+        fn nodes(&'a self) -> Nodes<'a, usize> {
+            (0..self.0.len()).collect()
+        }
+
+        fn edges(&'a self) -> crate::Edges<'a, (usize, usize)> {
+            Vec::new().into()
+        }
+
+        fn source(&'a self, e: &(usize, usize)) -> usize {
+            e.0
+        }
+
+        fn target(&'a self, e: &(usize, usize)) -> usize {
+            e.1
+        }
+    }
+
+    #[test]
+    fn parse() {
+        let styles: Styles = "filled, rounded,dashed ,setlinewidth( 2.5)"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            styles,
+            Styles::from([Style::Filled, Style::Rounded, Style::Dashed]).with_line_width(2.5)
+        );
+        assert_eq!(
+            styles.to_string(),
+            "filled,rounded,dashed,setlinewidth(2.5)"
+        );
+        assert_eq!("".parse(), Ok(Styles::new()));
+        assert_eq!(
+            "bold,sparkly".parse::<Styles>(),
+            Err(StyleError::UnknownStyle("sparkly".to_owned()))
+        );
+        assert_eq!(
+            "setlinewidth(1,2)"
+                .parse::<Styles>()
+                .unwrap_err()
+                .to_string(),
+            "Invalid line width style 'setlinewidth(1,2)'"
+        );
+        assert_eq!(
+            Attr::parse("style", crate::Text::esc("bold,filled")),
+            Attr::Style(Styles::from([Style::Bold, Style::Filled]))
+        );
+    }
+
+    #[test]
+    fn set() {
+        let mut styles = Styles::from(Style::None);
+        assert!(styles.is_empty());
+        assert!(styles.insert(Style::Dotted));
+        assert!(!styles.insert(Style::Dotted));
+        assert!(!styles.insert(Style::None));
+        styles.insert(Style::Bold);
+        assert!(styles.contains(Style::Bold));
+        assert_eq!(
+            styles.iter().collect::<Vec<_>>(),
+            [Style::Dotted, Style::Bold]
+        );
+        assert_eq!(styles.line_width(), None);
+    }
+
+    #[test]
+    fn render_styles() {
+        let g = Styled(vec![
+            Styles::from([Style::Filled, Style::Rounded]),
+            Styles::new().with(Style::Dashed).with_line_width(2.0),
+            Style::None.into(),
+        ]);
+        let mut writer = Vec::new();
+        render(&g, &mut writer).unwrap();
+        assert_eq!(
+            String::from_utf8(writer).unwrap(),
+            r#"digraph g {
+    N0[label="N0", style="filled,rounded"];
+    N1[label="N1", style="dashed,setlinewidth(2)"];
+    N2[label="N2"];
+}
+"#
+        );
+
+        let mut writer = Vec::new();
+        render_opts(
+            &g,
+            &mut writer,
+            RenderOptions::default().with_node_styles(false),
+        )
+        .unwrap();
+        assert!(!String::from_utf8(writer).unwrap().contains("style"));
+
+        let g = Styled(vec![Styles::new().with_line_width(-1.0)]);
+        let err = render(&g, &mut Vec::new()).unwrap_err();
+        assert!(matches!(
+            err,
+            RenderError::InvalidAttribute {
+                reason: "has a negative line width",
+                ..
+            }
+        ));
+    }
+}
+
+mod shape {
+    use crate::shape::{Polygon, ShapeError};
+    use crate::{render, Attr, GraphWalk, Id, Labeller, Nodes, Shape, Subgraphs, Text};
+
+    /// Nodes with the given shapes, all in one subgraph of boxes.
+    struct Shaped(Vec<Shape>);
+
+    impl<'a> Labeller<'a> for Shaped {
+        type Node = usize;
+        type Edge = (usize, usize);
+        type Subgraph = ();
+
+        fn graph_id(&'a self) -> Id<'a> {
+            Id::new("g").unwrap()
+        }
+
+        fn node_id(&'a self, n: &usize) -> Id<'a> {
+            Id::new(format!("N{n}")).unwrap()
+        }
+
+        fn node_shape(&'a self, n: &usize) -> Option<Shape> {
+            Some(self.0[*n].clone())
+        }
+
+        fn subgraph_id(&'a self, _s: &()) -> Option<Id<'a>> {
+            Id::new("cluster_0").ok()
+        }
+
+        fn subgraph_shape(&'a self, _s: &()) -> Option<Shape> {
+            Some(Shape::Box)
+        }
+    }
+
+    impl<'a> GraphWalk<'a> for Shaped {
+        type Node = usize;
+        type Edge = (usize, usize);
+        type Subgraph = ();
+
+        fn nodes(&'a self) -> Nodes<'a, usize> {
+            (0..self.0.len()).collect()
+        }
+
+        fn edges(&'a self) -> crate::Edges<'a, (usize, usize)> {
+            Vec::new().into()
+        }
+
+        fn source(&'a self, e: &(usize, usize)) -> usize {
+            e.0
+        }
+
+        fn target(&'a self, e: &(usize, usize)) -> usize {
+            e.1
+        }
+
+        fn subgraphs(&'a self) -> Subgraphs<'a, ()> {
+            vec![()].into()
+        }
+
+        fn subgraph_nodes(&'a self, _s: &()) -> Nodes<'a, usize> {
+            self.nodes()
+        }
+    }
+
+    #[test]
+    fn names() {
+        for name in [
+            "box",
+            "Mrecord",
+            "doublecircle",
+            "plain",
+            "none",
+            "cylinder",
+        ] {
+            assert_eq!(name.parse::<Shape>().unwrap().name(), name);
+        }
+        assert_eq!("polygon".parse(), Ok(Shape::Polygon(Polygon::default())));
+        assert_eq!(
+            "rectange".parse::<Shape>(),
+            Err(ShapeError::Unknown("rectange".to_owned()))
+        );
+        assert_eq!(
+            Attr::parse("shape", Text::esc("rectange")),
+            Attr::Custom(Id::new("shape").unwrap(), Text::esc("rectange"))
+        );
+        assert_eq!(
+            Attr::Shape(Shape::MRecord).to_string(),
+            r#"shape="Mrecord""#
+        );
+        assert!(Shape::Record.is_record() && !Shape::Box.is_record());
+    }
+
+    #[test]
+    fn render_shapes() {
+        let g = Shaped(vec![
+            Shape::MSquare,
+            Polygon::new(6)
+                .with_skew(0.5)
+                .with_distortion(-0.25)
+                .with_peripheries(2)
+                .into(),
+        ]);
+        let mut writer = Vec::new();
+        render(&g, &mut writer).unwrap();
+        assert_eq!(
+            String::from_utf8(writer).unwrap(),
+            r#"digraph g {
+    subgraph cluster_0 {
+        label="";
+        node[shape="box"];
+        N0;
+        N1;
+    }
+    N0[label="N0", shape="Msquare"];
+    N1[label="N1", shape="polygon", sides=6, skew=0.5, distortion=-0.25, peripheries=2];
+}
+"#
+        );
+
+        let g = Shaped(vec![Polygon::new(2).into()]);
+        let err = render(&g, &mut Vec::new()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            r#"Attribute shape="polygon" of node N0 has fewer than three sides"#
+        );
+    }
+}
+
+mod record {
+    use crate::graph::{DotGraph, Edge, Node};
+    use crate::record::{Field, Record};
+    use crate::validate::{validate, Diagnostic};
+    use crate::{render, Attr, GraphKind, Id, Shape, Text};
+
+    #[test]
+    fn fields() {
+        let record = Record::vertical()
+            .with_field("a")
+            .with_record(Record::horizontal().with_field("b").with_field("c"))
+            .with_record(Record::vertical().with_field("d"));
+        assert_eq!(record.to_string(), "{a|{b|c}|{{d}}}");
+        assert_eq!(
+            Record::horizontal()
+                .with_field("{x | y}")
+                .with_field("  two  spaces\\")
+                .to_string(),
+            r"\{x \| y\}|\ \ two\ \ spaces\\"
+        );
+        assert_eq!(
+            Text::from(Record::horizontal().with_field("say \"hi\"")).to_escaped_string(),
+            r#""say \"hi\"""#
+        );
+
+        let port = Id::new("p").unwrap();
+        let record =
+            Record::horizontal().with_record(Record::vertical().with_port(port.clone(), "x"));
+        assert_eq!(record.ports(), [&port]);
+        assert_eq!(record.port("q"), None);
+        assert!(matches!(&record.fields()[0], Field::Record(inner) if inner.fields().len() == 1));
+    }
+
+    #[test]
+    fn ports() {
+        let head = Id::new("head").unwrap();
+        let next = Id::new("next").unwrap();
+        let list = Record::horizontal()
+            .with_port(head.clone(), "head")
+            .with_field("len: usize");
+        let node = Record::vertical()
+            .with_field("value: i32")
+            .with_port(next.clone(), "next");
+
+        let mut g = DotGraph::new(Id::new("g").ok(), GraphKind::Directed);
+        let a = g.add_node(Node::new(Id::new("list").unwrap()).with_record(list));
+        let b = g.add_node(
+            Node::new(Id::new("item").unwrap())
+                .with_attr(Attr::Shape(Shape::MRecord))
+                .with_record(node),
+        );
+        g.add_edge(Edge::new(a, b).with_source_port(Some(head), None));
+        g.add_edge(Edge::new(b, b).with_source_port(Some(next.clone()), None));
+        assert_eq!(validate(&g), vec![]);
+
+        let mut writer = Vec::new();
+        render(&g, &mut writer).unwrap();
+        assert_eq!(
+            String::from_utf8(writer).unwrap(),
+            r#"digraph g {
+    list[label="<head> head|len: usize", shape="record"];
+    item[label="{value: i32|<next> next}", shape="Mrecord"];
+    list:head -> item;
+    item:next -> item;
+}
+"#
+        );
+
+        g.add_edge(Edge::new(a, b).with_target_port(Some(Id::new("prev").unwrap()), None));
+        assert_eq!(
+            validate(&g),
+            vec![Diagnostic::UndefinedPort {
+                edge: "list -> item".to_owned(),
+                node: Id::new("item").unwrap(),
+                port: Id::new("prev").unwrap(),
+            }]
+        );
+    }
+}
+
+mod html {
+    use crate::graph::{DotGraph, Edge, Node};
+    use crate::html::{Align, Cell, Font, HtmlLabel, HtmlText, Image, Row, Table};
+    use crate::validate::validate;
+    use crate::{render, Color, GraphKind, Id, Text};
+
+    #[test]
+    fn text() {
+        let text = HtmlText::new()
+            .with_bold("Vec<T>")
+            .with_break(Some(Align::Left))
+            .with_font(
+                Font::new()
+                    .with_color(Color::rgb(0xff, 0, 0))
+                    .with_point_size(9.0),
+                HtmlText::new().with_italic("\"a\" & b"),
+            )
+            .with_lines("x\ny", None);
+        assert_eq!(
+            text.to_string(),
+            concat!(
+                r#"<B>Vec&lt;T&gt;</B><BR ALIGN="LEFT"/>"#,
+                r##"<FONT COLOR="#ff0000" POINT-SIZE="9"><I>&quot;a&quot; &amp; b</I></FONT>"##,
+                "x<BR/>y<BR/>",
+            )
+        );
+        assert_eq!(
+            Text::from(HtmlLabel::from("<b>")),
+            Text::Html("&lt;b&gt;".into())
+        );
+    }
+
+    #[test]
+    fn well_formed() {
+        assert_eq!(
+            Table::new().to_string(),
+            "<TABLE><TR><TD></TD></TR></TABLE>"
+        );
+        // Rules are only written between rows and between cells.
+        let table = Table::new()
+            .with_rule()
+            .with_row(Row::new().with_rule())
+            .with_rule()
+            .with_row(
+                Row::new()
+                    .with_cell(Cell::new("a").with_colspan(0))
+                    .with_rule()
+                    .with_cell(Cell::image(Image::new("x\".png").with_scale("TRUE")))
+                    .with_rule(),
+            )
+            .with_rule()
+            .with_bold()
+            .with_font(Font::new().with_face("Helvetica"));
+        assert_eq!(
+            table.to_string(),
+            concat!(
+                r#"<B><FONT FACE="Helvetica"><TABLE><TR><TD></TD></TR><HR/>"#,
+                r#"<TR><TD COLSPAN="1">a</TD><VR/><TD><IMG SRC="x&quot;.png" SCALE="TRUE"/></TD></TR>"#,
+                r#"</TABLE></FONT></B>"#,
+            )
+        );
+    }
+
+    #[test]
+    fn ports() {
+        let table = Table::new().with_border(0).with_border(1).with_row(
+            Row::new()
+                .with_cell(Cell::new("in").with_port(Id::new("in").unwrap()))
+                .with_cell(
+                    Cell::new(
+                        Table::new().with_row(
+                            Row::new().with_cell(
+                                Cell::new("out")
+                                    .with_port(Id::new("out").unwrap())
+                                    .with_align_text()
+                                    .with_balign(Align::Right),
+                            ),
+                        ),
+                    )
+                    .with_bgcolor(Color::named("lightgrey")),
+                ),
+        );
+
+        let mut g = DotGraph::new(Id::new("g").ok(), GraphKind::Directed);
+        let a = g.add_node(
+            Node::new(Id::new("a").unwrap()).with_label(HtmlLabel::from(table).into_owned().into()),
+        );
+        g.add_edge(Edge::new(a, a).with_source_port(Id::new("out").ok(), None));
+        g.add_edge(Edge::new(a, a).with_target_port(Id::new("in").ok(), None));
+        assert_eq!(validate(&g), vec![]);
+
+        let mut writer = Vec::new();
+        render(&g, &mut writer).unwrap();
+        assert_eq!(
+            String::from_utf8(writer).unwrap(),
+            concat!(
+                "digraph g {\n",
+                r#"    a[label=<<TABLE BORDER="1"><TR><TD PORT="in">in</TD>"#,
+                r#"<TD BGCOLOR="lightgrey"><TABLE><TR><TD PORT="out" ALIGN="TEXT" BALIGN="RIGHT">out</TD></TR></TABLE></TD>"#,
+                "</TR></TABLE>>];\n",
+                "    a:out -> a;\n",
+                "    a -> a:in;\n",
+                "}\n",
+            )
+        );
+    }
+}
+
+mod html_check {
+    use crate::graph::{DotGraph, Edge, Node};
+    use crate::html::{check, Cell, HtmlErrorKind, Row, Table};
+    use crate::validate::{validate, Diagnostic};
+    use crate::{render, render_opts, Attr, GraphKind, Id, RenderError, RenderOptions, Text};
+
+    #[test]
+    fn valid() {
+        for html in [
+            "",
+            "plain &amp; simple",
+            "<b>bold</b><br align='left'/><FONT POINT-SIZE=\"8\"><sub>x</sub></FONT><BR></BR>",
+            "<!-- a comment --><i><table><tr><td><img src=\"a.png\"/></td></tr></table></i>",
+            "<TABLE>\n  <TR><TD>a</TD><VR/><TD><TABLE><TR><TD>b</TD></TR></TABLE></TD></TR>\n  <HR/>\n  <TR><TD/></TR>\n</TABLE>",
+        ] {
+            assert_eq!(check(html), Ok(vec![]), "{html}");
+        }
+
+        let table = Table::new()
+            .with_port(Id::new("t").unwrap())
+            .with_rule()
+            .with_row(Row::new().with_rule())
+            .with_row(Row::new().with_cell(Cell::new("a").with_port(Id::new("a").unwrap())));
+        assert_eq!(
+            check(&table.to_string()),
+            Ok(vec!["t".to_owned(), "a".to_owned()])
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let cases = [
+            ("<div>a</div>", 1, 1, "unknown tag <div>"),
+            ("<B color=\"red\">a</B>", 1, 4, "<B> has no attribute color"),
+            (
+                "<TD PORT=\"a\" port=\"b\">",
+                1,
+                14,
+                "attribute PORT of <TD> is given more than once",
+            ),
+            ("<FONT COLOR=red>", 1, 13, "attribute values must be quoted"),
+            ("a < b", 1, 3, "expected a tag name after '<'"),
+            ("<TD>a</TD>", 1, 1, "<TD> is not allowed outside of a table"),
+            (
+                "<TABLE><TR>a</TR></TABLE>",
+                1,
+                12,
+                "text is not allowed in <TR>",
+            ),
+            (
+                "<TABLE><TR><TD>a</TD></TR></TABLE>b",
+                1,
+                35,
+                "a table or image must be the only content of its label or cell",
+            ),
+            (
+                "<SUB><TABLE><TR><TD>a</TD></TR></TABLE></SUB>",
+                1,
+                6,
+                "<TABLE> is not allowed in <SUB>",
+            ),
+            ("<B><I>a</B></I>", 1, 8, "expected </I>, found </B>"),
+            ("a</B>", 1, 2, "unexpected </B>"),
+            ("<TABLE>\n<TR>\n<TD>a", 3, 1, "<TD> is not closed"),
+            ("<TABLE></TABLE>", 1, 1, "<TABLE> must not be empty"),
+            (
+                "<TABLE><TR><TD>a</TD></TR><HR/></TABLE>",
+                1,
+                27,
+                "<HR> is not allowed in <TABLE>",
+            ),
+            (
+                "<TABLE><TR><VR/><TD>a</TD></TR></TABLE>",
+                1,
+                12,
+                "<VR> is not allowed in <TR>",
+            ),
+            ("a<BR>b</BR>", 1, 6, "<BR> must be empty"),
+            ("<!-- a", 1, 1, "unterminated comment"),
+        ];
+        for (html, line, column, message) in cases {
+            let err = check(html).unwrap_err();
+            assert_eq!(
+                (err.line, err.column, err.to_string()),
+                (line, column, format!("{line}:{column}: {message}")),
+                "{html}"
+            );
+        }
+        assert_eq!(
+            check("<TABLE><TR><TD><IMG SRC=\"a\"/>b</TD></TR></TABLE>")
+                .unwrap_err()
+                .kind,
+            HtmlErrorKind::MixedContent
+        );
+    }
+
+    #[test]
+    fn validate_and_render() {
+        let mut g = DotGraph::new(Id::new("g").ok(), GraphKind::Directed);
+        let a = g.add_node(Node::new(Id::new("a").unwrap()).with_label(Text::html(
+            "<TABLE><TR><TD PORT=\"out\">a</TD></TR></TABLE>",
+        )));
+        let b = g.add_node(
+            Node::new(Id::new("b").unwrap()).with_attr(Attr::XLabel(Text::html("<TR>b</TR>"))),
+        );
+        g.add_edge(Edge::new(a, b).with_source_port(Id::new("out").ok(), None));
+        assert_eq!(
+            validate(&g)
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            vec![
+                "Attribute xlabel of node b is not valid HTML: \
+                 1:1: <TR> is not allowed outside of a table"
+            ]
+        );
+
+        let mut writer = Vec::new();
+        render(&g, &mut writer).unwrap();
+        let err = render_opts(
+            &g,
+            &mut Vec::new(),
+            RenderOptions::default().with_html_check(true),
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            RenderError::InvalidHtml { element, attr, .. } if element == "node b" && attr == "xlabel"
+        ));
+
+        g.add_edge(
+            Edge::new(a, b)
+                .with_source_port(Id::new("in").ok(), None)
+                .with_label(Text::html("<TABLE></TABLE>")),
+        );
+        let diagnostics = validate(&g);
+        assert_eq!(diagnostics.len(), 3);
+        assert!(matches!(
+            &diagnostics[1],
+            Diagnostic::UndefinedPort { port, .. } if port[..] == *"in"
+        ));
+        assert!(matches!(
+            &diagnostics[2],
+            Diagnostic::InvalidHtml { element, error, .. }
+                if element == "edge a -> b" && error.kind == HtmlErrorKind::Empty("TABLE")
+        ));
+    }
+}
+
+mod mermaid {
+    use super::DefaultStyleGraph;
+    use crate::attrs::Dir;
+    use crate::graph::{DotGraph, Edge, Node, Subgraph};
+    use crate::render::RenderOption;
+    use crate::{
+        mermaid, Arrow, ArrowVertex, Attr, Attrs, Color, GraphKind, Id, RankDir, Shape, Style, Text,
+    };
+
+    #[test]
+    fn flowchart() {
+        let mut g = DotGraph::new(Id::new("g").ok(), GraphKind::Directed);
+        let end = g.add_node(Node::new(Id::new("end").unwrap()));
+        let ab = g
+            .add_node(Node::new(Id::quoted("a b").unwrap()).with_label(Text::esc(r"\N\nsecond\l")));
+        g.add_node(Node::new(Id::new("a_b").unwrap()));
+        let c = g.add_node(
+            Node::new(Id::new("c").unwrap())
+                .with_label(Text::html(
+                    "<TABLE><TR><TD>x &amp; y</TD><TD>z</TD></TR><TR><TD>\"q\"</TD></TR></TABLE>",
+                ))
+                .with_attr(Attr::Shape(Shape::Cylinder)),
+        );
+        let d = g.add_node(
+            Node::new(Id::new("d").unwrap())
+                .with_attr(Attr::Style(Style::Filled.into()))
+                .with_attr(Attr::Color(Color::rgb(0xff, 0, 0))),
+        );
+        let e = g.add_node(Node::new(Id::new("e").unwrap()));
+
+        let inner = g.add_subgraph(Subgraph::new(Id::new("inner").ok()).with_nodes([e]));
+        g.add_subgraph(
+            Subgraph::new(Id::new("cluster_1").ok())
+                .with_label(Text::label("Group #1"))
+                .with_attr(Attr::Color(Color::named("blue")))
+                .with_node_defaults(Attrs::from([Attr::Shape(Shape::Hexagon)]))
+                .with_nodes([d])
+                .with_subgraphs([inner]),
+        );
+
+        g.add_edge(
+            Edge::new(end, ab)
+                .with_label(Text::label("x|y"))
+                .with_attr(Attr::ArrowHead(ArrowVertex::dot().into())),
+        );
+        g.add_edge(
+            Edge::new(ab, c)
+                .with_attr(Attr::Style(Style::Bold.into()))
+                .with_attr(Attr::Dir(Dir::Both))
+                .with_attr(Attr::ArrowHead(ArrowVertex::tee().into()))
+                .with_attr(Attr::ArrowTail(ArrowVertex::tee().into())),
+        );
+        g.add_edge(Edge::new(c, d).with_attr(Attr::Dir(Dir::Back)));
+        g.add_edge(
+            Edge::new(d, e)
+                .with_attr(Attr::Color(Color::brewer("set19", 1)))
+                .with_attr(Attr::PenWidth(2.0)),
+        );
+        g.add_edge(Edge::new(e, end).with_attr(Attr::ArrowHead(Arrow::none())));
+
+        let mut writer = Vec::new();
+        mermaid::render(&g, &mut writer).unwrap();
+        assert_eq!(
+            String::from_utf8(writer).unwrap(),
+            r#"flowchart TD
+    subgraph cluster_1 ["Group #35;1"]
+        e{{"e"}}
+        d{{"d"}}
+    end
+    end_["end"]
+    a_b["a b<br>second"]
+    a_b_1["a_b"]
+    c[("x #amp; y z<br>#quot;q#quot;")]
+    end_ --o|x#124;y| a_b
+    a_b x==x c
+    d --> c
+    d --> e
+    e --- end_
+    style cluster_1 stroke:blue
+    style d fill:#ff0000,stroke:#ff0000
+    linkStyle 3 stroke-width:2px
+"#
+        );
+    }
+
+    #[test]
+    fn options() {
+        let mut g = DotGraph::new(Id::new("g").ok(), GraphKind::Undirected)
+            .with_attr(Attr::Label(Text::label("A \"title\"")))
+            .with_attr(Attr::RankDir(RankDir::BottomTop));
+        let a = g.add_node(
+            Node::new(Id::new("a").unwrap())
+                .with_label(Text::label("A"))
+                .with_attr(Attr::Shape(Shape::Box))
+                .with_attr(Attr::Style(Style::Rounded.into()))
+                .with_attr(Attr::FontColor(Color::hsv(0.5, 1.0, 1.0))),
+        );
+        g.add_edge(Edge::new(a, a).with_label(Text::label("loop")));
+
+        let mut writer = Vec::new();
+        mermaid::render_opts(
+            &g,
+            &mut writer,
+            &[RenderOption::NoNodeLabels, RenderOption::NoEdgeLabels],
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(writer).unwrap(),
+            r#"---
+title: "A \"title\""
+---
+flowchart BT
+    a("a")
+    a --- a
+    style a color:#00ffff
+"#
+        );
+    }
+
+    #[test]
+    fn subgraph_only_nodes() {
+        let g = DefaultStyleGraph::new("g", GraphKind::Directed, 1, vec![(0, 0)], vec![vec![1]])
+            .with_subgraph_edges(vec![vec![0]]);
+        let mut writer = Vec::new();
+        mermaid::render(&g, &mut writer).unwrap();
+        assert_eq!(
+            String::from_utf8(writer).unwrap(),
+            r#"flowchart TD
+    subgraph cluster_0 [" "]
+        N1["N1"]
+        N0 --> N0
+    end
+    N0["N0"]
+"#
+        );
+    }
+}
+
+mod plantuml {
+    use super::DefaultStyleGraph;
+    use crate::attrs::Dir;
+    use crate::graph::{DotGraph, Edge, Node, Subgraph};
+    use crate::render::RenderOption;
+    use crate::{
+        plantuml, Arrow, ArrowVertex, Attr, Attrs, Color, GraphKind, Id, RankDir, Shape, Style,
+        Text,
+    };
+
+    #[test]
+    fn diagram() {
+        let mut g = DotGraph::new(Id::new("g").ok(), GraphKind::Directed);
+        let note = g.add_node(Node::new(Id::new("note").unwrap()));
+        let ab = g.add_node(
+            Node::new(Id::quoted("a b").unwrap()).with_label(Text::esc(r#"\N\n"quoted"\\"#)),
+        );
+        g.add_node(Node::new(Id::new("a_b").unwrap()));
+        let c = g.add_node(
+            Node::new(Id::new("c").unwrap())
+                .with_label(Text::html("x &amp; y<BR/>z"))
+                .with_attr(Attr::Shape(Shape::Ellipse))
+                .with_attr(Attr::Url(Text::label("https://example.com/a b")))
+                .with_attr(Attr::Tooltip(Text::label("See {here}"))),
+        );
+        let d = g.add_node(
+            Node::new(Id::new("d").unwrap())
+                .with_attr(Attr::Style(Style::Filled.into()))
+                .with_attr(Attr::Color(Color::rgba(0xff, 0, 0, 0x80)))
+                .with_attr(Attr::FontColor(Color::named("white"))),
+        );
+        let e = g.add_node(Node::new(Id::new("e").unwrap()));
+
+        let de = g.add_edge(
+            Edge::new(d, e)
+                .with_attr(Attr::Color(Color::named("green")))
+                .with_attr(Attr::PenWidth(2.0)),
+        );
+        let inner = g.add_subgraph(Subgraph::new(Id::new("inner").ok()).with_nodes([e]));
+        g.add_subgraph(
+            Subgraph::new(Id::new("cluster_1").ok())
+                .with_label(Text::label("Group 1"))
+                .with_attr(Attr::Color(Color::named("blue")))
+                .with_attr(Attr::Style(Style::Dashed.into()))
+                .with_node_defaults(Attrs::from([Attr::Shape(Shape::Cylinder)]))
+                .with_nodes([d])
+                .with_subgraphs([inner])
+                .with_edges([de]),
+        );
+
+        g.add_edge(
+            Edge::new(note, ab)
+                .with_label(Text::label("uses"))
+                .with_attr(Attr::ArrowHead(ArrowVertex::diamond().into())),
+        );
+        g.add_edge(
+            Edge::new(ab, c)
+                .with_attr(Attr::Style(Style::Bold.into()))
+                .with_attr(Attr::Dir(Dir::Both))
+                .with_attr(Attr::ArrowHead(ArrowVertex::crow().into()))
+                .with_attr(Attr::ArrowTail(ArrowVertex::normal().into())),
+        );
+        g.add_edge(Edge::new(c, d).with_attr(Attr::Dir(Dir::Back)));
+        g.add_edge(Edge::new(e, note).with_attr(Attr::ArrowHead(Arrow::none())));
+
+        let mut writer = Vec::new();
+        plantuml::render(&g, &mut writer).unwrap();
+        assert_eq!(
+            String::from_utf8(writer).unwrap(),
+            r##"@startuml
+package "Group 1" as cluster_1 #line:blue;line.dashed {
+    database "e" as e
+    database "d" as d #ff0000;line:ff0000;text:white
+    d -[#green,thickness=2]-> e
+}
+rectangle "note" as note_
+rectangle "a b\n&#34;quoted&#34;\\" as a_b
+rectangle "a_b" as a_b_1
+usecase "x & y\nz" as c [[https://example.com/a%20b{See here}]]
+note_ --* a_b : uses
+a_b <-[bold]-} c
+c <-- d
+e -- note_
+@enduml
+"##
+        );
+    }
+
+    #[test]
+    fn options() {
+        let mut g = DotGraph::new(Id::new("g").ok(), GraphKind::Undirected)
+            .with_attr(Attr::Label(Text::label("Two\nlines")))
+            .with_attr(Attr::RankDir(RankDir::RightLeft));
+        let a = g.add_node(
+            Node::new(Id::new("a").unwrap())
+                .with_label(Text::label("A"))
+                .with_attr(Attr::Shape(Shape::Box3d))
+                .with_attr(Attr::Color(Color::brewer("blues9", 3))),
+        );
+        g.add_edge(
+            Edge::new(a, a)
+                .with_label(Text::label("loop"))
+                .with_attr(Attr::Style(Style::Dotted.into())),
+        );
+
+        let mut writer = Vec::new();
+        plantuml::render_opts(
+            &g,
+            &mut writer,
+            &[RenderOption::NoNodeLabels, RenderOption::NoEdgeLabels],
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(writer).unwrap(),
+            r#"@startuml
+left to right direction
+title Two\nlines
+node "a" as a
+a -[dotted]- a
+@enduml
+"#
+        );
+    }
+
+    #[test]
+    fn subgraph_only_nodes() {
+        let g = DefaultStyleGraph::new("g", GraphKind::Directed, 1, vec![(0, 0)], vec![vec![1]])
+            .with_subgraph_edges(vec![vec![0]]);
+        let mut writer = Vec::new();
+        plantuml::render(&g, &mut writer).unwrap();
+        assert_eq!(
+            String::from_utf8(writer).unwrap(),
+            r#"@startuml
+package " " as cluster_0 {
+    rectangle "N1" as N1
+    N0 --> N0
+}
+rectangle "N0" as N0
+@enduml
+"#
+        );
+    }
+
+    #[test]
+    fn short_hex_color() {
+        let mut g = DotGraph::new(Id::new("g").ok(), GraphKind::Directed);
+        g.add_node(
+            Node::new(Id::new("a").unwrap())
+                .with_attr(Attr::Style(Style::Filled.into()))
+                .with_attr(Attr::Color(Color::named("#abc"))),
+        );
+
+        let mut writer = Vec::new();
+        plantuml::render(&g, &mut writer).unwrap();
+        assert_eq!(
+            String::from_utf8(writer).unwrap(),
+            "@startuml\nrectangle \"a\" as a #abc;line:abc\n@enduml\n"
+        );
+    }
+}
+
+mod graphml {
+    use crate::attrs::Dir;
+    use crate::graph::{DotGraph, Edge, Node, Subgraph};
+    use crate::graphml::{self, GraphmlErrorKind};
+    use crate::render::AttrOrder;
+    use crate::{Attr, Attrs, Color, CompassPoint, GraphKind, Id, RenderOptions, Shape, Text};
+
+    #[test]
+    fn round_trip() {
+        let mut g = DotGraph::new(Id::new("g").ok(), GraphKind::Directed)
+            .with_attr(Attr::Label(Text::label("A & B")));
+        let a = g.add_node(
+            Node::new(Id::quoted("a b").unwrap()).with_label(Text::label("two\nlines \"q\"")),
+        );
+        let b = g.add_node(Node::new(Id::new("b").unwrap()).with_label(Text::html("<B>bold</B>")));
+        let c = g.add_node(Node::new(Id::new("c").unwrap()));
+        let ab = g.add_edge(
+            Edge::new(a, b)
+                .with_source_port(Id::new("out").ok(), Some(CompassPoint::South))
+                .with_target_port(None, Some(CompassPoint::North))
+                .with_attr(Attr::Color(Color::named("red"))),
+        );
+        g.add_edge(Edge::new(b, c));
+
+        let inner = g.add_subgraph(Subgraph::new(None).with_nodes([b]));
+        g.add_subgraph(
+            Subgraph::new(Id::new("cluster_1").ok())
+                .with_label(Text::label("Group"))
+                .with_node_defaults(Attrs::from([Attr::Shape(Shape::Box)]))
+                .with_nodes([a, b])
+                .with_edges([ab])
+                .with_subgraphs([inner]),
+        );
+
+        let mut writer = Vec::new();
+        graphml::render(&g, &mut writer).unwrap();
+        let xml = String::from_utf8(writer).unwrap();
+        assert_eq!(
+            xml,
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<graphml xmlns="http://graphml.graphdrawing.org/xmlns">
+    <key id="d0" for="graph" attr.name="label" attr.type="string"/>
+    <key id="d1" for="node" attr.name="shape" attr.type="string"/>
+    <key id="d2" for="node" attr.name="label" attr.type="string"/>
+    <key id="d3" for="edge" attr.name="color" attr.type="string"/>
+    <graph id="g" edgedefault="directed">
+        <data key="d0">A &amp; B</data>
+        <node id="cluster_1">
+            <graph id="cluster_1:" edgedefault="directed">
+                <data key="d0">Group</data>
+                <node id="subgraph">
+                    <graph id="subgraph:" edgedefault="directed">
+                        <node id="b"><port name="n"/><data key="d1">box</data><data key="d2">&lt;&lt;B&gt;bold&lt;/B&gt;&gt;</data></node>
+                    </graph>
+                </node>
+                <node id="a b"><port name="out:s"/><data key="d1">box</data><data key="d2">two\nlines &quot;q&quot;</data></node>
+                <edge source="a b" target="b" sourceport="out:s" targetport="n"><data key="d3">red</data></edge>
+            </graph>
+        </node>
+        <node id="c"><data key="d2">c</data></node>
+        <edge source="b" target="c"/>
+    </graph>
+</graphml>
+"#
+        );
+
+        // Attributes from defaults come first in the original, so compare
+        // the two sorted.
+        let parsed = graphml::parse(&xml).unwrap();
+        let sorted = RenderOptions::default().with_attr_order(AttrOrder::Sorted);
+        let mut original = Vec::new();
+        graphml::render_opts(&g, &mut original, &sorted).unwrap();
+        let mut writer = Vec::new();
+        graphml::render_opts(&parsed, &mut writer, &sorted).unwrap();
+        assert_eq!(writer, original);
+
+        let a = parsed.find_node("a b").unwrap();
+        assert_eq!(parsed.node(a).label, Some(Text::esc(r#"two\nlines "q""#)));
+        let b = parsed.find_node("b").unwrap();
+        assert_eq!(parsed.node(b).label, Some(Text::html("<B>bold</B>")));
+        let edge = parsed.edge(crate::graph::EdgeIndex(0));
+        assert_eq!(edge.source_port, Id::new("out").ok());
+        assert_eq!(edge.source_compass, Some(CompassPoint::South));
+        assert_eq!(edge.target_compass, Some(CompassPoint::North));
+        let cluster = parsed.find_subgraph("cluster_1").unwrap();
+        assert_eq!(parsed.subgraph(cluster).label, Some(Text::esc("Group")));
+        assert_eq!(parsed.subgraph(cluster).nodes.len(), 2);
+    }
+
+    #[test]
+    fn labels_like_html() {
+        let mut g = DotGraph::new(Id::new("g").ok(), GraphKind::Directed);
+        let r = g.add_node(
+            Node::new(Id::new("r").unwrap())
+                .with_label(Text::esc("<in>|<out>"))
+                .with_attr(Attr::Shape(Shape::Record)),
+        );
+        let b = g.add_node(Node::new(Id::new("b").unwrap()).with_label(Text::label("<b>")));
+        let q = g.add_node(Node::new(Id::new("q").unwrap()).with_label(Text::label("\"q\"")));
+        g.add_edge(Edge::new(r, b).with_source_port(Id::new("out").ok(), None));
+        g.add_edge(Edge::new(q, r).with_target_port(Id::new("in").ok(), None));
+
+        let mut writer = Vec::new();
+        graphml::render(&g, &mut writer).unwrap();
+        let xml = String::from_utf8(writer).unwrap();
+        assert!(xml.contains("&quot;&lt;in&gt;|&lt;out&gt;&quot;"), "{xml}");
+
+        let parsed = graphml::parse(&xml).unwrap();
+        let r = parsed.find_node("r").unwrap();
+        assert_eq!(parsed.node(r).label, Some(Text::esc("<in>|<out>")));
+        let b = parsed.find_node("b").unwrap();
+        assert_eq!(parsed.node(b).label, Some(Text::esc("<b>")));
+        let q = parsed.find_node("q").unwrap();
+        assert_eq!(parsed.node(q).label, Some(Text::esc("\"q\"")));
+
+        let mut original = Vec::new();
+        crate::render(&g, &mut original).unwrap();
+        let mut writer = Vec::new();
+        crate::render(&parsed, &mut writer).unwrap();
+        assert_eq!(
+            String::from_utf8(writer).unwrap(),
+            String::from_utf8(original).unwrap()
+        );
+    }
+
+    #[test]
+    fn shared_node() {
+        let g: DotGraph = "digraph g { subgraph cluster_a { x } subgraph cluster_b { x } x -> y }"
+            .parse()
+            .unwrap();
+
+        let mut writer = Vec::new();
+        graphml::render(&g, &mut writer).unwrap();
+        let xml = String::from_utf8(writer).unwrap();
+        assert_eq!(xml.matches("<node id=\"x\"").count(), 1, "{xml}");
+
+        let parsed = graphml::parse(&xml).unwrap();
+        assert_eq!(parsed.node_count(), 2);
+        assert_eq!(parsed.edge_count(), 1);
+        let a = parsed.find_subgraph("cluster_a").unwrap();
+        assert_eq!(parsed.subgraph(a).nodes, [parsed.find_node("x").unwrap()]);
+    }
+
+    #[test]
+    fn yed() {
+        let graph = graphml::parse(
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="no"?>
+<graphml xmlns="http://graphml.graphdrawing.org/xmlns" xmlns:y="http://www.yworks.com/xml/graphml">
+  <!-- Created by yEd -->
+  <key for="node" id="d0" yfiles.type="nodegraphics"/>
+  <key for="edge" id="d1" yfiles.type="edgegraphics"/>
+  <key for="node" id="d2" attr.name="color" attr.type="string"><default>blue</default></key>
+  <key for="edge" id="d3" attr.name="weight" attr.type="double"/>
+  <graph edgedefault="directed" id="G">
+    <node id="n0" yfiles.foldertype="group">
+      <data key="d0"><y:ProxyAutoBoundsNode><y:Realizers active="0"><y:GroupNode>
+        <y:NodeLabel alignment="right">Group &amp; co</y:NodeLabel>
+      </y:GroupNode></y:Realizers></y:ProxyAutoBoundsNode></data>
+      <graph edgedefault="directed" id="n0:">
+        <node id="n0::n0">
+          <data key="d0"><y:ShapeNode><y:NodeLabel>Inner</y:NodeLabel></y:ShapeNode></data>
+          <data key="d2"><![CDATA[#ff0000]]></data>
+        </node>
+      </graph>
+    </node>
+    <node id="n1"/>
+    <edge id="e0" source="n1" target="n0::n0" directed="false">
+      <data key="d1"><y:PolyLineEdge><y:EdgeLabel>link</y:EdgeLabel></y:PolyLineEdge></data>
+      <data key="d3">1.5</data>
+    </edge>
+  </graph>
+</graphml>
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(graph.id, Id::new("G").ok());
+        let group = graph.subgraph(graph.find_subgraph("n0").unwrap());
+        assert_eq!(group.label, Some(Text::label("Group & co")));
+        let inner = graph.find_node("n0::n0").unwrap();
+        assert_eq!(group.nodes, [inner]);
+        assert_eq!(graph.node(inner).label, Some(Text::label("Inner")));
+        assert_eq!(
+            graph.node(inner).attrs.get("color"),
+            Some(&Attr::Color(Color::rgb(0xff, 0, 0)))
+        );
+        let n1 = graph.node(graph.find_node("n1").unwrap());
+        assert_eq!(
+            n1.attrs.get("color"),
+            Some(&Attr::Color(Color::named("blue")))
+        );
+        let edge = graph.edge(crate::graph::EdgeIndex(0));
+        assert_eq!(edge.label, Some(Text::label("link")));
+        assert_eq!(edge.attrs.get("dir"), Some(&Attr::Dir(Dir::None)));
+        assert_eq!(edge.attrs.get("weight"), Some(&Attr::Weight(1.5)));
+    }
+
+    #[test]
+    fn errors() {
+        let error = |input: &str| {
+            let err = graphml::parse(input).unwrap_err();
+            (err.kind, err.line, err.column)
+        };
+        assert_eq!(
+            error("<graphml><graph></graphml>"),
+            (
+                GraphmlErrorKind::MismatchedTag {
+                    open: "graph".to_owned(),
+                    close: "graphml".to_owned()
+                },
+                1,
+                17
+            )
+        );
+        assert_eq!(
+            error("<graphml><graph>\n<node id=a/></graph></graphml>"),
+            (
+                GraphmlErrorKind::Syntax("attribute values must be quoted"),
+                2,
+                10
+            )
+        );
+        assert_eq!(
+            error("<graphml/>"),
+            (GraphmlErrorKind::Missing("graph"), 1, 1)
+        );
+        assert_eq!(
+            error("<graphml><graph><node/></graph></graphml>"),
+            (
+                GraphmlErrorKind::MissingAttribute {
+                    element: "node".to_owned(),
+                    attr: "id"
+                },
+                1,
+                17
+            )
+        );
+        assert_eq!(
+            error(r#"<graphml><graph><node id="a"/><node id="a"/></graph></graphml>"#),
+            (GraphmlErrorKind::DuplicateNode("a".to_owned()), 1, 31)
+        );
+        assert_eq!(
+            error(
+                r#"<graphml><graph><node id="a"><data key="k">x</data></node></graph></graphml>"#
+            ),
+            (GraphmlErrorKind::UnknownKey("k".to_owned()), 1, 30)
+        );
+        assert_eq!(
+            error(
+                r#"<graphml><graph><node id="s"><graph/></node><node id="a"/><edge source="a" target="s"/></graph></graphml>"#
+            ),
+            (GraphmlErrorKind::SubgraphEdge("s".to_owned()), 1, 59)
+        );
+        assert_eq!(
+            error(r#"<graphml><graph><hyperedge/></graph></graphml>"#),
+            (GraphmlErrorKind::Hyperedge, 1, 17)
+        );
+        assert_eq!(
+            error(r#"<graphml><graph edgedefault="both"/></graphml>"#),
+            (
+                GraphmlErrorKind::InvalidValue {
+                    attr: "edgedefault",
+                    value: "both".to_owned()
+                },
+                1,
+                10
+            )
+        );
+    }
+}
@@ -0,0 +1,247 @@
+//! Color schemes for rendered graphs.
+//!
+//! A [`Theme`] sets the colors and font of the graph, and the default colors
+//! of its nodes, edges and clusters, through the `graph[...]`, `node[...]`
+//! and `edge[...]` statements that [`render_opts`](crate::render_opts())
+//! emits, and the attributes of each cluster. Attributes given by the
+//! [`Labeller`](crate::Labeller) take precedence over those of the theme.
+//!
+//! There are presets such as [`Theme::light`] and [`Theme::dark`], and
+//! custom themes are built up from [`Theme::new`] with the `with_*` methods.
+
+use std::borrow::Cow;
+
+use crate::{Attr, Attrs, Color, Style, Text};
+
+/// The colors and font used to draw a graph.
+///
+/// Anything that is not set is left to the Graphviz defaults.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Theme {
+    background: Option<Color<'static>>,
+    foreground: Option<Color<'static>>,
+    fontname: Option<Text<'static>>,
+    node_fills: Vec<Color<'static>>,
+    edge_color: Option<Color<'static>>,
+    cluster_fill: Option<Color<'static>>,
+    cluster_border: Option<Color<'static>>,
+}
+
+impl Theme {
+    /// A theme that sets nothing.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Black on white, with nodes filled in pale colors.
+    pub fn light() -> Self {
+        Theme::new()
+            .with_background(Color::named("white"))
+            .with_foreground(Color::named("black"))
+            .with_edge_color(Color::named("gray30"))
+            .with_node_fills([
+                Color::rgb(0xf2, 0xf2, 0xf2),
+                Color::rgb(0xdb, 0xe9, 0xf6),
+                Color::rgb(0xdc, 0xef, 0xdc),
+                Color::rgb(0xfb, 0xe5, 0xd6),
+            ])
+            .with_cluster_fill(Color::rgb(0xfa, 0xfa, 0xfa))
+            .with_cluster_border(Color::named("gray60"))
+    }
+
+    /// White on black.
+    pub fn dark() -> Self {
+        Theme::new()
+            .with_background(Color::named("black"))
+            .with_foreground(Color::named("white"))
+            .with_edge_color(Color::named("white"))
+    }
+
+    /// The light variant of the [Solarized](https://ethanschoonover.com/solarized/)
+    /// palette.
+    pub fn solarized_light() -> Self {
+        Theme::new()
+            .with_background(Color::rgb(0xfd, 0xf6, 0xe3))
+            .with_foreground(Color::rgb(0x58, 0x6e, 0x75))
+            .with_edge_color(Color::rgb(0x93, 0xa1, 0xa1))
+            .with_node_fills([Color::rgb(0xee, 0xe8, 0xd5), Color::rgb(0xfd, 0xf6, 0xe3)])
+            .with_cluster_fill(Color::rgb(0xfd, 0xf6, 0xe3))
+            .with_cluster_border(Color::rgb(0x93, 0xa1, 0xa1))
+    }
+
+    /// The dark variant of the [Solarized](https://ethanschoonover.com/solarized/)
+    /// palette.
+    pub fn solarized_dark() -> Self {
+        Theme::new()
+            .with_background(Color::rgb(0x00, 0x2b, 0x36))
+            .with_foreground(Color::rgb(0x93, 0xa1, 0xa1))
+            .with_edge_color(Color::rgb(0x83, 0x94, 0x96))
+            .with_node_fills([Color::rgb(0x07, 0x36, 0x42), Color::rgb(0x00, 0x2b, 0x36)])
+            .with_cluster_fill(Color::rgb(0x00, 0x2b, 0x36))
+            .with_cluster_border(Color::rgb(0x58, 0x6e, 0x75))
+    }
+
+    /// White on black, with yellow edges and cyan cluster borders, for
+    /// readers with low vision.
+    pub fn high_contrast() -> Self {
+        Theme::new()
+            .with_background(Color::named("black"))
+            .with_foreground(Color::named("white"))
+            .with_edge_color(Color::named("yellow"))
+            .with_node_fills([Color::named("black")])
+            .with_cluster_fill(Color::named("black"))
+            .with_cluster_border(Color::named("cyan"))
+    }
+
+    /// Black on white, with nodes filled in shades of grey, for printing.
+    pub fn print() -> Self {
+        Theme::new()
+            .with_background(Color::named("white"))
+            .with_foreground(Color::named("black"))
+            .with_edge_color(Color::named("black"))
+            .with_node_fills([
+                Color::named("white"),
+                Color::named("gray90"),
+                Color::named("gray80"),
+                Color::named("gray70"),
+            ])
+            .with_cluster_fill(Color::named("gray95"))
+            .with_cluster_border(Color::named("black"))
+    }
+
+    /// Sets the background color of the canvas.
+    pub fn with_background(self, color: Color<'static>) -> Self {
+        Self {
+            background: Some(color),
+            ..self
+        }
+    }
+
+    /// Sets the color of text, and of the outlines of nodes.
+    pub fn with_foreground(self, color: Color<'static>) -> Self {
+        Self {
+            foreground: Some(color),
+            ..self
+        }
+    }
+
+    /// Sets the font of the graph, nodes and edges.
+    ///
+    /// [`RenderOptions::with_fontname`](crate::RenderOptions::with_fontname)
+    /// takes precedence over this.
+    pub fn with_fontname(self, fontname: impl Into<Cow<'static, str>>) -> Self {
+        Self {
+            fontname: Some(Text::label(fontname)),
+            ..self
+        }
+    }
+
+    /// Sets the colors that nodes are filled with.
+    ///
+    /// Nodes are filled with the first color. Nodes that are first listed
+    /// within a cluster are filled with the color for the nesting depth of
+    /// the cluster, cycling through the rest, so that nested clusters stand
+    /// out from each other.
+    pub fn with_node_fills(self, colors: impl IntoIterator<Item = Color<'static>>) -> Self {
+        Self {
+            node_fills: colors.into_iter().collect(),
+            ..self
+        }
+    }
+
+    /// Sets the color of edges.
+    pub fn with_edge_color(self, color: Color<'static>) -> Self {
+        Self {
+            edge_color: Some(color),
+            ..self
+        }
+    }
+
+    /// Sets the color that clusters are filled with.
+    pub fn with_cluster_fill(self, color: Color<'static>) -> Self {
+        Self {
+            cluster_fill: Some(color),
+            ..self
+        }
+    }
+
+    /// Sets the color of the borders of clusters.
+    pub fn with_cluster_border(self, color: Color<'static>) -> Self {
+        Self {
+            cluster_border: Some(color),
+            ..self
+        }
+    }
+
+    /// The attributes of the graph itself.
+    pub fn graph_attrs(&self) -> Attrs<'static> {
+        let mut attrs = Attrs::new();
+        if let Some(background) = &self.background {
+            attrs.set(Attr::BgColor(background.clone()));
+        }
+        if let Some(foreground) = &self.foreground {
+            attrs.set(Attr::FontColor(foreground.clone()));
+        }
+        if let Some(fontname) = &self.fontname {
+            attrs.set(Attr::FontName(fontname.clone()));
+        }
+        attrs
+    }
+
+    /// The default attributes of every node.
+    pub fn node_attrs(&self) -> Attrs<'static> {
+        let mut attrs = Attrs::new();
+        if let Some(foreground) = &self.foreground {
+            attrs.set(Attr::Color(foreground.clone()));
+            attrs.set(Attr::FontColor(foreground.clone()));
+        }
+        if let Some(fontname) = &self.fontname {
+            attrs.set(Attr::FontName(fontname.clone()));
+        }
+        if let Some(fill) = self.node_fills.first() {
+            attrs.set(Attr::Style(Style::Filled.into()));
+            attrs.set(Attr::FillColor(fill.clone()));
+        }
+        attrs
+    }
+
+    /// The default attributes of every edge.
+    pub fn edge_attrs(&self) -> Attrs<'static> {
+        let mut attrs = Attrs::new();
+        if let Some(color) = &self.edge_color {
+            attrs.set(Attr::Color(color.clone()));
+        }
+        if let Some(foreground) = &self.foreground {
+            attrs.set(Attr::FontColor(foreground.clone()));
+        }
+        if let Some(fontname) = &self.fontname {
+            attrs.set(Attr::FontName(fontname.clone()));
+        }
+        attrs
+    }
+
+    /// The attributes of every cluster.
+    pub fn cluster_attrs(&self) -> Attrs<'static> {
+        let mut attrs = Attrs::new();
+        if let Some(fill) = &self.cluster_fill {
+            attrs.set(Attr::Style(Style::Filled.into()));
+            attrs.set(Attr::FillColor(fill.clone()));
+        }
+        if let Some(border) = &self.cluster_border {
+            attrs.set(Attr::Color(border.clone()));
+        }
+        attrs
+    }
+
+    /// The default attributes of the nodes within a cluster at nesting
+    /// level `depth`, where top-level clusters are at level 1.
+    pub fn cluster_node_attrs(&self, depth: usize) -> Attrs<'static> {
+        let mut attrs = Attrs::new();
+        if self.node_fills.len() > 1 {
+            // The first color is for nodes outside clusters.
+            let fill = &self.node_fills[1 + depth.saturating_sub(1) % (self.node_fills.len() - 1)];
+            attrs.set(Attr::FillColor(fill.clone()));
+        }
+        attrs
+    }
+}
@@ -51,8 +51,14 @@ impl RankDir {
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub enum IdError {
     EmptyName,
-    InvalidStartChar(char),
     InvalidChar(char),
+    /// The name is one of the DOT keywords, which must be quoted.
+    Keyword(&'static str),
+    /// The content of an HTML `Id` has unbalanced angle brackets.
+    UnbalancedHtml,
+    /// A quoted `Id` has an odd run of backslashes before a `"`, a line
+    /// break or its end, which DOT would read as an escape.
+    DanglingBackslash,
 }
 
 impl std::error::Error for IdError {}
@@ -61,8 +67,15 @@ impl std::fmt::Display for IdError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             IdError::EmptyName => write!(f, "Id cannot be empty"),
-            IdError::InvalidStartChar(c) => write!(f, "Id cannot begin with '{c}'"),
-            IdError::InvalidChar(c) => write!(f, "Id cannot contain '{c}'"),
+            IdError::InvalidChar(c) => write!(f, "Id cannot contain {c:?}"),
+            IdError::Keyword(kw) => write!(f, "Id cannot be the keyword '{kw}'"),
+            IdError::UnbalancedHtml => write!(f, "HTML Id has unbalanced angle brackets"),
+            IdError::DanglingBackslash => {
+                write!(
+                    f,
+                    "quoted Id has a backslash that would escape what follows"
+                )
+            }
         }
     }
 }
@@ -100,9 +113,115 @@ impl std::fmt::Display for IdError {
 // So in the end I decided to use the third approach described above.
 
 /// `Id` is a Graphviz `ID`.
-#[derive(Clone, Hash, PartialEq, Eq)]
+///
+/// An `Id` remembers which of the DOT forms it is written in, see
+/// [`IdKind`]. Two `Id`s are equal if they have the same name, regardless
+/// of whether they are quoted, but an HTML `Id` is never equal to a
+/// non-HTML one.
+///
+/// `Id` derefs to its name, without any quotes or escaping; use its
+/// `Display` implementation to write it in DOT syntax.
+#[derive(Clone, Debug)]
 pub struct Id<'a> {
     pub(crate) name: Cow<'a, str>,
+    pub(crate) kind: IdKind,
+}
+
+/// The form of a DOT [`Id`].
+///
+/// See <https://www.graphviz.org/doc/info/lang.html#ids>.
+#[derive(Copy, Clone, Hash, PartialEq, Eq, Debug)]
+pub enum IdKind {
+    /// Alphabetic characters, underscores or digits, not beginning with a
+    /// digit. Any non-ASCII character counts as alphabetic.
+    Ident,
+    /// A numeral such as `3`, `-.5` or `2.0`.
+    Numeral,
+    /// A double-quoted string.
+    Quoted,
+    /// An HTML string, delimited by `<` and `>`.
+    Html,
+}
+
+const KEYWORDS: [&str; 6] = ["strict", "graph", "digraph", "node", "edge", "subgraph"];
+
+pub(crate) fn is_ident_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_' || !c.is_ascii()
+}
+
+pub(crate) fn is_ident_char(c: char) -> bool {
+    is_ident_start(c) || c.is_ascii_digit()
+}
+
+/// Returns the keyword that `s` spells, ignoring case.
+pub(crate) fn keyword(s: &str) -> Option<&'static str> {
+    KEYWORDS.into_iter().find(|kw| kw.eq_ignore_ascii_case(s))
+}
+
+/// Returns the length of the numeral at the start of `s`, matching
+/// `-?(\.[0-9]+|[0-9]+(\.[0-9]*)?)`.
+pub(crate) fn numeral_len(s: &str) -> Option<usize> {
+    let bytes = s.as_bytes();
+    let digits = |from: usize| {
+        bytes[from.min(bytes.len())..]
+            .iter()
+            .take_while(|b| b.is_ascii_digit())
+            .count()
+    };
+    let mut len = usize::from(bytes.first() == Some(&b'-'));
+    let int = digits(len);
+    len += int;
+    let mut frac = 0;
+    if bytes.get(len) == Some(&b'.') {
+        frac = digits(len + 1);
+        len += 1 + frac;
+    }
+    (int > 0 || frac > 0).then_some(len)
+}
+
+/// Returns `true` if the angle brackets in `s` are balanced, as required
+/// for the content of an HTML string.
+pub(crate) fn is_balanced_html(s: &str) -> bool {
+    let mut depth = 0usize;
+    for c in s.chars() {
+        match c {
+            '<' => depth += 1,
+            '>' => match depth.checked_sub(1) {
+                Some(d) => depth = d,
+                None => return false,
+            },
+            _ => {}
+        }
+    }
+    depth == 0
+}
+
+/// Returns `true` if `s` has an odd run of backslashes before a `"`, a line
+/// break or the end of `s`. DOT reads `\\` as a pair and drops the backslash
+/// of `\"` and of a line continuation, so no quoted string reads as `s`.
+fn has_dangling_backslash(s: &str) -> bool {
+    let mut backslashes = 0;
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            backslashes += 1;
+            continue;
+        }
+        let escaped = match c {
+            '"' | '\n' => true,
+            '\r' => chars.peek() == Some(&'\n'),
+            _ => false,
+        };
+        if escaped && backslashes % 2 == 1 {
+            return true;
+        }
+        backslashes = 0;
+    }
+    backslashes % 2 == 1
+}
+
+fn is_ident(s: &str) -> bool {
+    s.starts_with(is_ident_start) && s.chars().all(is_ident_char)
 }
 
 impl<'a> std::ops::Deref for Id<'a> {
@@ -113,41 +232,123 @@ impl<'a> std::ops::Deref for Id<'a> {
     }
 }
 
+impl PartialEq for Id<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name && (self.kind == IdKind::Html) == (other.kind == IdKind::Html)
+    }
+}
+
+impl Eq for Id<'_> {}
+
+impl std::hash::Hash for Id<'_> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.name.hash(state);
+        (self.kind == IdKind::Html).hash(state);
+    }
+}
+
 impl<'a> Id<'a> {
     /// Creates an `Id` named `name`.
     ///
-    /// The caller must ensure that the input conforms to an
-    /// identifier format: it must be a non-empty string made up of
-    /// alphanumeric or underscore characters, not beginning with a
-    /// digit (i.e., the regular expression `[a-zA-Z_][a-zA-Z_0-9]*`).
-    ///
-    /// (Note: this format is a strict subset of the `ID` format
-    /// defined by the DOT language. This function may change in the
-    /// future to accept a broader subset, or the entirety, of DOT's
-    /// `ID` format.)
+    /// Any non-empty string is accepted, except for the DOT keywords
+    /// (`strict`, `graph`, `digraph`, `node`, `edge` and `subgraph`, in any
+    /// case) and strings containing NUL characters. The form is chosen from
+    /// the content: identifiers such as `N0` and numerals such as `3.14` are
+    /// written as they are, and anything else, such as `my-crate` or
+    /// `std::vec::Vec`, is written as a quoted string.
     ///
-    /// Passing an invalid string (containing spaces, brackets,
-    /// quotes, ...) will return an empty `Err` value.
+    /// Use [`Id::quoted`] to allow keywords or the empty string, and
+    /// [`Id::html`] for HTML strings.
     pub fn new(name: impl Into<Cow<'a, str>>) -> Result<Id<'a>, IdError> {
         let name = name.into();
-        match name.chars().next() {
-            Some(c) if c.is_ascii_alphabetic() || c == '_' => {}
-            Some(c) => return Err(IdError::InvalidStartChar(c)),
-            None => return Err(IdError::EmptyName),
+        if name.is_empty() {
+            return Err(IdError::EmptyName);
         }
-        if let Some(c) = name
-            .chars()
-            .find(|c| !(c.is_ascii_alphanumeric() || *c == '_'))
-        {
-            return Err(IdError::InvalidChar(c));
+        if let Some(kw) = keyword(&name) {
+            return Err(IdError::Keyword(kw));
         }
+        let kind = if is_ident(&name) {
+            IdKind::Ident
+        } else if numeral_len(&name) == Some(name.len()) {
+            IdKind::Numeral
+        } else {
+            IdKind::Quoted
+        };
+        Id::with_kind(name, kind)
+    }
 
-        Ok(Id { name })
+    /// Creates an `Id` that is always written as a quoted string.
+    ///
+    /// DOT has no way to write an odd run of backslashes before a `"`, a
+    /// line break or the end of a quoted string, so such names are rejected
+    /// with [`IdError::DanglingBackslash`].
+    pub fn quoted(name: impl Into<Cow<'a, str>>) -> Result<Id<'a>, IdError> {
+        Id::with_kind(name.into(), IdKind::Quoted)
+    }
+
+    /// Creates an HTML `Id`, which is written between `<` and `>`.
+    ///
+    /// The content is not escaped, but its angle brackets must be
+    /// balanced.
+    pub fn html(content: impl Into<Cow<'a, str>>) -> Result<Id<'a>, IdError> {
+        let content = content.into();
+        if !is_balanced_html(&content) {
+            return Err(IdError::UnbalancedHtml);
+        }
+        Id::with_kind(content, IdKind::Html)
+    }
+
+    fn with_kind(name: Cow<'a, str>, kind: IdKind) -> Result<Id<'a>, IdError> {
+        if name.contains('\0') {
+            return Err(IdError::InvalidChar('\0'));
+        }
+        if kind == IdKind::Quoted && has_dangling_backslash(&name) {
+            return Err(IdError::DanglingBackslash);
+        }
+        Ok(Id { name, kind })
+    }
+
+    /// The form in which this `Id` is written.
+    pub fn kind(&self) -> IdKind {
+        self.kind
+    }
+
+    /// Converts the `Id` into one that owns its name.
+    pub fn into_owned(self) -> Id<'static> {
+        Id {
+            name: Cow::Owned(self.name.into_owned()),
+            kind: self.kind,
+        }
+    }
+}
+
+impl std::fmt::Display for Id<'_> {
+    /// Writes the `Id` in DOT syntax, quoting and escaping it as needed.
+    ///
+    /// Within quotes, `"` is escaped and other characters are written as
+    /// they are, since DOT does not interpret other escapes in `ID`s. Names
+    /// that no quoted string reads back as, such as `a\`, are rejected by
+    /// [`Id::quoted`].
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.kind {
+            IdKind::Ident | IdKind::Numeral => f.write_str(&self.name),
+            IdKind::Html => write!(f, "<{}>", self.name),
+            IdKind::Quoted => {
+                f.write_str("\"")?;
+                let mut rest = &self.name[..];
+                while let Some(i) = rest.find('"') {
+                    write!(f, "{}\\\"", &rest[..i])?;
+                    rest = &rest[i + 1..];
+                }
+                f.write_str(rest)?;
+                f.write_str("\"")
+            }
+        }
     }
 }
 
 /// The text for a graphviz label on a node or edge.
-#[derive(Clone, Hash, PartialEq, Eq)]
+#[derive(Clone, Hash, PartialEq, Eq, Debug)]
 pub enum Text<'a> {
     /// This kind of label preserves the text directly as is.
     ///
@@ -192,30 +393,107 @@ impl<'a> Text<'a> {
     /// This includes quotes or suitable delimiters.
     pub fn to_escaped_string(&self) -> String {
         match self {
-            Self::Label(s) => format!("\"{}\"", s.escape_default()),
+            Self::Label(s) => format!("\"{}\"", Text::escape_str(&s.replace('\\', "\\\\"))),
             Self::Esc(s) => format!("\"{}\"", Text::escape_str(s)),
             Self::Html(s) => format!("<{s}>"),
         }
     }
 
+    /// Returns `true` if the text is the empty string.
+    pub fn is_empty(&self) -> bool {
+        match self {
+            Text::Label(s) | Text::Esc(s) | Text::Html(s) => s.is_empty(),
+        }
+    }
+
     pub fn into_inner(self) -> Cow<'a, str> {
         match self {
             Text::Label(s) | Text::Esc(s) | Text::Html(s) => s,
         }
     }
 
+    /// Converts the text into one that owns its content.
+    pub fn into_owned(self) -> Text<'static> {
+        match self {
+            Text::Label(s) => Text::Label(Cow::Owned(s.into_owned())),
+            Text::Esc(s) => Text::Esc(Cow::Owned(s.into_owned())),
+            Text::Html(s) => Text::Html(Cow::Owned(s.into_owned())),
+        }
+    }
+
+    /// The lines of the text for output formats other than DOT, with `\N`
+    /// and the like in an escString replaced by `name`, and only the text of
+    /// an HTML-like label.
+    pub(crate) fn plain_lines(&self, name: &str) -> Vec<String> {
+        let mut lines = vec![String::new()];
+        match self {
+            Text::Label(s) => lines = s.lines().map(str::to_owned).collect(),
+            Text::Esc(s) => {
+                let mut chars = s.chars();
+                while let Some(c) = chars.next() {
+                    let line = lines.last_mut().unwrap();
+                    match c {
+                        '\\' => match chars.next() {
+                            Some('n' | 'l' | 'r') => lines.push(String::new()),
+                            Some('N' | 'G' | 'E' | 'T' | 'H' | 'L') => line.push_str(name),
+                            Some(c) => line.push(c),
+                            None => {}
+                        },
+                        '\n' => lines.push(String::new()),
+                        c => line.push(c),
+                    }
+                }
+            }
+            Text::Html(s) => {
+                let mut rest = &s[..];
+                while let Some(start) = rest.find('<') {
+                    lines
+                        .last_mut()
+                        .unwrap()
+                        .push_str(&decode_entities(&rest[..start]));
+                    let end = rest[start..]
+                        .find('>')
+                        .map_or(rest.len(), |end| start + end + 1);
+                    let tag = rest[start + 1..end]
+                        .trim_end_matches('>')
+                        .to_ascii_lowercase();
+                    let name = tag.trim_end_matches('/').split_whitespace().next();
+                    match name {
+                        Some("br" | "/tr") => lines.push(String::new()),
+                        Some("/td") => lines.last_mut().unwrap().push(' '),
+                        _ => {}
+                    }
+                    rest = &rest[end..];
+                }
+                lines.last_mut().unwrap().push_str(&decode_entities(rest));
+                for line in &mut lines {
+                    *line = line.split_whitespace().collect::<Vec<_>>().join(" ");
+                }
+            }
+        }
+        // A line break at the end only ends the last line.
+        while lines.len() > 1 && lines.last().is_some_and(String::is_empty) {
+            lines.pop();
+        }
+        lines
+    }
+
     pub(crate) fn escape_char(c: char, mut f: impl FnMut(char)) {
         match c {
             // not escaping \\, since Graphviz escString needs to
             // interpret backslashes; see EscStr above.
-            '\\' => f(c),
-            _ => {
-                for c in c.escape_default() {
-                    f(c)
-                }
+            '"' => {
+                f('\\');
+                f('"');
+            }
+            '\n' => {
+                f('\\');
+                f('n');
             }
+            _ => f(c),
         }
     }
+
     pub(crate) fn escape_str(s: &str) -> String {
         let mut out = String::with_capacity(s.len());
         for c in s.chars() {
@@ -225,6 +503,45 @@ impl<'a> Text<'a> {
     }
 }
 
+/// Replaces the XML entities in `s` by the characters they stand for.
+pub(crate) fn decode_entities(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let entity = rest.find(';').map(|end| (&rest[1..end], end));
+        let c = entity.and_then(|(entity, _)| match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some(' '),
+            _ => match entity.strip_prefix("#x").or(entity.strip_prefix("#X")) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok().and_then(char::from_u32),
+                None => entity
+                    .strip_prefix('#')?
+                    .parse()
+                    .ok()
+                    .and_then(char::from_u32),
+            },
+        });
+        match (c, entity) {
+            (Some(c), Some((_, end))) => {
+                out.push(c);
+                rest = &rest[end + 1..];
+            }
+            _ => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
 /// The style for a node or edge.
 /// See <https://www.graphviz.org/docs/attr-types/style/> for descriptions.
 /// Note that some of these are not valid for edges.
@@ -259,8 +576,184 @@ impl Style {
     }
 }
 
+/// A list of styles for a node, edge or subgraph, such as `filled,rounded`
+/// or `dashed,setlinewidth(2)`.
+/// See <https://www.graphviz.org/docs/attr-types/style/>.
+///
+/// Each style is listed at most once, in the order it was added, and
+/// [`Style::None`] is left out.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Styles {
+    styles: Vec<Style>,
+    line_width: Option<f64>,
+}
+
+impl Styles {
+    /// An empty list, which sets no style.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `style` to the list.
+    pub fn with(mut self, style: Style) -> Self {
+        self.insert(style);
+        self
+    }
+
+    /// Adds `style` to the list, returning `false` if it was already there
+    /// or is [`Style::None`].
+    pub fn insert(&mut self, style: Style) -> bool {
+        if style == Style::None || self.contains(style) {
+            return false;
+        }
+        self.styles.push(style);
+        true
+    }
+
+    /// Adds the parameterized style `setlinewidth(width)`, which sets the
+    /// line width in points. Newer versions of Graphviz prefer the
+    /// `penwidth` attribute.
+    pub fn with_line_width(self, width: f64) -> Self {
+        Styles {
+            line_width: Some(width),
+            ..self
+        }
+    }
+
+    pub fn contains(&self, style: Style) -> bool {
+        self.styles.contains(&style)
+    }
+
+    /// The styles, in the order they were added.
+    pub fn iter(&self) -> impl Iterator<Item = Style> + '_ {
+        self.styles.iter().copied()
+    }
+
+    /// The width given by `setlinewidth`, if any.
+    pub fn line_width(&self) -> Option<f64> {
+        self.line_width
+    }
+
+    /// Returns `true` if the list sets no style at all.
+    pub fn is_empty(&self) -> bool {
+        self.styles.is_empty() && self.line_width.is_none()
+    }
+}
+
+impl From<Style> for Styles {
+    fn from(style: Style) -> Self {
+        Styles::new().with(style)
+    }
+}
+
+impl<const N: usize> From<[Style; N]> for Styles {
+    fn from(styles: [Style; N]) -> Self {
+        styles.into_iter().collect()
+    }
+}
+
+impl FromIterator<Style> for Styles {
+    fn from_iter<I: IntoIterator<Item = Style>>(iter: I) -> Self {
+        let mut styles = Styles::new();
+        for style in iter {
+            styles.insert(style);
+        }
+        styles
+    }
+}
+
+impl std::fmt::Display for Styles {
+    /// Writes the styles separated by commas, without quotes.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut first = true;
+        let mut sep = |f: &mut std::fmt::Formatter<'_>| match std::mem::take(&mut first) {
+            true => Ok(()),
+            false => f.write_str(","),
+        };
+        for style in &self.styles {
+            sep(f)?;
+            f.write_str(style.as_static_str())?;
+        }
+        if let Some(width) = self.line_width {
+            sep(f)?;
+            write!(f, "setlinewidth({width})")?;
+        }
+        Ok(())
+    }
+}
+
+/// Error returned when parsing [`Styles`] from a Graphviz `style` string.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum StyleError {
+    UnknownStyle(String),
+    /// A `setlinewidth` style without a single number as its argument.
+    InvalidLineWidth(String),
+}
+
+impl std::error::Error for StyleError {}
+
+impl std::fmt::Display for StyleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StyleError::UnknownStyle(s) => write!(f, "Unknown style '{s}'"),
+            StyleError::InvalidLineWidth(s) => write!(f, "Invalid line width style '{s}'"),
+        }
+    }
+}
+
+impl std::str::FromStr for Styles {
+    type Err = StyleError;
+
+    /// Parses a [`style`](https://graphviz.org/docs/attr-types/style/) list,
+    /// such as `"filled, rounded"` or `"dashed,setlinewidth(2)"`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        const STYLES: [Style; 9] = [
+            Style::Solid,
+            Style::Dashed,
+            Style::Dotted,
+            Style::Bold,
+            Style::Rounded,
+            Style::Diagonals,
+            Style::Filled,
+            Style::Striped,
+            Style::Wedged,
+        ];
+        let mut styles = Styles::new();
+        // Commas also separate the arguments of parameterized styles, so
+        // split on the ones outside of parentheses.
+        let mut depth = 0;
+        let items = s.split(|c| {
+            match c {
+                '(' => depth += 1,
+                ')' => depth -= 1,
+                _ => {}
+            }
+            c == ',' && depth == 0
+        });
+        for item in items.map(str::trim).filter(|item| !item.is_empty()) {
+            if let Some(args) = item
+                .strip_prefix("setlinewidth")
+                .map(str::trim_start)
+                .and_then(|rest| rest.strip_prefix('('))
+                .and_then(|rest| rest.strip_suffix(')'))
+            {
+                let width = args.trim().parse().ok().filter(|w: &f64| w.is_finite());
+                let width = width.ok_or_else(|| StyleError::InvalidLineWidth(item.to_owned()))?;
+                styles = styles.with_line_width(width);
+                continue;
+            }
+            let style = STYLES
+                .into_iter()
+                .find(|style| style.as_static_str().eq_ignore_ascii_case(item))
+                .ok_or_else(|| StyleError::UnknownStyle(item.to_owned()))?;
+            styles.insert(style);
+        }
+        Ok(styles)
+    }
+}
+
 /// Arrow modifier that determines if the shape is empty or filled.
-#[derive(Clone, Copy, Hash, PartialEq, Eq)]
+#[derive(Clone, Copy, Hash, PartialEq, Eq, Debug)]
 pub enum ShapeFill {
     Open,
     Filled,
@@ -277,7 +770,7 @@ impl ShapeFill {
 
 /// Arrow modifier that determines if the shape is clipped.
 /// For example `Side::Left` means only left side is visible.
-#[derive(Clone, Copy, Hash, PartialEq, Eq)]
+#[derive(Clone, Copy, Hash, PartialEq, Eq, Debug)]
 pub enum Side {
     Left,
     Right,
@@ -296,7 +789,7 @@ impl Side {
 
 /// This enumeration represents all possible arrow edge
 /// as defined in [grapviz documentation](http://www.graphviz.org/content/arrow-shapes).
-#[derive(Clone, Copy, Hash, PartialEq, Eq)]
+#[derive(Clone, Copy, Hash, PartialEq, Eq, Debug)]
 pub enum ArrowVertex {
     /// No arrow will be displayed
     None,
@@ -425,7 +918,7 @@ impl ArrowVertex {
 /// either start or end of an edge.
 ///
 /// <https://graphviz.org/doc/info/arrows.html>
-#[derive(Clone, Hash, PartialEq, Eq)]
+#[derive(Clone, Hash, PartialEq, Eq, Debug)]
 pub struct Arrow {
     pub arrows: Vec<ArrowVertex>,
 }
@@ -491,8 +984,107 @@ macro_rules! impl_arrow_from_vertex_array {
 
 impl_arrow_from_vertex_array!(1 2 3 4);
 
+/// Error returned when parsing an [`Arrow`] from a Graphviz `arrowType` string.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ArrowError {
+    Empty,
+    UnknownShape(String),
+    InvalidModifier(String),
+    TooManyShapes,
+}
+
+impl std::error::Error for ArrowError {}
+
+impl std::fmt::Display for ArrowError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ArrowError::Empty => write!(f, "Arrow cannot be empty"),
+            ArrowError::UnknownShape(s) => write!(f, "Unknown arrow shape '{s}'"),
+            ArrowError::InvalidModifier(s) => write!(f, "Arrow shape '{s}' has invalid modifiers"),
+            ArrowError::TooManyShapes => write!(f, "Arrow cannot have more than four shapes"),
+        }
+    }
+}
+
+impl std::str::FromStr for Arrow {
+    type Err = ArrowError;
+
+    /// Parses an [`arrowType`](https://graphviz.org/docs/attr-types/arrowType/),
+    /// such as `"normal"`, `"olbox"` or `"invodot"`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        const SHAPES: [&str; 11] = [
+            "normal", "inv", "dot", "box", "crow", "curve", "icurve", "diamond", "tee", "vee",
+            "none",
+        ];
+        // Legacy names that only make sense as a whole arrow.
+        let legacy = match s {
+            "ediamond" => Some(ArrowVertex::Diamond(ShapeFill::Open, Side::Both)),
+            "open" => Some(ArrowVertex::vee()),
+            "halfopen" => Some(ArrowVertex::Vee(Side::Left)),
+            "empty" => Some(ArrowVertex::Normal(ShapeFill::Open, Side::Both)),
+            "invempty" => Some(ArrowVertex::Inv(ShapeFill::Open, Side::Both)),
+            _ => None,
+        };
+        if let Some(vertex) = legacy {
+            return Ok(Arrow::from(vertex));
+        }
+        if s.is_empty() {
+            return Err(ArrowError::Empty);
+        }
+
+        let mut arrows = Vec::new();
+        let mut rest = s;
+        while !rest.is_empty() {
+            let start = rest;
+            let fill = match rest.strip_prefix('o') {
+                Some(r) => {
+                    rest = r;
+                    ShapeFill::Open
+                }
+                None => ShapeFill::Filled,
+            };
+            let side = if let Some(r) = rest.strip_prefix('l') {
+                rest = r;
+                Side::Left
+            } else if let Some(r) = rest.strip_prefix('r') {
+                rest = r;
+                Side::Right
+            } else {
+                Side::Both
+            };
+            let Some(name) = SHAPES.iter().find(|name| rest.starts_with(*name)) else {
+                return Err(ArrowError::UnknownShape(start.to_owned()));
+            };
+            rest = &rest[name.len()..];
+            let shape = &start[..start.len() - rest.len()];
+
+            let open = fill == ShapeFill::Open;
+            let sided = side != Side::Both;
+            let vertex = match *name {
+                "normal" => ArrowVertex::Normal(fill, side),
+                "inv" => ArrowVertex::Inv(fill, side),
+                "box" => ArrowVertex::Box(fill, side),
+                "icurve" => ArrowVertex::ICurve(fill, side),
+                "diamond" => ArrowVertex::Diamond(fill, side),
+                "dot" if !sided => ArrowVertex::Dot(fill),
+                "crow" if !open => ArrowVertex::Crow(side),
+                "curve" if !open => ArrowVertex::Curve(side),
+                "tee" if !open => ArrowVertex::Tee(side),
+                "vee" if !open => ArrowVertex::Vee(side),
+                "none" if !open && !sided => ArrowVertex::None,
+                _ => return Err(ArrowError::InvalidModifier(shape.to_owned())),
+            };
+            if arrows.len() == 4 {
+                return Err(ArrowError::TooManyShapes);
+            }
+            arrows.push(vertex);
+        }
+        Ok(Arrow { arrows })
+    }
+}
+
 /// <https://graphviz.org/docs/attr-types/portPos/>
-#[derive(Clone, Copy, Hash, PartialEq, Eq)]
+#[derive(Clone, Copy, Hash, PartialEq, Eq, Debug)]
 pub enum CompassPoint {
     North,
     NorthEast,
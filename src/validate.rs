@@ -0,0 +1,513 @@
+//! Checks for problems in a graph before it is rendered.
+//!
+//! [`validate`] walks a [`Labeller`] + [`GraphWalk`] implementation and
+//! collects every [`Diagnostic`] it finds, rather than stopping at the first
+//! one as [`render`](crate::render()) does. Some of the problems, such as
+//! styled subgraphs that are not clusters, do not prevent rendering but
+//! probably do not have the intended effect.
+
+use std::collections::{HashMap, HashSet};
+use std::convert::Infallible;
+
+use crate::html::{self, HtmlError};
+use crate::render::{resolve_node_attrs, walk_subgraph, SubgraphVisitor};
+use crate::{Attr, Attrs, GraphWalk, Id, Labeller, RenderOptions, Shape, Text};
+
+/// A problem found by [`validate`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum Diagnostic {
+    /// The edge `edge` (such as `"a -> b"`) has an endpoint that is not
+    /// among the nodes of the graph.
+    UnknownNode { edge: String, node: Id<'static> },
+    /// Several nodes of the graph have the same id.
+    DuplicateNode(Id<'static>),
+    /// Several subgraphs have the same id.
+    DuplicateSubgraph(Id<'static>),
+    /// `node` is listed in `subgraphs`, neither of which is nested in the
+    /// other, so Graphviz can only draw it in one of them.
+    NodeInSeveralSubgraphs {
+        node: Id<'static>,
+        subgraphs: [String; 2],
+    },
+    /// The subgraph has a style, color or label, which Graphviz only draws
+    /// for clusters, but its id does not start with `cluster`.
+    NotACluster { subgraph: String },
+    /// The edge `edge` refers to `port` on `node`, but the node's label does
+    /// not define such a port.
+    UndefinedPort {
+        edge: String,
+        node: Id<'static>,
+        port: Id<'static>,
+    },
+    /// An arrow of the edge `edge` has more than four shapes.
+    TooManyArrowShapes { edge: String, shapes: usize },
+    /// The attribute `attr` of `element` has a value, `value` as written in
+    /// DOT, that Graphviz does not accept, as explained by `reason`.
+    InvalidAttribute {
+        element: String,
+        attr: String,
+        value: String,
+        reason: &'static str,
+    },
+    /// The attribute `attr` of `element` is an HTML-like label that does not
+    /// follow the Graphviz grammar, as explained by `error`.
+    InvalidHtml {
+        element: String,
+        attr: String,
+        error: HtmlError,
+    },
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Diagnostic::UnknownNode { edge, node } => {
+                write!(f, "Edge {edge} refers to unknown node {node}")
+            }
+            Diagnostic::DuplicateNode(id) => write!(f, "Node {id} is declared more than once"),
+            Diagnostic::DuplicateSubgraph(id) => {
+                write!(f, "Subgraph {id} is declared more than once")
+            }
+            Diagnostic::NodeInSeveralSubgraphs {
+                node,
+                subgraphs: [a, b],
+            } => write!(f, "Node {node} is in both {a} and {b}"),
+            Diagnostic::NotACluster { subgraph } => write!(
+                f,
+                "{subgraph} is styled like a cluster, but its id does not start with \"cluster\""
+            ),
+            Diagnostic::UndefinedPort { edge, node, port } => {
+                write!(
+                    f,
+                    "Edge {edge} refers to port {port}, which node {node} does not define"
+                )
+            }
+            Diagnostic::TooManyArrowShapes { edge, shapes } => {
+                write!(
+                    f,
+                    "Edge {edge} has an arrow with {shapes} shapes, but at most four are allowed"
+                )
+            }
+            Diagnostic::InvalidAttribute {
+                element,
+                attr,
+                value,
+                reason,
+            } => write!(f, "Attribute {attr}={value} of {element} {reason}"),
+            Diagnostic::InvalidHtml {
+                element,
+                attr,
+                error,
+            } => write!(
+                f,
+                "Attribute {attr} of {element} is not valid HTML: {error}"
+            ),
+        }
+    }
+}
+
+/// Attributes that only have an effect on clusters.
+const CLUSTER_ATTRS: [&str; 7] = [
+    "bgcolor",
+    "color",
+    "fillcolor",
+    "pencolor",
+    "penwidth",
+    "peripheries",
+    "style",
+];
+
+/// Checks `g` for problems, returning all of them in the order they were
+/// found. An empty list means that the graph is fine.
+pub fn validate<'a, N, E, S, G>(g: &'a G) -> Vec<Diagnostic>
+where
+    N: Clone + 'a,
+    E: Clone + 'a,
+    S: Clone + 'a,
+    G: Labeller<'a, Node = N, Edge = E, Subgraph = S>
+        + GraphWalk<'a, Node = N, Edge = E, Subgraph = S>,
+{
+    let mut diagnostics = Vec::new();
+    check_attrs(&mut diagnostics, || "the graph".to_owned(), g.graph_attrs());
+    check_attrs(
+        &mut diagnostics,
+        || "the node defaults".to_owned(),
+        g.node_defaults(),
+    );
+    check_attrs(
+        &mut diagnostics,
+        || "the edge defaults".to_owned(),
+        g.edge_defaults(),
+    );
+    for (selector, attrs) in g.stylesheet().rules() {
+        check_attrs(
+            &mut diagnostics,
+            || format!("the stylesheet rule {selector}"),
+            attrs.clone(),
+        );
+    }
+
+    // The ports of a node depend on its label and shape, which may come
+    // from the defaults of the subgraph it is rendered in.
+    let graph_defaults = g.node_defaults();
+    let mut node_defaults = NodeDefaults {
+        graph: g,
+        defaults: HashMap::new(),
+    };
+    let edges = g.edges();
+    for s in g.subgraphs().iter() {
+        let walked = walk_subgraph(
+            g,
+            &mut node_defaults,
+            s,
+            0,
+            &edges,
+            &graph_defaults,
+            &Attrs::new(),
+        );
+        if let Err(never) = walked {
+            match never {}
+        }
+    }
+
+    let stylesheet = g.stylesheet();
+    let options = RenderOptions::default();
+    let mut ports = HashMap::new();
+    for n in g.nodes().iter() {
+        let id = g.node_id(n);
+        let attrs = g.node_attrs(n);
+        check_attrs(&mut diagnostics, || format!("node {id}"), attrs.clone());
+        if attrs.get("label").is_none() {
+            check_label(&mut diagnostics, || format!("node {id}"), &g.node_label(n));
+        }
+
+        let mut resolved = match node_defaults.defaults.get(&id) {
+            Some(defaults) => defaults.clone(),
+            None => graph_defaults.clone(),
+        };
+        resolved.extend(resolve_node_attrs(g, &stylesheet, n, &options));
+        let label = match resolved.get("label") {
+            Some(Attr::Label(label)) => label.clone(),
+            _ => g.node_label(n),
+        };
+        let shape = match resolved.get("shape") {
+            Some(Attr::Shape(shape)) => Some(shape),
+            _ => None,
+        };
+        let defined = label_ports(&label, shape);
+        if ports.insert(id.clone(), defined).is_some() {
+            diagnostics.push(Diagnostic::DuplicateNode(id.into_owned()));
+        }
+    }
+
+    for e in g.edges().iter() {
+        let source = g.node_id(&g.source(e));
+        let target = g.node_id(&g.target(e));
+        let edge = format!("{source} {} {target}", g.kind().as_edge_op());
+
+        let endpoints = [
+            (&source, g.edge_start_port(e)),
+            (&target, g.edge_end_port(e)),
+        ];
+        for (node, port) in endpoints {
+            let Some(defined) = ports.get(node) else {
+                diagnostics.push(Diagnostic::UnknownNode {
+                    edge: edge.clone(),
+                    node: node.clone().into_owned(),
+                });
+                continue;
+            };
+            let Some(port) = port else { continue };
+            // A port that is not defined by the node, but is named like a
+            // compass point, is taken as that compass point.
+            if !defined.contains(&port[..]) && !is_compass_point(&port) {
+                diagnostics.push(Diagnostic::UndefinedPort {
+                    edge: edge.clone(),
+                    node: node.clone().into_owned(),
+                    port: port.into_owned(),
+                });
+            }
+        }
+
+        let attrs = g.edge_attrs(e);
+        if attrs.get("label").is_none() {
+            check_label(
+                &mut diagnostics,
+                || format!("edge {edge}"),
+                &g.edge_label(e),
+            );
+        }
+        let arrows = [g.edge_start_arrow(e), g.edge_end_arrow(e)];
+        let arrows = arrows
+            .iter()
+            .chain(attrs.iter().filter_map(|attr| match attr {
+                Attr::ArrowHead(arrow) | Attr::ArrowTail(arrow) => Some(arrow),
+                _ => None,
+            }));
+        for arrow in arrows {
+            if arrow.arrows.len() > 4 {
+                diagnostics.push(Diagnostic::TooManyArrowShapes {
+                    edge: edge.clone(),
+                    shapes: arrow.arrows.len(),
+                });
+            }
+        }
+        let attrs = attrs
+            .into_iter()
+            .filter(|attr| !matches!(attr, Attr::ArrowHead(_) | Attr::ArrowTail(_)));
+        check_attrs(&mut diagnostics, || format!("edge {edge}"), attrs.collect());
+    }
+
+    let mut subgraph_ids = HashSet::new();
+    let mut memberships: HashMap<Id<'a>, Vec<Vec<usize>>> = HashMap::new();
+    let mut names = Vec::new();
+    let mut stack: Vec<(S, Vec<usize>)> = g
+        .subgraphs()
+        .iter()
+        .rev()
+        .map(|s| (s.clone(), Vec::new()))
+        .collect();
+    while let Some((s, mut path)) = stack.pop() {
+        let id = g.subgraph_id(&s);
+        let name = match &id {
+            Some(id) => format!("subgraph {id}"),
+            None => "an anonymous subgraph".to_owned(),
+        };
+        path.push(names.len());
+        names.push(name.clone());
+
+        let attrs = g.subgraph_attrs(&s);
+        let styled = !g.subgraph_style(&s).is_empty()
+            || g.subgraph_color(&s).is_some()
+            || !g.subgraph_label(&s).is_empty()
+            || attrs
+                .iter()
+                .any(|attr| CLUSTER_ATTRS.contains(&attr.name()));
+        let is_cluster = id.as_ref().is_some_and(|id| id.starts_with("cluster"));
+        if styled && !is_cluster {
+            diagnostics.push(Diagnostic::NotACluster {
+                subgraph: name.clone(),
+            });
+        }
+        if attrs.get("label").is_none() {
+            check_label(&mut diagnostics, || name.clone(), &g.subgraph_label(&s));
+        }
+        check_attrs(&mut diagnostics, || name.clone(), attrs);
+        check_attrs(
+            &mut diagnostics,
+            || format!("the node defaults of {name}"),
+            g.subgraph_node_defaults(&s),
+        );
+        check_attrs(
+            &mut diagnostics,
+            || format!("the edge defaults of {name}"),
+            g.subgraph_edge_defaults(&s),
+        );
+
+        if let Some(id) = id {
+            if let Some(id) = subgraph_ids.replace(id) {
+                diagnostics.push(Diagnostic::DuplicateSubgraph(id.into_owned()));
+            }
+        }
+
+        for n in g.subgraph_nodes(&s).iter() {
+            memberships
+                .entry(g.node_id(n))
+                .or_default()
+                .push(path.clone());
+        }
+        let children = g.subgraph_children(&s);
+        stack.extend(children.iter().rev().map(|c| (c.clone(), path.clone())));
+    }
+
+    let mut nodes: Vec<_> = memberships.into_iter().collect();
+    nodes.sort_by(|(_, a), (_, b)| a.cmp(b));
+    for (node, paths) in nodes {
+        // Two subgraphs are nested if the path to one is a prefix of the
+        // path to the other.
+        let conflict = paths.iter().enumerate().find_map(|(i, a)| {
+            paths[i + 1..]
+                .iter()
+                .find(|b| !a.starts_with(b) && !b.starts_with(a))
+                .map(|b| (a, b))
+        });
+        if let Some((a, b)) = conflict {
+            let name = |path: &Vec<usize>| names[*path.last().unwrap()].clone();
+            diagnostics.push(Diagnostic::NodeInSeveralSubgraphs {
+                node: node.into_owned(),
+                subgraphs: [name(a), name(b)],
+            });
+        }
+    }
+
+    diagnostics
+}
+
+/// Collects the node defaults that each node is rendered with, which are
+/// those of the first subgraph that it is rendered in.
+struct NodeDefaults<'a, G> {
+    graph: &'a G,
+    defaults: HashMap<Id<'a>, Attrs<'a>>,
+}
+
+impl<'a, N, E, S, G> SubgraphVisitor<'a, N, E, S> for NodeDefaults<'a, G>
+where
+    G: Labeller<'a, Node = N, Edge = E, Subgraph = S>,
+{
+    type Error = Infallible;
+
+    fn enter(
+        &mut self,
+        _: &S,
+        depth: usize,
+        _: &Attrs<'a>,
+        _: &Attrs<'a>,
+    ) -> Result<usize, Infallible> {
+        Ok(depth + 1)
+    }
+
+    fn visit_node(&mut self, n: &N, _: usize, defaults: &Attrs<'a>) -> Result<(), Infallible> {
+        let id = self.graph.node_id(n);
+        self.defaults.entry(id).or_insert_with(|| defaults.clone());
+        Ok(())
+    }
+
+    fn visit_edge(&mut self, _: &E, _: usize, _: &Attrs<'a>) -> Result<(), Infallible> {
+        Ok(())
+    }
+
+    fn exit(&mut self, _: usize, _: usize) -> Result<(), Infallible> {
+        Ok(())
+    }
+}
+
+fn check_attrs(diagnostics: &mut Vec<Diagnostic>, element: impl Fn() -> String, attrs: Attrs<'_>) {
+    for attr in &attrs {
+        if let Err(reason) = attr.check() {
+            diagnostics.push(Diagnostic::InvalidAttribute {
+                element: element(),
+                attr: attr.name().to_owned(),
+                value: attr.value_to_string(),
+                reason,
+            });
+        } else if let Attr::Label(text)
+        | Attr::XLabel(text)
+        | Attr::HeadLabel(text)
+        | Attr::TailLabel(text) = attr
+        {
+            check_html(diagnostics, &element, attr.name(), text);
+        }
+    }
+}
+
+/// Checks a label given by a [`Labeller`] method rather than by an
+/// attribute.
+fn check_label(diagnostics: &mut Vec<Diagnostic>, element: impl Fn() -> String, label: &Text<'_>) {
+    check_html(diagnostics, &element, "label", label);
+}
+
+/// Checks `text`, the value of the attribute `attr`, if it is an HTML-like
+/// label.
+fn check_html(
+    diagnostics: &mut Vec<Diagnostic>,
+    element: &impl Fn() -> String,
+    attr: &str,
+    text: &Text<'_>,
+) {
+    if let Text::Html(html) = text {
+        if let Err(error) = html::check(html) {
+            diagnostics.push(Diagnostic::InvalidHtml {
+                element: element(),
+                attr: attr.to_owned(),
+                error,
+            });
+        }
+    }
+}
+
+/// Returns `true` if `port` is the name of a compass point, such as `ne`.
+fn is_compass_point(port: &str) -> bool {
+    matches!(
+        port,
+        "n" | "ne" | "e" | "se" | "s" | "sw" | "w" | "nw" | "c" | "_"
+    )
+}
+
+/// Returns the names of the ports defined by a node's label: the `PORT`
+/// attributes of an HTML label, or the `<port>` fields of a record label.
+fn label_ports(label: &Text<'_>, shape: Option<&Shape>) -> HashSet<String> {
+    match label {
+        // Ports are still looked for in labels that fail the check, which
+        // is reported on its own.
+        Text::Html(html) => match html::check(html) {
+            Ok(ports) => ports.into_iter().collect(),
+            Err(_) => html_ports(html),
+        },
+        Text::Label(s) | Text::Esc(s) => {
+            if shape.is_some_and(Shape::is_record) {
+                record_ports(s)
+            } else {
+                HashSet::new()
+            }
+        }
+    }
+}
+
+/// Finds the `<port>` fields of a record label.
+fn record_ports(label: &str) -> HashSet<String> {
+    let mut ports = HashSet::new();
+    let mut chars = label.chars();
+    let mut port = None::<String>;
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                let escaped = chars.next();
+                if let Some(port) = &mut port {
+                    port.extend(escaped);
+                }
+            }
+            '<' if port.is_none() => port = Some(String::new()),
+            '>' => {
+                if let Some(port) = port.take() {
+                    ports.insert(port.trim().to_owned());
+                }
+            }
+            c => {
+                if let Some(port) = &mut port {
+                    port.push(c);
+                }
+            }
+        }
+    }
+    ports
+}
+
+/// Finds the values of the `PORT` attributes in an HTML label that is not
+/// well-formed.
+fn html_ports(html: &str) -> HashSet<String> {
+    let mut ports = HashSet::new();
+    let lower = html.to_ascii_lowercase();
+    let mut rest = &lower[..];
+    let mut offset = 0;
+    while let Some(i) = rest.find("port") {
+        let after = offset + i + 4;
+        let before = html[..offset + i].chars().next_back();
+        offset = after;
+        rest = &lower[after..];
+        if before.is_some_and(|c| !c.is_whitespace()) {
+            continue;
+        }
+        let value = html[after..].trim_start();
+        let Some(value) = value.strip_prefix('=') else {
+            continue;
+        };
+        let value = value.trim_start();
+        let Some(quote) = value.chars().next().filter(|c| *c == '"' || *c == '\'') else {
+            continue;
+        };
+        if let Some(end) = value[1..].find(quote) {
+            ports.insert(value[1..1 + end].to_owned());
+        }
+    }
+    ports
+}